@@ -1,8 +1,19 @@
-use num::Float;
+use std::f64::consts::PI;
 
-use crate::tf::TransferFunction;
+use num::{Complex, Float};
+
+use crate::{
+    analysis::logspace,
+    error::ControlError,
+    tf::{TimeDomain, TransferFunction, mimo::TransferFunctionMatrix, zpk::ZeroPoleGain},
+    util::{
+        linalg::complex_singular_values,
+        poly::{Polynomial, mobius_substitution, trim_leading_zeros},
+    },
+};
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BodePoint<T: Float> {
     pub omega: T,
     pub mag_db: T,
@@ -21,9 +32,612 @@ pub fn bode_data<T: Float>(tf: &dyn TransferFunction<T>, omega: &[T]) -> Vec<Bod
         .collect()
 }
 
+/// Same as `bode_data`, with the phase taken from `unwrapped_phase` instead of the principal value
+pub fn unwrapped_bode_data<T: Float>(
+    tf: &dyn TransferFunction<T>,
+    omega: &[T],
+) -> Vec<BodePoint<T>> {
+    bode_data(tf, omega)
+        .into_iter()
+        .zip(unwrapped_phase(tf, omega))
+        .map(|(point, phase_rad)| BodePoint { phase_rad, ..point })
+        .collect()
+}
+
+/// Continuous phase in radians, summed factor by factor from the poles and zeroes so it has no
+/// jumps at +-pi and does not depend on the frequency grid. Each factor only jumps when its root
+/// lies on the imaginary axis (unit circle for discrete systems)
+pub fn unwrapped_phase<T: Float>(tf: &dyn TransferFunction<T>, omega: &[T]) -> Vec<T> {
+    let zpk = ZeroPoleGain::from_tf(tf);
+    let gain_phase = Complex::from(zpk.gain()).arg();
+    omega
+        .iter()
+        .map(|&w| {
+            let x = frequency_point(tf, w);
+            let zero_phase = zpk
+                .zeroes()
+                .iter()
+                .fold(T::zero(), |acc, &z| acc + (x - z).arg());
+            let pole_phase = zpk
+                .poles()
+                .iter()
+                .fold(T::zero(), |acc, &p| acc + (x - p).arg());
+            gain_phase + zero_phase - pole_phase - w * tf.delay()
+        })
+        .collect()
+}
+
+/// Group delay -dphi/dw in seconds, evaluated exactly from the polynomial derivatives.
+/// With H = N/D and x = jw (x = e^(jwT) for discrete systems) this is
+/// -Re(dx/dw / j * (N'(x)/N(x) - D'(x)/D(x))) plus the transport delay
+pub fn group_delay<T: Float>(tf: &dyn TransferFunction<T>, omega: &[T]) -> Vec<T> {
+    let numerator = Polynomial::new(tf.numerator());
+    let denominator = Polynomial::new(tf.denominator());
+    let (d_numerator, d_denominator) = (numerator.derivative(), denominator.derivative());
+    omega
+        .iter()
+        .map(|&w| {
+            let x = frequency_point(tf, w);
+            let log_derivative = d_numerator.eval_complex(x) / numerator.eval_complex(x)
+                - d_denominator.eval_complex(x) / denominator.eval_complex(x);
+            let scaled = match tf.time_domain() {
+                TimeDomain::Continous => log_derivative,
+                TimeDomain::Discrete { sample_time } => log_derivative * x * sample_time,
+            };
+            tf.delay() - scaled.re
+        })
+        .collect()
+}
+
+/// Phase delay -phi(w)/w in seconds from the unwrapped phase. At w = 0 the limit for a system
+/// with positive DC gain is returned, which equals the group delay there
+pub fn phase_delay<T: Float>(tf: &dyn TransferFunction<T>, omega: &[T]) -> Vec<T> {
+    unwrapped_phase(tf, omega)
+        .into_iter()
+        .zip(omega)
+        .map(|(phase, &w)| {
+            if w.is_zero() {
+                group_delay(tf, &[w])[0]
+            } else {
+                -phase / w
+            }
+        })
+        .collect()
+}
+
+/// Point at which the transfer function is evaluated for the frequency w in rad/s
+fn frequency_point<T: Float>(tf: &dyn TransferFunction<T>, w: T) -> Complex<T> {
+    match tf.time_domain() {
+        TimeDomain::Continous => Complex::new(T::zero(), w),
+        TimeDomain::Discrete { sample_time } => Complex::from_polar(T::one(), w * sample_time),
+    }
+}
+
+/// Classical stability margins of an open loop L, frequencies in rad/s. When L crosses more than
+/// once the crossover with the smallest margin is reported, `None` means there is no crossover
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Margins<T: Float> {
+    /// -20 log10|L| where the phase of L crosses -180 degrees
+    pub gain_margin_db: Option<T>,
+    pub phase_crossover: Option<T>,
+    /// 180 degrees plus the phase of L where |L| = 1, in (-pi, pi]
+    pub phase_margin_rad: Option<T>,
+    pub gain_crossover: Option<T>,
+    /// Smallest additional loop delay in seconds that puts a closed-loop pole on the stability boundary
+    pub delay_margin: Option<T>,
+    /// Smallest distance from L to -1, the inverse of the peak sensitivity
+    pub modulus_margin: T,
+    /// Infinite when the smallest distance is only approached as w grows
+    pub modulus_frequency: T,
+}
+
+/// Gain, phase, delay and modulus margins of the open loop `tf`.
+/// Crossovers are the positive real roots of |N|^2 - |D|^2 and Im(N conj(D)) on the imaginary
+/// axis, after mapping the unit circle to it with z = (1 + s) / (1 - s) for discrete systems.
+/// The phase of a transport delay is not polynomial, so with a delay the phase crossovers and the
+/// modulus margin are bracketed on a dense logarithmic grid and refined
+pub fn margins<T: Float>(tf: &dyn TransferFunction<T>) -> Result<Margins<T>, ControlError> {
+    let axis = AxisPolynomials::new(tf);
+    let tol = T::epsilon().cbrt();
+    let response = |w: T| tf.evaluate(frequency_point(tf, w));
+
+    let gain_crossovers: Vec<T> = axis
+        .frequencies(positive_axis_roots(&axis.gain_crossing())?)
+        .into_iter()
+        .filter(|&w| (response(w).norm() - T::one()).abs() <= tol)
+        .collect();
+    let (phase_candidates, mut modulus_candidates) = if tf.delay().is_zero() {
+        (
+            axis.frequencies(positive_axis_roots(&axis.phase_crossing())?),
+            axis.frequencies(positive_axis_roots(&axis.modulus_stationary())?),
+        )
+    } else {
+        let grid = corner_grid(tf, &gain_crossovers);
+        (
+            grid_phase_crossings(&response, &grid),
+            grid_modulus_minimum(&response, &grid),
+        )
+    };
+    let phase_crossovers = phase_candidates
+        .into_iter()
+        .chain(axis.endpoints())
+        .filter(|&w| {
+            let l = response(w);
+            l.norm().is_finite() && l.re < T::zero() && l.im.abs() <= tol * l.norm()
+        });
+    modulus_candidates.extend(axis.endpoints());
+
+    let gain_margin = phase_crossovers
+        .map(|w| (w, -T::from(20.0).unwrap() * response(w).norm().log10()))
+        .min_by(|a, b| a.1.abs().partial_cmp(&b.1.abs()).unwrap());
+    let pi = T::from(PI).unwrap();
+    let phase_margins: Vec<(T, T)> = gain_crossovers
+        .iter()
+        .map(|&w| {
+            let pm = response(w).arg() + pi;
+            (w, if pm > pi { pm - pi - pi } else { pm })
+        })
+        .collect();
+    let phase_margin = phase_margins
+        .iter()
+        .copied()
+        .min_by(|a, b| a.1.abs().partial_cmp(&b.1.abs()).unwrap());
+    // A negative phase margin has to be rotated almost a full turn by the delay
+    let delay_margin = phase_margins
+        .iter()
+        .map(|&(w, pm)| if pm < T::zero() { pm + pi + pi } else { pm } / w)
+        .min_by(|a, b| a.partial_cmp(b).unwrap());
+    let (modulus_frequency, modulus_margin) = modulus_candidates
+        .into_iter()
+        .map(|w| (w, (response(w) + T::one()).norm()))
+        .chain(axis.modulus_at_infinity(tf.delay()))
+        .filter(|(_, m)| m.is_finite())
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .unwrap_or((T::zero(), T::infinity()));
+
+    Ok(Margins {
+        gain_margin_db: gain_margin.map(|(_, gm)| gm),
+        phase_crossover: gain_margin.map(|(w, _)| w),
+        phase_margin_rad: phase_margin.map(|(_, pm)| pm),
+        gain_crossover: phase_margin.map(|(w, _)| w),
+        delay_margin,
+        modulus_margin,
+        modulus_frequency,
+    })
+}
+
+/// Real and imaginary parts of N(jv) and D(jv) as real polynomials in v. For discrete systems
+/// v = tan(wT/2) is the frequency on the imaginary axis of the bilinear w-plane
+struct AxisPolynomials<T: Float> {
+    num_re: Polynomial<T>,
+    num_im: Polynomial<T>,
+    den_re: Polynomial<T>,
+    den_im: Polynomial<T>,
+    sample_time: Option<T>,
+}
+
+impl<T: Float> AxisPolynomials<T> {
+    fn new(tf: &dyn TransferFunction<T>) -> Self {
+        let (numerator, denominator, sample_time) = match tf.time_domain() {
+            TimeDomain::Continous => (tf.numerator().to_vec(), tf.denominator().to_vec(), None),
+            TimeDomain::Discrete { sample_time } => {
+                let order = tf.numerator().len().max(tf.denominator().len()) - 1;
+                let mobius = (T::one(), T::one(), -T::one(), T::one());
+                (
+                    trim_leading_zeros(&mobius_substitution(tf.numerator(), order, mobius)),
+                    trim_leading_zeros(&mobius_substitution(tf.denominator(), order, mobius)),
+                    Some(sample_time),
+                )
+            }
+        };
+        let (num_re, num_im) = axis_parts(&numerator);
+        let (den_re, den_im) = axis_parts(&denominator);
+        Self {
+            num_re,
+            num_im,
+            den_re,
+            den_im,
+            sample_time,
+        }
+    }
+
+    // |N|^2 - |D|^2
+    fn gain_crossing(&self) -> Polynomial<T> {
+        &(&(&self.num_re * &self.num_re) + &(&self.num_im * &self.num_im))
+            - &(&(&self.den_re * &self.den_re) + &(&self.den_im * &self.den_im))
+    }
+
+    // Im(N conj(D))
+    fn phase_crossing(&self) -> Polynomial<T> {
+        &(&self.num_im * &self.den_re) - &(&self.num_re * &self.den_im)
+    }
+
+    // Stationary points of |N + D|^2 / |D|^2 = |1 + L|^2
+    fn modulus_stationary(&self) -> Polynomial<T> {
+        let sum_re = &self.num_re + &self.den_re;
+        let sum_im = &self.num_im + &self.den_im;
+        let f = &(&sum_re * &sum_re) + &(&sum_im * &sum_im);
+        let g = &(&self.den_re * &self.den_re) + &(&self.den_im * &self.den_im);
+        &(&f.derivative() * &g) - &(&f * &g.derivative())
+    }
+
+    /// Maps axis frequencies v back to w in rad/s
+    fn frequencies(&self, v: Vec<T>) -> Vec<T> {
+        match self.sample_time {
+            None => v,
+            Some(sample_time) => v
+                .into_iter()
+                .map(|v| (T::one() + T::one()) * v.atan() / sample_time)
+                .collect(),
+        }
+    }
+
+    /// Ends of the frequency range that are reached at a finite w
+    fn endpoints(&self) -> Vec<T> {
+        match self.sample_time {
+            None => vec![T::zero()],
+            Some(sample_time) => vec![T::zero(), T::from(PI).unwrap() / sample_time],
+        }
+    }
+
+    // |1 + L| as w -> inf, only defined for continuous loops without delay
+    fn modulus_at_infinity(&self, delay: T) -> Option<(T, T)> {
+        if self.sample_time.is_some() || !delay.is_zero() {
+            return None;
+        }
+        let limit = |re: &Polynomial<T>, im: &Polynomial<T>| {
+            if re.degree() >= im.degree() {
+                (re.leading_coefficient(), re.degree())
+            } else {
+                (im.leading_coefficient(), im.degree())
+            }
+        };
+        let (num, num_degree) = limit(&self.num_re, &self.num_im);
+        let (den, den_degree) = limit(&self.den_re, &self.den_im);
+        let l = match num_degree.cmp(&den_degree) {
+            std::cmp::Ordering::Less => T::zero(),
+            std::cmp::Ordering::Equal => num / den,
+            std::cmp::Ordering::Greater => return None,
+        };
+        Some((T::infinity(), (T::one() + l).abs()))
+    }
+}
+
+/// Splits P(jv) into real polynomials Re and Im in v, using j^k = 1, j, -1, -j
+fn axis_parts<T: Float>(coeffs: &[T]) -> (Polynomial<T>, Polynomial<T>) {
+    let degree = coeffs.len() - 1;
+    let mut re = vec![T::zero(); coeffs.len()];
+    let mut im = vec![T::zero(); coeffs.len()];
+    for (k, &c) in coeffs.iter().enumerate() {
+        match (degree - k) % 4 {
+            0 => re[k] = c,
+            1 => im[k] = c,
+            2 => re[k] = -c,
+            _ => im[k] = -c,
+        }
+    }
+    (Polynomial::new(&re), Polynomial::new(&im))
+}
+
+/// Positive real roots of a polynomial that is even or odd in v, as all the crossing polynomials
+/// are. Exact roots at zero are divided out and the rest is solved in u = v^2, which halves the
+/// degree, then polished with Newton steps
+fn positive_axis_roots<T: Float>(p: &Polynomial<T>) -> Result<Vec<T>, ControlError> {
+    let coeffs = p.coeffs();
+    let end = coeffs
+        .iter()
+        .rposition(|c| !c.is_zero())
+        .map_or(0, |k| k + 1);
+    let squared: Vec<T> = coeffs[..end]
+        .iter()
+        .rev()
+        .step_by(2)
+        .rev()
+        .copied()
+        .collect();
+    let p = Polynomial::new(&squared);
+    if p.degree() == 0 {
+        return Ok(Vec::new());
+    }
+    let dp = p.derivative();
+    let tol = T::epsilon().cbrt();
+    let mut found: Vec<T> = p
+        .roots()?
+        .into_iter()
+        .filter(|r| r.re > T::zero() && r.im.abs() <= tol * (T::one() + r.re))
+        .map(|r| {
+            let mut u = r.re;
+            for _ in 0..3 {
+                let step = p.eval(u) / dp.eval(u);
+                if !step.is_finite() || step.abs() > tol * u {
+                    break;
+                }
+                u = u - step;
+            }
+            u.sqrt()
+        })
+        .collect();
+    found.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    found.dedup_by(|a, b| (*a - *b).abs() <= tol * *b);
+    Ok(found)
+}
+
+// Log grid from two decades below the slowest to two above the fastest pole, zero, delay or
+// `extra` corner, with 200 points per decade. Roots of discrete systems count with the
+// frequency |ln z| / T of their continuous counterparts
+fn corner_grid<T: Float>(tf: &dyn TransferFunction<T>, extra: &[T]) -> Vec<T> {
+    let roots = tf.poles().iter().chain(tf.zeroes());
+    let corners: Vec<T> = match tf.time_domain() {
+        TimeDomain::Continous => roots.map(|r| r.norm()).collect(),
+        TimeDomain::Discrete { sample_time } => {
+            roots.map(|r| r.ln().norm() / sample_time).collect()
+        }
+    };
+    let (lo, hi) = corners
+        .into_iter()
+        .chain(extra.iter().copied())
+        .chain([tf.delay().recip()])
+        .filter(|&w| w > T::zero() && w.is_finite())
+        .fold((T::infinity(), T::zero()), |(lo, hi), w| {
+            (lo.min(w), hi.max(w))
+        });
+    let (lo, hi) = if lo.is_finite() {
+        (lo, hi)
+    } else {
+        (T::one(), T::one())
+    };
+    let hundred = T::from(100.0).unwrap();
+    let (start, stop) = ((lo / hundred).log10(), (hi * hundred).log10());
+    let points = ((stop - start) * T::from(200.0).unwrap())
+        .ceil()
+        .to_usize()
+        .unwrap()
+        + 1;
+    logspace(start, stop, points)
+}
+
+// Sign changes of Im(L) with Re(L) < 0, refined by bisection
+fn grid_phase_crossings<T: Float>(response: &dyn Fn(T) -> Complex<T>, grid: &[T]) -> Vec<T> {
+    grid.windows(2)
+        .filter_map(|pair| {
+            let (mut a, mut b) = (pair[0], pair[1]);
+            let (la, lb) = (response(a), response(b));
+            if (la.im < T::zero()) == (lb.im < T::zero())
+                || (la.re >= T::zero() && lb.re >= T::zero())
+            {
+                return None;
+            }
+            let sign_a = la.im < T::zero();
+            for _ in 0..60 {
+                let mid = (a + b) / (T::one() + T::one());
+                if (response(mid).im < T::zero()) == sign_a {
+                    a = mid;
+                } else {
+                    b = mid;
+                }
+            }
+            Some((a + b) / (T::one() + T::one()))
+        })
+        .collect()
+}
+
+// Grid minimum of |1 + L|, refined by golden-section search between its neighbours
+fn grid_modulus_minimum<T: Float>(response: &dyn Fn(T) -> Complex<T>, grid: &[T]) -> Vec<T> {
+    let distance = |w: T| (response(w) + T::one()).norm();
+    let Some(k) =
+        (0..grid.len()).min_by(|&i, &j| distance(grid[i]).partial_cmp(&distance(grid[j])).unwrap())
+    else {
+        return Vec::new();
+    };
+    let (mut a, mut b) = (grid[k.saturating_sub(1)], grid[(k + 1).min(grid.len() - 1)]);
+    let ratio = T::from((5f64.sqrt() - 1.0) / 2.0).unwrap();
+    for _ in 0..60 {
+        let c = b - ratio * (b - a);
+        let d = a + ratio * (b - a);
+        if distance(c) < distance(d) {
+            b = d;
+        } else {
+            a = c;
+        }
+    }
+    vec![(a + b) / (T::one() + T::one())]
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NyquistPoint<T: Float> {
+    /// Frequency of the contour point, the pole frequency along an indentation
+    pub omega: T,
+    pub re: T,
+    pub im: T,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NyquistData<T: Float> {
+    /// Image of the Nyquist contour from -w to w, the closing arc at infinity is left out
+    pub points: Vec<NyquistPoint<T>>,
+    /// Clockwise encirclements of -1
+    pub encirclements: i32,
+    /// Open-loop poles in the right half-plane, or outside the unit circle for discrete systems
+    pub open_loop_unstable_poles: usize,
+}
+
+impl<T: Float> NyquistData<T> {
+    /// Closed-loop poles under unity negative feedback that are unstable, Z = N + P
+    pub fn closed_loop_unstable_poles(&self) -> i32 {
+        self.encirclements + self.open_loop_unstable_poles as i32
+    }
+}
+
+/// Nyquist diagram of the open loop `tf` over the positive frequencies `omega` in ascending
+/// order, mirrored to negative frequencies. Discrete systems are cut at the Nyquist frequency.
+/// Poles on the imaginary axis (unit circle) are passed on a small semicircle into the unstable
+/// region, so they count as stable open-loop poles. `omega` is only used for the returned points,
+/// the encirclements are the winding number of 1 + L along a contour sampled densely around the
+/// pole, zero and delay corners of `tf`
+pub fn nyquist_data<T: Float>(tf: &dyn TransferFunction<T>, omega: &[T]) -> NyquistData<T> {
+    let tol = T::epsilon().sqrt();
+    let unstable = match tf.time_domain() {
+        TimeDomain::Continous => tf.poles().iter().filter(|p| p.re > tol).count(),
+        TimeDomain::Discrete { .. } => tf
+            .poles()
+            .iter()
+            .filter(|p| p.norm() > T::one() + tol)
+            .count(),
+    };
+    let counting = nyquist_contour(tf, &corner_grid(tf, &[]));
+    let winding = counting
+        .iter()
+        .zip(counting.iter().cycle().skip(1))
+        .fold(T::zero(), |acc, (&(_, a), &(_, b))| {
+            acc + ((b + T::one()) / (a + T::one())).arg()
+        });
+    NyquistData {
+        points: nyquist_contour(tf, omega)
+            .into_iter()
+            .map(|(omega, l)| NyquistPoint {
+                omega,
+                re: l.re,
+                im: l.im,
+            })
+            .collect(),
+        encirclements: -(winding / T::from(2.0 * PI).unwrap())
+            .round()
+            .to_i32()
+            .unwrap_or(0),
+        open_loop_unstable_poles: unstable,
+    }
+}
+
+/// Frequencies and values of L along the indented contour through `omega` and its mirror image
+fn nyquist_contour<T: Float>(tf: &dyn TransferFunction<T>, omega: &[T]) -> Vec<(T, Complex<T>)> {
+    let tol = T::epsilon().sqrt();
+    let pi = T::from(PI).unwrap();
+    let half = T::from(0.5).unwrap();
+    let arc_points = 32;
+    let (nyquist, scale, axis_poles) = match tf.time_domain() {
+        TimeDomain::Continous => (
+            None,
+            T::one(),
+            tf.poles()
+                .iter()
+                .filter(|p| p.re.abs() <= tol * p.norm().max(T::one()))
+                .map(|p| p.im)
+                .collect::<Vec<T>>(),
+        ),
+        TimeDomain::Discrete { sample_time } => (
+            Some(pi / sample_time),
+            sample_time,
+            tf.poles()
+                .iter()
+                .filter(|p| (p.norm() - T::one()).abs() <= tol)
+                .map(|p| p.arg() / sample_time)
+                .collect(),
+        ),
+    };
+
+    // Contour points are ordered by a key that runs along the imaginary axis (unit circle)
+    let mut positive: Vec<T> = omega
+        .iter()
+        .copied()
+        .filter(|&w| w > T::zero() && nyquist.is_none_or(|nyquist| w < nyquist))
+        .collect();
+    positive.extend(nyquist);
+    let w_min = positive.first().copied().unwrap_or(T::one());
+    let w_max = positive.last().copied().unwrap_or(T::one());
+    let mut contour: Vec<(T, T, Complex<T>)> = positive
+        .iter()
+        .rev()
+        .map(|&w| -w)
+        .chain([T::zero()])
+        .chain(positive.iter().copied())
+        .map(|w| (w, w, frequency_point(tf, w)))
+        .collect();
+    // The unit circle closes at the Nyquist frequency, keys and distances wrap around there
+    let wrap = |key: T| match nyquist {
+        Some(nyquist) if key > nyquist => key - nyquist - nyquist,
+        Some(nyquist) if key < -nyquist => key + nyquist + nyquist,
+        _ => key,
+    };
+    let mut axis_poles: Vec<T> = axis_poles
+        .into_iter()
+        .filter(|w| w.abs() <= w_max)
+        .collect();
+    axis_poles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    axis_poles.dedup_by(|a, b| (*a - *b).abs() <= tol * w_max);
+    for &w_pole in &axis_poles {
+        let radius = T::from(1e-3).unwrap() * w_pole.abs().max(w_min);
+        contour.retain(|&(key, _, _)| wrap(key - w_pole).abs() >= radius);
+        let center = frequency_point(tf, w_pole);
+        // Outward normal of the axis at the pole, 1 in the s-plane and the pole itself in z
+        let normal = match tf.time_domain() {
+            TimeDomain::Continous => Complex::from(T::one()),
+            TimeDomain::Discrete { .. } => center,
+        };
+        contour.extend((0..=arc_points).map(|k| {
+            let phi = pi * (T::from(k).unwrap() / T::from(arc_points).unwrap() - half);
+            let x = center + normal * Complex::from_polar(radius * scale, phi);
+            (wrap(w_pole + radius * phi.sin()), w_pole, x)
+        }));
+    }
+    contour.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    contour
+        .into_iter()
+        .map(|(_, omega, x)| (omega, tf.evaluate(x)))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NicholsPoint<T: Float> {
+    pub omega: T,
+    pub phase_deg: T,
+    pub mag_db: T,
+}
+
+/// Open-loop magnitude in dB against phase in degrees. The phase is unwrapped, so the curve runs
+/// on continuously instead of jumping between the 360 degree copies of the chart
+pub fn nichols_data<T: Float>(tf: &dyn TransferFunction<T>, omega: &[T]) -> Vec<NicholsPoint<T>> {
+    unwrapped_bode_data(tf, omega)
+        .into_iter()
+        .map(|point| NicholsPoint {
+            omega: point.omega,
+            phase_deg: point.phase_rad.to_degrees(),
+            mag_db: point.mag_db,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SigmaPoint<T: Float> {
+    pub omega: T,
+    /// Singular values of G(jw) in dB, largest first
+    pub sigma_db: Vec<T>,
+}
+
+pub fn sigma_data<T: Float, S: TransferFunction<T>>(
+    tfm: &TransferFunctionMatrix<T, S>,
+    omega: &[T],
+) -> Vec<SigmaPoint<T>> {
+    tfm.frequency_response(omega)
+        .iter()
+        .zip(omega.iter())
+        .map(|(g, &w)| SigmaPoint {
+            omega: w,
+            sigma_db: complex_singular_values(g)
+                .iter()
+                .map(|&sigma| T::from(20.0).unwrap() * sigma.log10())
+                .collect(),
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::tf::ctf::ContinousTransferFunction;
+    use crate::tf::{ctf::ContinousTransferFunction, dtf::DiscreteTransferFunction};
     use approx::assert_relative_eq;
     use std::f64::consts::FRAC_PI_4;
 
@@ -34,7 +648,7 @@ mod tests {
         // Given
         // H(s) = 1 / (s + 1) (1st-order low-pass filter)
         // omega = 1 rad/s
-        let tf = ContinousTransferFunction::<f64>::from_numden(&[1.0], &[1.0, 1.0]);
+        let tf = ContinousTransferFunction::<f64>::from_numden(&[1.0], &[1.0, 1.0]).unwrap();
         let omega = vec![1.0];
 
         // When
@@ -47,4 +661,347 @@ mod tests {
         assert_relative_eq!(-3.01, cutoff.mag_db, epsilon = 1e-3);
         assert_relative_eq!(-FRAC_PI_4, cutoff.phase_rad, epsilon = 1e-2)
     }
+
+    #[test]
+    fn test_sigma_data_diagonal() {
+        // Given
+        // G(s) = diag(10 / (s + 1), 1 / (s + 1)), singular values are the entry magnitudes
+        let tfm = TransferFunctionMatrix::new(vec![
+            vec![
+                ContinousTransferFunction::from_numden(&[10.0], &[1.0, 1.0]).unwrap(),
+                ContinousTransferFunction::from_numden(&[0.0], &[1.0]).unwrap(),
+            ],
+            vec![
+                ContinousTransferFunction::from_numden(&[0.0], &[1.0]).unwrap(),
+                ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0]).unwrap(),
+            ],
+        ])
+        .unwrap();
+
+        // When
+        let sigma_points = sigma_data(&tfm, &[0.0, 1.0]);
+
+        // Then
+        // 20 dB and 0 dB at DC, both 3 dB lower at w = 1 rad/s
+        assert_relative_eq!(20.0, sigma_points[0].sigma_db[0], epsilon = 1e-9);
+        assert_relative_eq!(0.0, sigma_points[0].sigma_db[1], epsilon = 1e-9);
+        assert_relative_eq!(16.99, sigma_points[1].sigma_db[0], epsilon = 1e-3);
+        assert_relative_eq!(-3.01, sigma_points[1].sigma_db[1], epsilon = 1e-3);
+    }
+
+    #[test]
+    fn test_bode_data_f32_matches_f64() {
+        // Given
+        // H(s) = 100 / (s^2 + 2s + 100), resonant peak at about 10 rad/s
+        let tf =
+            ContinousTransferFunction::<f64>::from_numden(&[100.0], &[1.0, 2.0, 100.0]).unwrap();
+        let tf_f32 =
+            ContinousTransferFunction::<f32>::from_numden(&[100.0], &[1.0, 2.0, 100.0]).unwrap();
+        let omega = [0.1, 1.0, 9.9, 10.0, 100.0];
+        let omega_f32 = omega.map(|w| w as f32);
+
+        // When
+        let bode_points = bode_data(&tf, &omega);
+        let bode_points_f32 = bode_data(&tf_f32, &omega_f32);
+
+        // Then
+        for (a, b) in bode_points.iter().zip(&bode_points_f32) {
+            assert_relative_eq!(a.mag_db, b.mag_db as f64, epsilon = 1e-4);
+            assert_relative_eq!(a.phase_rad, b.phase_rad as f64, epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_unwrapped_phase() {
+        // Given
+        // H(s) = 1 / (s + 1)^4, the principal phase wraps past -pi above 1 rad/s
+        let tf = ContinousTransferFunction::<f64>::from_numden(&[1.0], &[1.0, 4.0, 6.0, 4.0, 1.0])
+            .unwrap();
+        let omega = [0.1, 1.0, 10.0, 100.0];
+
+        // When
+        let bode_points = unwrapped_bode_data(&tf, &omega);
+
+        // Then
+        // Phase = -4 atan(w), approaching -2 pi
+        for point in &bode_points {
+            assert_relative_eq!(-4.0 * point.omega.atan(), point.phase_rad, epsilon = 1e-9);
+        }
+        assert_relative_eq!(bode_data(&tf, &omega)[3].mag_db, bode_points[3].mag_db);
+    }
+
+    #[test]
+    fn test_group_delay_first_order() {
+        // Given
+        // H(s) = e^(-0.25s) / (s + 1), group delay 1 / (1 + w^2) + 0.25
+        let tf = ContinousTransferFunction::<f64>::from_numden(&[1.0], &[1.0, 1.0])
+            .unwrap()
+            .with_delay(0.25)
+            .unwrap();
+
+        // When
+        let tau_g = group_delay(&tf, &[0.0, 1.0, 3.0]);
+        let tau_p = phase_delay(&tf, &[0.0, 1.0]);
+
+        // Then
+        assert_relative_eq!(1.25, tau_g[0], epsilon = 1e-12);
+        assert_relative_eq!(0.75, tau_g[1], epsilon = 1e-12);
+        assert_relative_eq!(0.35, tau_g[2], epsilon = 1e-12);
+        assert_relative_eq!(1.25, tau_p[0], epsilon = 1e-12);
+        assert_relative_eq!(1.0f64.atan() + 0.25, tau_p[1], epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_group_delay_matches_phase_slope() {
+        // Given
+        // 4th-order Bessel-like low-pass with a zero, compared to a central difference
+        let tf = ContinousTransferFunction::<f64>::from_numden(
+            &[0.5, 105.0],
+            &[1.0, 10.0, 45.0, 105.0, 105.0],
+        )
+        .unwrap();
+        let (w, h) = (2.0, 1e-5);
+
+        // When
+        let tau_g = group_delay(&tf, &[w])[0];
+        let phase = unwrapped_phase(&tf, &[w - h, w + h]);
+
+        // Then
+        assert_relative_eq!(-(phase[1] - phase[0]) / (2.0 * h), tau_g, epsilon = 1e-7);
+    }
+
+    #[test]
+    fn test_discrete_delays() {
+        // Given
+        // H(z) = (1 + z^-1) / 2 at T = 0.1 s has a constant delay of half a sample,
+        // an extra z^-1 adds one sample
+        let average =
+            DiscreteTransferFunction::from_numden(vec![0.5, 0.5], vec![1.0, 0.0], 0.1).unwrap();
+        let delayed =
+            DiscreteTransferFunction::from_numden(vec![0.5, 0.5], vec![1.0, 0.0, 0.0], 0.1)
+                .unwrap();
+        let omega = [0.0, 5.0, 20.0];
+
+        // When
+        let tau_g = group_delay(&average, &omega);
+        let tau_p = phase_delay(&delayed, &omega);
+
+        // Then
+        for k in 0..omega.len() {
+            assert_relative_eq!(0.05, tau_g[k], epsilon = 1e-12);
+            assert_relative_eq!(0.15, tau_p[k], epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_margins_third_order() {
+        // Given
+        // L(s) = 2 / (s (s + 1) (s + 2)), phase crossover at sqrt(2) rad/s where |L| = 1/3
+        let tf =
+            ContinousTransferFunction::<f64>::from_numden(&[2.0], &[1.0, 3.0, 2.0, 0.0]).unwrap();
+
+        // When
+        let margins = margins(&tf).unwrap();
+
+        // Then
+        assert_relative_eq!(
+            20.0 * 3f64.log10(),
+            margins.gain_margin_db.unwrap(),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            2f64.sqrt(),
+            margins.phase_crossover.unwrap(),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(0.749368, margins.gain_crossover.unwrap(), epsilon = 1e-6);
+        assert_relative_eq!(0.569206, margins.phase_margin_rad.unwrap(), epsilon = 1e-6);
+        assert_relative_eq!(0.759581, margins.delay_margin.unwrap(), epsilon = 1e-6);
+        assert_relative_eq!(0.432467, margins.modulus_margin, epsilon = 1e-6);
+        assert_relative_eq!(0.92529, margins.modulus_frequency, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn test_margins_with_delay() {
+        // Given
+        // L(s) = 2 e^(-0.5s) / (s + 1), gain crossover at sqrt(3) rad/s
+        let tf = ContinousTransferFunction::<f64>::from_numden(&[2.0], &[1.0, 1.0])
+            .unwrap()
+            .with_delay(0.5)
+            .unwrap();
+
+        // When
+        let margins = margins(&tf).unwrap();
+
+        // Then
+        // Phase crossover solves atan(w) + 0.5w = pi
+        assert_relative_eq!(3f64.sqrt(), margins.gain_crossover.unwrap(), epsilon = 1e-9);
+        assert_relative_eq!(1.228370, margins.phase_margin_rad.unwrap(), epsilon = 1e-6);
+        assert_relative_eq!(0.709200, margins.delay_margin.unwrap(), epsilon = 1e-6);
+        assert_relative_eq!(3.673194, margins.phase_crossover.unwrap(), epsilon = 1e-6);
+        assert_relative_eq!(5.590790, margins.gain_margin_db.unwrap(), epsilon = 1e-6);
+        assert_relative_eq!(0.450328, margins.modulus_margin, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_margins_discrete_integrator() {
+        // Given
+        // L(z) = 0.5 / (z - 1) at T = 1 s, the phase reaches -180 degrees at the Nyquist frequency
+        let tf = DiscreteTransferFunction::from_numden(vec![0.5], vec![1.0, -1.0], 1.0).unwrap();
+
+        // When
+        let margins = margins(&tf).unwrap();
+
+        // Then
+        // |e^(jw) - 1| = 2 sin(w/2) = 0.5 at the gain crossover
+        assert_relative_eq!(
+            20.0 * 4f64.log10(),
+            margins.gain_margin_db.unwrap(),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(PI, margins.phase_crossover.unwrap(), epsilon = 1e-12);
+        assert_relative_eq!(
+            2.0 * 0.25f64.asin(),
+            margins.gain_crossover.unwrap(),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(1.318116, margins.phase_margin_rad.unwrap(), epsilon = 1e-6);
+        assert_relative_eq!(2.608269, margins.delay_margin.unwrap(), epsilon = 1e-6);
+        assert_relative_eq!(0.75, margins.modulus_margin, epsilon = 1e-9);
+        assert_relative_eq!(PI, margins.modulus_frequency, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_margins_without_crossovers() {
+        // Given
+        // L(s) = 0.5 / (s + 1) never reaches unit gain or -180 degrees
+        let tf = ContinousTransferFunction::<f64>::from_numden(&[0.5], &[1.0, 1.0]).unwrap();
+
+        // When
+        let margins = margins(&tf).unwrap();
+
+        // Then
+        // |1 + L| decreases towards 1 as w grows
+        assert_eq!(None, margins.gain_margin_db);
+        assert_eq!(None, margins.phase_margin_rad);
+        assert_eq!(None, margins.delay_margin);
+        assert_relative_eq!(1.0, margins.modulus_margin);
+        assert!(margins.modulus_frequency.is_infinite());
+    }
+
+    #[test]
+    fn test_nyquist_integrating_loop() {
+        // Given
+        // L(s) = K / (s (s + 1) (s + 2)), the closed loop turns unstable above K = 6
+        let stable =
+            ContinousTransferFunction::<f64>::from_numden(&[2.0], &[1.0, 3.0, 2.0, 0.0]).unwrap();
+        let unstable =
+            ContinousTransferFunction::<f64>::from_numden(&[10.0], &[1.0, 3.0, 2.0, 0.0]).unwrap();
+        let omega = logspace(-3.0, 3.0, 2000);
+
+        // When
+        let stable_data = nyquist_data(&stable, &omega);
+        let unstable_data = nyquist_data(&unstable, &omega);
+
+        // Then
+        // The integrator is passed on the right, its image is a large arc through Re(L) > 0
+        assert_eq!(0, stable_data.encirclements);
+        assert_eq!(0, stable_data.closed_loop_unstable_poles());
+        assert_eq!(2, unstable_data.encirclements);
+        assert_eq!(2, unstable_data.closed_loop_unstable_poles());
+        let arc: Vec<_> = stable_data
+            .points
+            .iter()
+            .filter(|point| point.omega == 0.0)
+            .collect();
+        assert_eq!(33, arc.len());
+        assert!(arc[16].re > 1e3);
+        assert_relative_eq!(0.0, arc[16].im, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_nyquist_open_loop_unstable() {
+        // Given
+        // L(s) = 2 / (s - 1) with one unstable pole, 1 + L has its zero at s = -1
+        let tf = ContinousTransferFunction::<f64>::from_numden(&[2.0], &[1.0, -1.0]).unwrap();
+
+        // When
+        let data = nyquist_data(&tf, &logspace(-3.0, 3.0, 1000));
+
+        // Then
+        // One counter-clockwise encirclement cancels the unstable open-loop pole
+        assert_eq!(1, data.open_loop_unstable_poles);
+        assert_eq!(-1, data.encirclements);
+        assert_eq!(0, data.closed_loop_unstable_poles());
+    }
+
+    #[test]
+    fn test_nyquist_discrete_integrator() {
+        // Given
+        // L(z) = K / (z - 1) at T = 1 s, the closed-loop pole 1 - K leaves the unit circle above K = 2
+        let stable =
+            DiscreteTransferFunction::from_numden(vec![0.5], vec![1.0, -1.0], 1.0).unwrap();
+        let unstable =
+            DiscreteTransferFunction::from_numden(vec![3.0], vec![1.0, -1.0], 1.0).unwrap();
+        let omega = logspace(-3.0, 1.0, 1000);
+
+        // When
+        let stable_data = nyquist_data(&stable, &omega);
+        let unstable_data = nyquist_data(&unstable, &omega);
+
+        // Then
+        // Frequencies above the Nyquist frequency are dropped
+        assert_eq!(0, stable_data.closed_loop_unstable_poles());
+        assert_eq!(1, unstable_data.closed_loop_unstable_poles());
+        assert!(
+            stable_data
+                .points
+                .iter()
+                .all(|point| point.omega.abs() <= PI)
+        );
+        assert_relative_eq!(
+            -0.25,
+            stable_data.points.last().unwrap().re,
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_nichols_data() {
+        // Given
+        // L(s) = 2 / (s (s + 1) (s + 2)) crosses -180 degrees at sqrt(2) rad/s with |L| = 1/3
+        let tf =
+            ContinousTransferFunction::<f64>::from_numden(&[2.0], &[1.0, 3.0, 2.0, 0.0]).unwrap();
+
+        // When
+        let nichols_points = nichols_data(&tf, &[2f64.sqrt(), 10.0]);
+
+        // Then
+        // Above the crossover the phase continues below -180 degrees towards -270
+        assert_relative_eq!(-180.0, nichols_points[0].phase_deg, epsilon = 1e-9);
+        assert_relative_eq!(
+            -20.0 * 3f64.log10(),
+            nichols_points[0].mag_db,
+            epsilon = 1e-9
+        );
+        let phase = -90.0 - 10f64.atan().to_degrees() - 5f64.atan().to_degrees();
+        assert_relative_eq!(phase, nichols_points[1].phase_deg, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_nyquist_count_independent_of_drawn_range() {
+        // Given
+        // L(s) = 10 / (s/1000 + 1)^3 crosses -180 degrees near 1700 rad/s, closed loop unstable
+        let tf = ContinousTransferFunction::<f64>::from_numden(&[10.0], &[1e-9, 3e-6, 3e-3, 1.0])
+            .unwrap();
+
+        // When
+        let narrow = nyquist_data(&tf, &logspace(-2.0, 2.0, 1000));
+        let wide = nyquist_data(&tf, &logspace(-2.0, 5.0, 1000));
+
+        // Then
+        assert_eq!(2, narrow.closed_loop_unstable_poles());
+        assert_eq!(2, wide.closed_loop_unstable_poles());
+        assert!(narrow.points.iter().all(|point| point.omega.abs() <= 100.0));
+    }
 }
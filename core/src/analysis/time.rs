@@ -5,6 +5,11 @@ use num::Float;
 use crate::tf::{TransferFunction, dtf::DiscreteTransferFunction};
 
 #[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum ResponseType {
     Impulse,
     Step,
@@ -26,6 +31,7 @@ impl ResponseType {
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ResponsePoint<T: Float> {
     pub time: T,
     pub mag: T,
@@ -46,11 +52,12 @@ pub struct OpenLoopResponse<'a, T: Float> {
 
 impl<'a, T: Float> OpenLoopResponse<'a, T> {
     pub fn new(tf: &'a DiscreteTransferFunction<T>, response_type: ResponseType) -> Self {
+        let state_len = tf.numerator().len().max(tf.denominator().len());
         Self {
             tf,
             response_type,
-            input_state: vec![T::zero(); tf.numerator().len()],
-            output_state: vec![T::zero(); tf.numerator().len()],
+            input_state: vec![T::zero(); state_len],
+            output_state: vec![T::zero(); state_len],
         }
     }
 }
@@ -60,9 +67,15 @@ impl<'a, T: Float + Sum> LTIResponse<T> for OpenLoopResponse<'a, T> {
         self.input_state.rotate_right(1);
         self.input_state[0] = input;
 
+        // Strictly proper systems have a shorter numerator, delayed relative to the denominator
+        let delay = self
+            .tf
+            .denominator()
+            .len()
+            .saturating_sub(self.tf.numerator().len());
         let forward = self.tf.numerator()[..]
             .iter()
-            .zip(self.input_state.iter())
+            .zip(self.input_state.iter().skip(delay))
             .map(|(&b, &x)| b * x)
             .sum::<T>();
 
@@ -0,0 +1,167 @@
+use std::fmt;
+
+use crate::tf::SCHEMA_VERSION;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlError {
+    /// A continuous system was combined with a discrete one
+    TimeDomainMismatch,
+    /// Two discrete systems with different sample times were combined
+    SampleTimeMismatch { left: f64, right: f64 },
+    /// A complex pole or zero without its conjugate would give complex coefficients
+    UnpairedComplexRoot { re: f64, im: f64 },
+    /// A state-space matrix does not fit the dimensions implied by the others
+    DimensionMismatch {
+        matrix: &'static str,
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    /// A channel was requested outside the input and output ranges of the system
+    ChannelOutOfRange {
+        output: usize,
+        input: usize,
+        outputs: usize,
+        inputs: usize,
+    },
+    /// The numerator order exceeds the denominator order, so no realization exists
+    ImproperTransferFunction,
+    /// A single-input single-output system was required
+    NotSiso { inputs: usize, outputs: usize },
+    /// Closing the loop gives a singular I + D_g * D_h, so the feedback is ill-posed
+    AlgebraicLoop,
+    /// Adding systems with different transport delays does not give a delayed rational function
+    DelayMismatch { left: f64, right: f64 },
+    /// The operation needs a rational transfer function, so the delay must be approximated first
+    DelayNotSupported,
+    /// A numerator or denominator was given without any coefficients
+    EmptyPolynomial { polynomial: &'static str },
+    /// Every denominator coefficient is zero
+    ZeroDenominator,
+    /// A coefficient is NaN or infinite
+    NonFiniteCoefficient,
+    /// Sample times must be positive and finite
+    InvalidSampleTime { sample_time: f64 },
+    /// Transport delays must be non-negative and finite
+    InvalidDelay { delay: f64 },
+    /// A transport delay is not a whole number of samples, so it has no exact z^-N equivalent
+    FractionalDelay { delay: f64, sample_time: f64 },
+    /// The eigenvalue solver did not converge
+    RootFindingFailed,
+    /// Pre-warping needs a frequency between zero and the Nyquist frequency
+    InvalidPrewarpFrequency { omega_p: f64 },
+    /// The discretization method has no discrete-to-continuous inverse
+    NoInverse { method: &'static str },
+    /// A pole or zero at z = 0 or on the negative real axis has no continuous equivalent
+    NoContinuousEquivalent,
+    /// A transfer function expression could not be parsed, position counts characters from zero
+    InvalidExpression {
+        position: usize,
+        reason: &'static str,
+    },
+    /// A serialized model was written with a schema version this build cannot read
+    UnsupportedSchemaVersion { version: u32 },
+}
+
+impl fmt::Display for ControlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ControlError::TimeDomainMismatch => {
+                write!(f, "cannot combine continuous and discrete systems")
+            }
+            ControlError::SampleTimeMismatch { left, right } => write!(
+                f,
+                "cannot combine discrete systems with sample times {} s and {} s",
+                left, right
+            ),
+            ControlError::UnpairedComplexRoot { re, im } => {
+                write!(f, "complex root {} + {}j has no conjugate pair", re, im)
+            }
+            ControlError::DimensionMismatch {
+                matrix,
+                expected,
+                found,
+            } => write!(
+                f,
+                "matrix {} should be {}x{} but is {}x{}",
+                matrix, expected.0, expected.1, found.0, found.1
+            ),
+            ControlError::ChannelOutOfRange {
+                output,
+                input,
+                outputs,
+                inputs,
+            } => write!(
+                f,
+                "channel from input {} to output {} does not exist in a system with {} inputs and {} outputs",
+                input, output, inputs, outputs
+            ),
+            ControlError::ImproperTransferFunction => {
+                write!(f, "numerator order exceeds denominator order")
+            }
+            ControlError::NotSiso { inputs, outputs } => write!(
+                f,
+                "expected a single-input single-output system, got {} inputs and {} outputs",
+                inputs, outputs
+            ),
+            ControlError::AlgebraicLoop => {
+                write!(f, "feedback loop is ill-posed, I + D_g * D_h is singular")
+            }
+            ControlError::DelayMismatch { left, right } => write!(
+                f,
+                "cannot add systems with transport delays {} s and {} s",
+                left, right
+            ),
+            ControlError::DelayNotSupported => write!(
+                f,
+                "transport delay is not supported here, replace it with a Pade approximation"
+            ),
+            ControlError::EmptyPolynomial { polynomial } => {
+                write!(f, "{} has no coefficients", polynomial)
+            }
+            ControlError::ZeroDenominator => write!(f, "denominator is zero"),
+            ControlError::NonFiniteCoefficient => {
+                write!(f, "coefficients must be finite numbers")
+            }
+            ControlError::InvalidSampleTime { sample_time } => write!(
+                f,
+                "sample time must be positive and finite, got {}",
+                sample_time
+            ),
+            ControlError::InvalidDelay { delay } => write!(
+                f,
+                "transport delay must be non-negative and finite, got {}",
+                delay
+            ),
+            ControlError::FractionalDelay { delay, sample_time } => write!(
+                f,
+                "transport delay {} s is not a whole number of {} s samples, use a multiple of the sample time or a Pade approximation",
+                delay, sample_time
+            ),
+            ControlError::RootFindingFailed => {
+                write!(f, "polynomial root finding did not converge")
+            }
+            ControlError::InvalidPrewarpFrequency { omega_p } => write!(
+                f,
+                "pre-warp frequency {} rad/s must lie between zero and the Nyquist frequency",
+                omega_p
+            ),
+            ControlError::NoInverse { method } => {
+                write!(f, "{} has no discrete-to-continuous conversion", method)
+            }
+            ControlError::NoContinuousEquivalent => write!(
+                f,
+                "poles or zeroes at z = 0 or on the negative real axis have no continuous equivalent"
+            ),
+            ControlError::InvalidExpression { position, reason } => {
+                write!(f, "{} at character {}", reason, position + 1)
+            }
+            ControlError::UnsupportedSchemaVersion { version } => write!(
+                f,
+                "schema version {} is not supported, this build reads versions 1 to {}",
+                version, SCHEMA_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ControlError {}
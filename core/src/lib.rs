@@ -1,4 +1,6 @@
 pub mod analysis;
+pub mod error;
 pub mod filter;
+pub mod ss;
 pub mod tf;
 pub mod util;
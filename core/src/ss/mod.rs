@@ -0,0 +1,4 @@
+pub mod realization;
+pub mod statespace;
+
+pub use statespace::{CanonicalForm, StateSpace};
@@ -0,0 +1,63 @@
+use ndarray::Array2;
+use num::Float;
+
+use crate::util::{
+    linalg::{faddeev_leverrier, matmul},
+    poly::trim_leading_zeros,
+};
+
+// Controllable canonical realization of a proper SISO transfer function with a nonzero
+// denominator. Matches the companion matrix layout used for root finding
+pub fn controllable_canonical<T: Float>(
+    num: &[T],
+    den: &[T],
+) -> (Array2<T>, Array2<T>, Array2<T>, T) {
+    // Leading zeroes would put a zero in the normalization and overstate the order
+    let num = &trim_leading_zeros(num);
+    let den = &trim_leading_zeros(den);
+    let n = den.len() - 1;
+    let a0 = den[0];
+    let den: Vec<T> = den.iter().map(|&c| c / a0).collect();
+    let mut num_padded = vec![T::zero(); (n + 1).saturating_sub(num.len())];
+    num_padded.extend(num.iter().map(|&c| c / a0));
+    let d = num_padded[0];
+
+    let mut a = Array2::zeros((n, n));
+    let mut b = Array2::zeros((n, 1));
+    let mut c = Array2::zeros((1, n));
+    for i in 0..n {
+        a[(0, i)] = -den[i + 1];
+        if i < n - 1 {
+            a[(i + 1, i)] = T::one();
+        }
+        c[(0, i)] = num_padded[i + 1] - d * den[i + 1];
+    }
+    if n > 0 {
+        b[(0, 0)] = T::one();
+    }
+    (a, b, c, d)
+}
+
+// Dual of the controllable form, (A^T, C^T, B^T, D)
+pub fn observable_canonical<T: Float>(
+    num: &[T],
+    den: &[T],
+) -> (Array2<T>, Array2<T>, Array2<T>, T) {
+    let (a, b, c, d) = controllable_canonical(num, den);
+    (a.t().to_owned(), c.t().to_owned(), b.t().to_owned(), d)
+}
+
+// H(x) = c adj(xI - A) b / det(xI - A) + d for a single input column b and output row c
+pub fn realization_to_numden<T: Float>(
+    a: &Array2<T>,
+    b: &Array2<T>,
+    c: &Array2<T>,
+    d: T,
+) -> (Vec<T>, Vec<T>) {
+    let (den, adjugate_coeffs) = faddeev_leverrier(a);
+    let mut num: Vec<T> = den.iter().map(|&p| p * d).collect();
+    for (k, m) in adjugate_coeffs.iter().enumerate() {
+        num[k + 1] = num[k + 1] + matmul(&matmul(c, m), b)[(0, 0)];
+    }
+    (trim_leading_zeros(&num), den)
+}
@@ -0,0 +1,488 @@
+use ndarray::{Array2, Axis, concatenate, s};
+use num::{Float, complex::Complex};
+
+use crate::{
+    error::ControlError,
+    tf::{
+        TimeDomain, TransferFunction,
+        algebra::{FeedbackSign, check_same_domain},
+        ctf::ContinousTransferFunction,
+        dtf::DiscreteTransferFunction,
+        traits::{check_sample_time, eigenvalues},
+    },
+    util::linalg::{inv, matmul},
+};
+
+use super::realization::{controllable_canonical, observable_canonical, realization_to_numden};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CanonicalForm {
+    Controllable,
+    Observable,
+}
+
+/// dx = Ax + Bu, y = Cx + Du, where dx is the derivative (continuous) or next state (discrete)
+#[derive(Debug, Clone)]
+pub struct StateSpace<T: Float> {
+    a: Array2<T>,
+    b: Array2<T>,
+    c: Array2<T>,
+    d: Array2<T>,
+    time_domain: TimeDomain<T>,
+}
+
+impl<T: Float> StateSpace<T> {
+    pub fn new(
+        a: Array2<T>,
+        b: Array2<T>,
+        c: Array2<T>,
+        d: Array2<T>,
+        time_domain: TimeDomain<T>,
+    ) -> Result<Self, ControlError> {
+        let states = a.nrows();
+        let inputs = b.ncols();
+        let outputs = c.nrows();
+        check_dimensions("A", (states, states), a.dim())?;
+        check_dimensions("B", (states, inputs), b.dim())?;
+        check_dimensions("C", (outputs, states), c.dim())?;
+        check_dimensions("D", (outputs, inputs), d.dim())?;
+        if let TimeDomain::Discrete { sample_time } = time_domain {
+            check_sample_time(sample_time)?;
+        }
+        Ok(Self {
+            a,
+            b,
+            c,
+            d,
+            time_domain,
+        })
+    }
+
+    pub fn from_tf(
+        tf: &dyn TransferFunction<T>,
+        form: CanonicalForm,
+    ) -> Result<Self, ControlError> {
+        if tf.numerator().len() > tf.denominator().len() {
+            return Err(ControlError::ImproperTransferFunction);
+        }
+        if tf.delay() != T::zero() {
+            return Err(ControlError::DelayNotSupported);
+        }
+        let (a, b, c, d) = match form {
+            CanonicalForm::Controllable => controllable_canonical(tf.numerator(), tf.denominator()),
+            CanonicalForm::Observable => observable_canonical(tf.numerator(), tf.denominator()),
+        };
+        Ok(Self {
+            a,
+            b,
+            c,
+            d: Array2::from_elem((1, 1), d),
+            time_domain: tf.time_domain(),
+        })
+    }
+
+    pub fn a(&self) -> &Array2<T> {
+        &self.a
+    }
+
+    pub fn b(&self) -> &Array2<T> {
+        &self.b
+    }
+
+    pub fn c(&self) -> &Array2<T> {
+        &self.c
+    }
+
+    pub fn d(&self) -> &Array2<T> {
+        &self.d
+    }
+
+    pub fn time_domain(&self) -> TimeDomain<T> {
+        self.time_domain
+    }
+
+    pub fn states(&self) -> usize {
+        self.a.nrows()
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.b.ncols()
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.c.nrows()
+    }
+
+    /// Eigenvalues of A
+    pub fn poles(&self) -> Result<Vec<Complex<T>>, ControlError> {
+        eigenvalues(&self.a)
+    }
+
+    /// Numerator and denominator of the transfer function from `input` to `output`
+    /// A channel outside the system is reported against the dimensions of D
+    pub fn channel_numden(
+        &self,
+        output: usize,
+        input: usize,
+    ) -> Result<(Vec<T>, Vec<T>), ControlError> {
+        if output >= self.outputs() || input >= self.inputs() {
+            return Err(ControlError::ChannelOutOfRange {
+                output,
+                input,
+                outputs: self.outputs(),
+                inputs: self.inputs(),
+            });
+        }
+        Ok(realization_to_numden(
+            &self.a,
+            &self.b.slice(s![.., input..input + 1]).to_owned(),
+            &self.c.slice(s![output..output + 1, ..]).to_owned(),
+            self.d[(output, input)],
+        ))
+    }
+
+    pub fn to_ctf(&self) -> Result<ContinousTransferFunction<T>, ControlError> {
+        self.check_siso()?;
+        match self.time_domain {
+            TimeDomain::Continous => {
+                let (numerator, denominator) = self.channel_numden(0, 0)?;
+                ContinousTransferFunction::from_numden(&numerator, &denominator)
+            }
+            TimeDomain::Discrete { .. } => Err(ControlError::TimeDomainMismatch),
+        }
+    }
+
+    pub fn to_dtf(&self) -> Result<DiscreteTransferFunction<T>, ControlError> {
+        self.check_siso()?;
+        match self.time_domain {
+            TimeDomain::Discrete { sample_time } => {
+                let (numerator, denominator) = self.channel_numden(0, 0)?;
+                DiscreteTransferFunction::from_numden(numerator, denominator, sample_time)
+            }
+            TimeDomain::Continous => Err(ControlError::TimeDomainMismatch),
+        }
+    }
+
+    /// Closed loop with self in the forward path and h in the return path
+    pub fn feedback(&self, h: &StateSpace<T>, sign: FeedbackSign) -> Result<Self, ControlError> {
+        check_same_domain(self.time_domain, h.time_domain)?;
+        let expected = (self.inputs(), self.outputs());
+        let found = (h.outputs(), h.inputs());
+        if expected != found {
+            return Err(ControlError::DimensionMismatch {
+                matrix: "H",
+                expected,
+                found,
+            });
+        }
+        let loop_sign = match sign {
+            FeedbackSign::Negative => T::one(),
+            FeedbackSign::Positive => -T::one(),
+        };
+        let (inputs, outputs) = (self.inputs(), self.outputs());
+        let (g_states, h_states) = (self.states(), h.states());
+
+        // y = C_y x + D_y r, solved from y = C_g x_g + D_g (r -+ (C_h x_h + D_h y))
+        let f = inv(&(Array2::eye(outputs) + matmul(&self.d, &h.d).mapv(|x| x * loop_sign)))
+            .ok_or(ControlError::AlgebraicLoop)?;
+        let c_y = concatenate![
+            Axis(1),
+            matmul(&f, &self.c),
+            matmul(&matmul(&f, &self.d), &h.c).mapv(|x| -loop_sign * x)
+        ];
+        let d_y = matmul(&f, &self.d);
+
+        // e = C_e x + D_e r is the signal entering G
+        let c_e = (concatenate![Axis(1), Array2::zeros((inputs, g_states)), h.c]
+            + matmul(&h.d, &c_y))
+        .mapv(|x| -loop_sign * x);
+        let d_e = Array2::eye(inputs) - matmul(&h.d, &d_y).mapv(|x| loop_sign * x);
+
+        let b_g = concatenate![Axis(0), self.b, Array2::zeros((h_states, inputs))];
+        let b_h = concatenate![Axis(0), Array2::zeros((g_states, outputs)), h.b];
+        let mut a = Array2::zeros((g_states + h_states, g_states + h_states));
+        a.slice_mut(s![..g_states, ..g_states]).assign(&self.a);
+        a.slice_mut(s![g_states.., g_states..]).assign(&h.a);
+        let a = a + matmul(&b_g, &c_e) + matmul(&b_h, &c_y);
+        let b = matmul(&b_g, &d_e) + matmul(&b_h, &d_y);
+        Self::new(a, b, c_y, d_y, self.time_domain)
+    }
+
+    fn check_siso(&self) -> Result<(), ControlError> {
+        if self.inputs() == 1 && self.outputs() == 1 {
+            Ok(())
+        } else {
+            Err(ControlError::NotSiso {
+                inputs: self.inputs(),
+                outputs: self.outputs(),
+            })
+        }
+    }
+}
+
+fn check_dimensions(
+    matrix: &'static str,
+    expected: (usize, usize),
+    found: (usize, usize),
+) -> Result<(), ControlError> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(ControlError::DimensionMismatch {
+            matrix,
+            expected,
+            found,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use ndarray::array;
+
+    use super::*;
+
+    fn assert_coeffs(expected: &[f64], actual: &[f64]) {
+        assert_eq!(expected.len(), actual.len());
+        for (e, a) in expected.iter().zip(actual.iter()) {
+            assert_relative_eq!(e, a, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_mass_spring_damper_to_tf() {
+        // Given
+        // m = 2, c = 3, k = 4 with position output: H(s) = 1 / (2s^2 + 3s + 4)
+        let (m, c, k) = (2.0, 3.0, 4.0);
+        let ss = StateSpace::new(
+            array![[0.0, 1.0], [-k / m, -c / m]],
+            array![[0.0], [1.0 / m]],
+            array![[1.0, 0.0]],
+            array![[0.0]],
+            TimeDomain::Continous,
+        )
+        .unwrap();
+
+        // When
+        let tf = ss.to_ctf().unwrap();
+
+        // Then
+        assert_coeffs(&[0.5], tf.numerator());
+        assert_coeffs(&[1.0, 1.5, 2.0], tf.denominator());
+    }
+
+    #[test]
+    fn test_tf_round_trip() {
+        // Given
+        // H(s) = (2s^2 + 3s + 1) / (s^3 + 6s^2 + 11s + 6)
+        let tf = ContinousTransferFunction::from_numden(&[2.0, 3.0, 1.0], &[1.0, 6.0, 11.0, 6.0])
+            .unwrap();
+
+        for form in [CanonicalForm::Controllable, CanonicalForm::Observable] {
+            // When
+            let ss = StateSpace::from_tf(&tf, form).unwrap();
+            let result = ss.to_ctf().unwrap();
+
+            // Then
+            assert_eq!(3, ss.states());
+            assert_coeffs(tf.numerator(), result.numerator());
+            assert_coeffs(tf.denominator(), result.denominator());
+        }
+    }
+
+    #[test]
+    fn test_feedthrough_round_trip() {
+        // Given
+        // H(z) = (2z + 1) / (z - 0.5), sample time 0.1
+        let tf =
+            DiscreteTransferFunction::from_numden(vec![2.0, 1.0], vec![1.0, -0.5], 0.1).unwrap();
+
+        // When
+        let ss = StateSpace::from_tf(&tf, CanonicalForm::Controllable).unwrap();
+        let result = ss.to_dtf().unwrap();
+
+        // Then
+        assert_relative_eq!(2.0, ss.d()[(0, 0)]);
+        assert_eq!(TimeDomain::Discrete { sample_time: 0.1 }, ss.time_domain());
+        assert_coeffs(&[2.0, 1.0], result.numerator());
+        assert_coeffs(&[1.0, -0.5], result.denominator());
+    }
+
+    #[test]
+    fn test_poles() {
+        // Given
+        // Poles of (s + 1)(s + 2)(s + 3)
+        let tf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 6.0, 11.0, 6.0]).unwrap();
+        let ss = StateSpace::from_tf(&tf, CanonicalForm::Observable).unwrap();
+
+        // When
+        let mut poles: Vec<f64> = ss.poles().unwrap().iter().map(|p| p.re).collect();
+        poles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        // Then
+        assert_coeffs(&[-3.0, -2.0, -1.0], &poles);
+    }
+
+    #[test]
+    fn test_feedback_matches_tf_feedback() {
+        // Given
+        // G(s) = (s + 3) / (s^2 + 2s + 1), H(s) = 2 / (s + 5)
+        let g = ContinousTransferFunction::from_numden(&[1.0, 3.0], &[1.0, 2.0, 1.0]).unwrap();
+        let h = ContinousTransferFunction::from_numden(&[2.0], &[1.0, 5.0]).unwrap();
+        let g_ss = StateSpace::from_tf(&g, CanonicalForm::Controllable).unwrap();
+        let h_ss = StateSpace::from_tf(&h, CanonicalForm::Observable).unwrap();
+
+        for sign in [FeedbackSign::Negative, FeedbackSign::Positive] {
+            // When
+            let result = g_ss.feedback(&h_ss, sign).unwrap().to_ctf().unwrap();
+
+            // Then
+            let expected = crate::tf::algebra::feedback(&g, &h, sign).unwrap();
+            assert_coeffs(expected.numerator(), result.numerator());
+            assert_coeffs(expected.denominator(), result.denominator());
+        }
+    }
+
+    #[test]
+    fn test_feedback_with_feedthrough() {
+        // Given
+        // G = 2 (static gain), H = 1 / (s + 1)
+        // G / (1 + GH) = 2(s + 1) / (s + 3)
+        let g = ContinousTransferFunction::from_numden(&[2.0], &[1.0]).unwrap();
+        let h = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0]).unwrap();
+        let g_ss = StateSpace::from_tf(&g, CanonicalForm::Controllable).unwrap();
+        let h_ss = StateSpace::from_tf(&h, CanonicalForm::Controllable).unwrap();
+
+        // When
+        let result = g_ss
+            .feedback(&h_ss, FeedbackSign::Negative)
+            .unwrap()
+            .to_ctf()
+            .unwrap();
+
+        // Then
+        assert_coeffs(&[2.0, 2.0], result.numerator());
+        assert_coeffs(&[1.0, 3.0], result.denominator());
+    }
+
+    #[test]
+    fn test_algebraic_loop() {
+        // Given
+        // G = 1, H = -1 in negative feedback: 1 + GH = 0
+        let g = ContinousTransferFunction::from_numden(&[1.0], &[1.0]).unwrap();
+        let h = ContinousTransferFunction::from_numden(&[-1.0], &[1.0]).unwrap();
+        let g_ss = StateSpace::from_tf(&g, CanonicalForm::Controllable).unwrap();
+        let h_ss = StateSpace::from_tf(&h, CanonicalForm::Controllable).unwrap();
+
+        // When
+        let result = g_ss.feedback(&h_ss, FeedbackSign::Negative);
+
+        // Then
+        assert_eq!(ControlError::AlgebraicLoop, result.unwrap_err());
+    }
+
+    #[test]
+    fn test_dimension_mismatch() {
+        // When
+        let result = StateSpace::new(
+            array![[0.0, 1.0], [-1.0, -1.0]],
+            array![[0.0], [1.0]],
+            array![[1.0, 0.0]],
+            array![[0.0, 0.0]],
+            TimeDomain::Continous,
+        );
+
+        // Then
+        assert_eq!(
+            ControlError::DimensionMismatch {
+                matrix: "D",
+                expected: (1, 1),
+                found: (1, 2)
+            },
+            result.unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_invalid_sample_time() {
+        // Given
+        let a = array![[0.5]];
+        let b = array![[1.0]];
+        let c = array![[1.0]];
+        let d = array![[0.0]];
+
+        for sample_time in [0.0, -0.1, f64::NAN] {
+            // When
+            let result = StateSpace::new(
+                a.clone(),
+                b.clone(),
+                c.clone(),
+                d.clone(),
+                TimeDomain::Discrete { sample_time },
+            );
+
+            // Then
+            assert!(matches!(
+                result.unwrap_err(),
+                ControlError::InvalidSampleTime { .. }
+            ));
+        }
+    }
+
+    #[test]
+    fn test_improper_and_mimo_rejected() {
+        // Given
+        let improper =
+            ContinousTransferFunction::from_numden(&[1.0, 0.0, 0.0], &[1.0, 1.0]).unwrap();
+        let mimo = StateSpace::new(
+            array![[-1.0]],
+            array![[1.0, 1.0]],
+            array![[1.0]],
+            array![[0.0, 0.0]],
+            TimeDomain::Continous,
+        )
+        .unwrap();
+
+        // Then
+        assert_eq!(
+            ControlError::ImproperTransferFunction,
+            StateSpace::from_tf(&improper, CanonicalForm::Controllable).unwrap_err()
+        );
+        assert_eq!(
+            ControlError::NotSiso {
+                inputs: 2,
+                outputs: 1
+            },
+            mimo.to_ctf().unwrap_err()
+        );
+        assert_coeffs(&[1.0], &mimo.channel_numden(0, 1).unwrap().0);
+        assert_eq!(
+            ControlError::ChannelOutOfRange {
+                output: 1,
+                input: 0,
+                outputs: 1,
+                inputs: 2
+            },
+            mimo.channel_numden(1, 0).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_leading_zeros_ignored() {
+        // Given
+        // 1/(s + 2) stored with padded coefficients
+        let tf =
+            ContinousTransferFunction::from_numden(&[0.0, 0.0, 1.0], &[0.0, 1.0, 2.0]).unwrap();
+
+        // When
+        let ss = StateSpace::from_tf(&tf, CanonicalForm::Controllable).unwrap();
+
+        // Then
+        assert_eq!(1, ss.states());
+        assert_relative_eq!(-2.0, ss.a()[(0, 0)]);
+        let ctf = ss.to_ctf().unwrap();
+        assert_coeffs(&[1.0], ctf.numerator());
+        assert_coeffs(&[1.0, 2.0], ctf.denominator());
+    }
+}
@@ -0,0 +1,426 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use num::Float;
+
+use crate::{
+    error::ControlError,
+    util::poly::{convolve, poly_add},
+};
+
+use super::{
+    TimeDomain, TransferFunction, ctf::ContinousTransferFunction, dtf::DiscreteTransferFunction,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeedbackSign {
+    Negative,
+    Positive,
+}
+
+/// Transfer functions that can be rebuilt from new coefficients in their own time domain
+pub trait Interconnect<T: Float>: TransferFunction<T> + Sized {
+    fn with_numden(
+        &self,
+        numerator: &[T],
+        denominator: &[T],
+        delay: T,
+    ) -> Result<Self, ControlError>;
+
+    fn negated(&self) -> Self;
+}
+
+impl<T: Float> Interconnect<T> for ContinousTransferFunction<T> {
+    fn with_numden(
+        &self,
+        numerator: &[T],
+        denominator: &[T],
+        delay: T,
+    ) -> Result<Self, ControlError> {
+        ContinousTransferFunction::from_numden(numerator, denominator)?.with_delay(delay)
+    }
+
+    fn negated(&self) -> Self {
+        ContinousTransferFunction::negated(self)
+    }
+}
+
+impl<T: Float> Interconnect<T> for DiscreteTransferFunction<T> {
+    // Discrete delays are already part of the polynomials as z^-N, so `delay` is always zero
+    fn with_numden(
+        &self,
+        numerator: &[T],
+        denominator: &[T],
+        _delay: T,
+    ) -> Result<Self, ControlError> {
+        DiscreteTransferFunction::from_numden(
+            numerator.to_vec(),
+            denominator.to_vec(),
+            self.sample_time(),
+        )
+    }
+
+    fn negated(&self) -> Self {
+        DiscreteTransferFunction::negated(self)
+    }
+}
+
+pub fn check_time_domains<T: Float>(
+    a: &dyn TransferFunction<T>,
+    b: &dyn TransferFunction<T>,
+) -> Result<(), ControlError> {
+    check_same_domain(a.time_domain(), b.time_domain())
+}
+
+pub fn check_same_domain<T: Float>(a: TimeDomain<T>, b: TimeDomain<T>) -> Result<(), ControlError> {
+    match (a, b) {
+        (TimeDomain::Continous, TimeDomain::Continous) => Ok(()),
+        (
+            TimeDomain::Discrete { sample_time: left },
+            TimeDomain::Discrete { sample_time: right },
+        ) => {
+            if left == right {
+                Ok(())
+            } else {
+                Err(ControlError::SampleTimeMismatch {
+                    left: left.to_f64().unwrap(),
+                    right: right.to_f64().unwrap(),
+                })
+            }
+        }
+        _ => Err(ControlError::TimeDomainMismatch),
+    }
+}
+
+fn scale<T: Float>(coeffs: &[T], factor: T) -> Vec<T> {
+    coeffs.iter().map(|&c| c * factor).collect()
+}
+
+// e^(-s*t1) * a + e^(-s*t2) * b only factors back into a delayed rational function when t1 == t2
+fn check_delays<T: Float>(
+    a: &dyn TransferFunction<T>,
+    b: &dyn TransferFunction<T>,
+) -> Result<(), ControlError> {
+    if a.delay() == b.delay() {
+        Ok(())
+    } else {
+        Err(ControlError::DelayMismatch {
+            left: a.delay().to_f64().unwrap(),
+            right: b.delay().to_f64().unwrap(),
+        })
+    }
+}
+
+/// a * b, transport delays add up
+pub fn series<T: Float, S: Interconnect<T>>(a: &S, b: &S) -> Result<S, ControlError> {
+    check_time_domains(a, b)?;
+    a.with_numden(
+        &convolve(a.numerator(), b.numerator()),
+        &convolve(a.denominator(), b.denominator()),
+        a.delay() + b.delay(),
+    )
+}
+
+/// a + b, both must have the same transport delay
+pub fn parallel<T: Float, S: Interconnect<T>>(a: &S, b: &S) -> Result<S, ControlError> {
+    check_time_domains(a, b)?;
+    check_delays(a, b)?;
+    a.with_numden(
+        &poly_add(
+            &convolve(a.numerator(), b.denominator()),
+            &convolve(b.numerator(), a.denominator()),
+        ),
+        &convolve(a.denominator(), b.denominator()),
+        a.delay(),
+    )
+}
+
+/// Closed loop of g in the forward path and h in the return path, g / (1 + gh) for negative feedback
+pub fn feedback<T: Float, S: Interconnect<T>>(
+    g: &S,
+    h: &S,
+    sign: FeedbackSign,
+) -> Result<S, ControlError> {
+    check_time_domains(g, h)?;
+    // A delay inside the loop makes the characteristic equation transcendental
+    if g.delay() != T::zero() || h.delay() != T::zero() {
+        return Err(ControlError::DelayNotSupported);
+    }
+    let loop_sign = match sign {
+        FeedbackSign::Negative => T::one(),
+        FeedbackSign::Positive => -T::one(),
+    };
+    g.with_numden(
+        &convolve(g.numerator(), h.denominator()),
+        &poly_add(
+            &convolve(g.denominator(), h.denominator()),
+            &scale(&convolve(g.numerator(), h.numerator()), loop_sign),
+        ),
+        T::zero(),
+    )
+}
+
+// The inverse of a delay is a time advance, which is not causal
+fn invert<T: Float, S: Interconnect<T>>(a: &S) -> Result<S, ControlError> {
+    if a.delay() != T::zero() {
+        return Err(ControlError::DelayNotSupported);
+    }
+    a.with_numden(a.denominator(), a.numerator(), T::zero())
+}
+
+macro_rules! impl_tf_ops {
+    ($tf:ident) => {
+        impl<T: Float> Mul for &$tf<T> {
+            type Output = Result<$tf<T>, ControlError>;
+
+            fn mul(self, rhs: Self) -> Self::Output {
+                series(self, rhs)
+            }
+        }
+
+        impl<T: Float> Add for &$tf<T> {
+            type Output = Result<$tf<T>, ControlError>;
+
+            fn add(self, rhs: Self) -> Self::Output {
+                parallel(self, rhs)
+            }
+        }
+
+        impl<T: Float> Sub for &$tf<T> {
+            type Output = Result<$tf<T>, ControlError>;
+
+            fn sub(self, rhs: Self) -> Self::Output {
+                parallel(self, &rhs.negated())
+            }
+        }
+
+        impl<T: Float> Div for &$tf<T> {
+            type Output = Result<$tf<T>, ControlError>;
+
+            fn div(self, rhs: Self) -> Self::Output {
+                series(self, &invert(rhs)?)
+            }
+        }
+
+        impl<T: Float> Neg for &$tf<T> {
+            type Output = $tf<T>;
+
+            fn neg(self) -> Self::Output {
+                self.negated()
+            }
+        }
+
+        impl<T: Float> Mul for $tf<T> {
+            type Output = Result<$tf<T>, ControlError>;
+
+            fn mul(self, rhs: Self) -> Self::Output {
+                &self * &rhs
+            }
+        }
+
+        impl<T: Float> Add for $tf<T> {
+            type Output = Result<$tf<T>, ControlError>;
+
+            fn add(self, rhs: Self) -> Self::Output {
+                &self + &rhs
+            }
+        }
+
+        impl<T: Float> Sub for $tf<T> {
+            type Output = Result<$tf<T>, ControlError>;
+
+            fn sub(self, rhs: Self) -> Self::Output {
+                &self - &rhs
+            }
+        }
+
+        impl<T: Float> Div for $tf<T> {
+            type Output = Result<$tf<T>, ControlError>;
+
+            fn div(self, rhs: Self) -> Self::Output {
+                &self / &rhs
+            }
+        }
+
+        impl<T: Float> Neg for $tf<T> {
+            type Output = $tf<T>;
+
+            fn neg(self) -> Self::Output {
+                -&self
+            }
+        }
+    };
+}
+
+impl_tf_ops!(ContinousTransferFunction);
+impl_tf_ops!(DiscreteTransferFunction);
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    fn assert_coeffs(expected: &[f64], actual: &[f64]) {
+        assert_eq!(expected.len(), actual.len());
+        for (e, a) in expected.iter().zip(actual.iter()) {
+            assert_relative_eq!(e, a, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_series() {
+        // Given
+        // G1(s) = 1 / (s + 1), G2(s) = 2 / (s + 2)
+        let g1 = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0]).unwrap();
+        let g2 = ContinousTransferFunction::from_numden(&[2.0], &[1.0, 2.0]).unwrap();
+
+        // When
+        let result = (&g1 * &g2).unwrap();
+
+        // Then
+        // G(s) = 2 / (s^2 + 3s + 2)
+        assert_coeffs(&[2.0], result.numerator());
+        assert_coeffs(&[1.0, 3.0, 2.0], result.denominator());
+        assert_eq!(2, result.poles().len());
+    }
+
+    #[test]
+    fn test_parallel_and_difference() {
+        // Given
+        // G1(s) = 1 / (s + 1), G2(s) = 1 / (s + 2)
+        let g1 = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0]).unwrap();
+        let g2 = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 2.0]).unwrap();
+
+        // When
+        let sum = (&g1 + &g2).unwrap();
+        let difference = (g1 - g2).unwrap();
+
+        // Then
+        // G1 + G2 = (2s + 3) / (s^2 + 3s + 2), G1 - G2 = 1 / (s^2 + 3s + 2)
+        assert_coeffs(&[2.0, 3.0], sum.numerator());
+        assert_coeffs(&[1.0, 3.0, 2.0], sum.denominator());
+        assert_coeffs(&[1.0], difference.numerator());
+        assert_coeffs(&[1.0, 3.0, 2.0], difference.denominator());
+    }
+
+    #[test]
+    fn test_division_and_negation() {
+        // Given
+        // G1(s) = 1 / (s + 1), G2(s) = (s + 3) / (s + 2)
+        let g1 = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0]).unwrap();
+        let g2 = ContinousTransferFunction::from_numden(&[1.0, 3.0], &[1.0, 2.0]).unwrap();
+
+        // When
+        let quotient = (&g1 / &g2).unwrap();
+        let negated = -g1;
+
+        // Then
+        // G1 / G2 = (s + 2) / (s^2 + 4s + 3)
+        assert_coeffs(&[1.0, 2.0], quotient.numerator());
+        assert_coeffs(&[1.0, 4.0, 3.0], quotient.denominator());
+        assert_coeffs(&[-1.0], negated.numerator());
+    }
+
+    #[test]
+    fn test_feedback() {
+        // Given
+        // G(s) = 1 / (s + 1), H(s) = 1
+        let g = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0]).unwrap();
+        let h = ContinousTransferFunction::from_numden(&[1.0], &[1.0]).unwrap();
+
+        // When
+        let negative = feedback(&g, &h, FeedbackSign::Negative).unwrap();
+        let positive = feedback(&g, &h, FeedbackSign::Positive).unwrap();
+
+        // Then
+        // G / (1 + G) = 1 / (s + 2), G / (1 - G) = 1 / s
+        assert_coeffs(&[1.0], negative.numerator());
+        assert_coeffs(&[1.0, 2.0], negative.denominator());
+        assert_coeffs(&[1.0], positive.numerator());
+        assert_coeffs(&[1.0, 0.0], positive.denominator());
+    }
+
+    #[test]
+    fn test_discrete_series() {
+        // Given
+        let g1 = DiscreteTransferFunction::from_numden(vec![1.0], vec![1.0, -0.5], 0.1).unwrap();
+        let g2 =
+            DiscreteTransferFunction::from_numden(vec![1.0, 0.0], vec![1.0, -0.2], 0.1).unwrap();
+
+        // When
+        let result = (g1 * g2).unwrap();
+
+        // Then
+        assert_coeffs(&[1.0, 0.0], result.numerator());
+        assert_coeffs(&[1.0, -0.7, 0.1], result.denominator());
+        assert_relative_eq!(0.1, result.sample_time());
+    }
+
+    #[test]
+    fn test_sample_time_mismatch() {
+        // Given
+        let g1 = DiscreteTransferFunction::from_numden(vec![1.0], vec![1.0, -0.5], 0.1).unwrap();
+        let g2 = DiscreteTransferFunction::from_numden(vec![1.0], vec![1.0, -0.5], 0.01).unwrap();
+
+        // When
+        let series = &g1 * &g2;
+        let closed_loop = feedback(&g1, &g2, FeedbackSign::Negative);
+
+        // Then
+        let expected = ControlError::SampleTimeMismatch {
+            left: 0.1,
+            right: 0.01,
+        };
+        assert_eq!(Err(expected.clone()), series.map(|_| ()));
+        assert_eq!(Err(expected), closed_loop.map(|_| ()));
+    }
+
+    #[test]
+    fn test_time_domain_mismatch() {
+        // Given
+        let ctf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0]).unwrap();
+        let dtf = DiscreteTransferFunction::from_numden(vec![1.0], vec![1.0, -0.5], 0.1).unwrap();
+
+        // When
+        let result = check_time_domains(&ctf, &dtf);
+
+        // Then
+        assert_eq!(Err(ControlError::TimeDomainMismatch), result);
+    }
+
+    #[test]
+    fn test_delays() {
+        // Given
+        // G1(s) = e^(-0.1s) / (s + 1), G2(s) = e^(-0.2s) / (s + 2)
+        let g1 = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0])
+            .unwrap()
+            .with_delay(0.1)
+            .unwrap();
+        let g2 = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 2.0])
+            .unwrap()
+            .with_delay(0.2)
+            .unwrap();
+
+        // When
+        let product = (&g1 * &g2).unwrap();
+        let sum = &g1 + &g2;
+        let closed_loop = feedback(&g1, &g2, FeedbackSign::Negative);
+        let quotient = &g1 / &g2;
+
+        // Then
+        assert_relative_eq!(0.3, product.delay(), epsilon = 1e-15);
+        assert_eq!(
+            Err(ControlError::DelayMismatch {
+                left: 0.1,
+                right: 0.2
+            }),
+            sum.map(|_| ())
+        );
+        assert_eq!(
+            Err(ControlError::DelayNotSupported),
+            closed_loop.map(|_| ())
+        );
+        assert_eq!(Err(ControlError::DelayNotSupported), quotient.map(|_| ()));
+        assert_relative_eq!(0.1, (-g1).delay());
+    }
+}
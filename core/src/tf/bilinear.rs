@@ -1,57 +1,155 @@
-use num::Float;
+use num::{Float, complex::Complex};
 
-use crate::util::poly::{binomial_expansion, convolve, poly_add};
+use crate::{
+    error::ControlError,
+    util::poly::{mobius_substitution, trim_leading_zeros},
+};
 
-pub fn bilinear_transform<T: Float>(num_s: &[T], den_s: &[T], t_sample: T) -> (Vec<T>, Vec<T>) {
-    let m = num_s.len() - 1;
-    let n = den_s.len() - 1;
-    let max_ord = m.max(n);
+use super::{
+    traits::{check_numden, check_sample_time},
+    zpk::ZeroPoleGain,
+};
 
-    let mut num_z = vec![0.0; max_ord + 1];
-    let mut den_z = vec![0.0; max_ord + 1];
+// Above this order the substitution sums terms (z - 1)^k (z + 1)^(n - k) whose binomial
+// coefficients grow like 2^n and cancel, so the poles and zeroes are mapped one by one instead
+const POLE_ZERO_MAPPING_ORDER: usize = 8;
 
-    let scale = 2.0 / t_sample.to_f64().unwrap();
+pub fn bilinear_transform<T: Float>(
+    num_s: &[T],
+    den_s: &[T],
+    t_sample: T,
+) -> Result<(Vec<T>, Vec<T>), ControlError> {
+    check_sample_time(t_sample)?;
+    scaled_bilinear_transform(num_s, den_s, tustin_scale(t_sample))
+}
 
-    // TODO: wrap in function
-    // Get numerator coefficients
-    for (k, &coeff) in num_s.iter().enumerate() {
-        let ord = m - k;
-        let mult = coeff.to_f64().unwrap() * scale.powi(ord as i32);
-        let poly_a = binomial_expansion(ord, true);
-        let poly_b = binomial_expansion(max_ord - ord, false);
-        let contrib: Vec<f64> = convolve(&poly_a, &poly_b)
-            .iter()
-            .map(|c| c * mult)
-            .collect();
-        num_z = poly_add(&num_z, &contrib);
-    }
+// Tustin with frequency pre-warping, s = (w_p / tan(w_p * T / 2)) * (z - 1)/(z + 1)
+// The continuous and discrete responses match exactly at w_p instead of only at DC,
+// w_p = 0 reduces to the plain transform
+pub fn prewarped_bilinear_transform<T: Float>(
+    num_s: &[T],
+    den_s: &[T],
+    t_sample: T,
+    omega_p: T,
+) -> Result<(Vec<T>, Vec<T>), ControlError> {
+    scaled_bilinear_transform(num_s, den_s, prewarped_scale(t_sample, omega_p)?)
+}
 
-    // Get denominator coefficients
-    for (l, &coeff) in den_s.iter().enumerate() {
-        let ord = n - l;
-        let mult = coeff.to_f64().unwrap() * scale.powi(ord as i32);
-        let poly_a = binomial_expansion(ord, true);
-        let poly_b = binomial_expansion(max_ord - ord, false);
-        let contrib: Vec<f64> = convolve(&poly_a, &poly_b)
-            .iter()
-            .map(|c| c * mult)
-            .collect();
-        den_z = poly_add(&den_z, &contrib);
+/// Tustin applied to poles, zeroes and gain directly, without forming polynomial coefficients
+pub fn bilinear_transform_zpk<T: Float>(
+    zpk: &ZeroPoleGain<T>,
+    t_sample: T,
+) -> Result<ZeroPoleGain<T>, ControlError> {
+    check_sample_time(t_sample)?;
+    map_zpk(zpk, tustin_scale(t_sample))
+}
+
+/// Pre-warped Tustin applied to poles, zeroes and gain directly
+pub fn prewarped_bilinear_transform_zpk<T: Float>(
+    zpk: &ZeroPoleGain<T>,
+    t_sample: T,
+    omega_p: T,
+) -> Result<ZeroPoleGain<T>, ControlError> {
+    map_zpk(zpk, prewarped_scale(t_sample, omega_p)?)
+}
+
+fn tustin_scale<T: Float>(t_sample: T) -> T {
+    T::from(2.0).unwrap() / t_sample
+}
+
+fn prewarped_scale<T: Float>(t_sample: T, omega_p: T) -> Result<T, ControlError> {
+    check_sample_time(t_sample)?;
+    if omega_p == T::zero() {
+        return Ok(tustin_scale(t_sample));
+    }
+    let nyquist = T::from(std::f64::consts::PI).unwrap() / t_sample;
+    if !(omega_p > T::zero() && omega_p < nyquist) {
+        return Err(ControlError::InvalidPrewarpFrequency {
+            omega_p: omega_p.to_f64().unwrap(),
+        });
     }
+    Ok(omega_p / (omega_p * t_sample / T::from(2.0).unwrap()).tan())
+}
+
+fn scaled_bilinear_transform<T: Float>(
+    num_s: &[T],
+    den_s: &[T],
+    scale: T,
+) -> Result<(Vec<T>, Vec<T>), ControlError> {
+    check_numden(num_s, den_s)?;
+    let num_s = trim_leading_zeros(num_s);
+    let den_s = trim_leading_zeros(den_s);
+    let order = num_s.len().max(den_s.len()) - 1;
+    let zero_numerator = num_s.iter().all(|c| c.is_zero());
+
+    let (num_z, den_z) = if order > POLE_ZERO_MAPPING_ORDER && !zero_numerator {
+        let zpk = ZeroPoleGain::from_numden(&num_s, &den_s)?;
+        map_zpk(&zpk, scale)?.to_numden()
+    } else {
+        // s = scale * (z - 1)/(z + 1)
+        let mobius = (scale, -scale, T::one(), T::one());
+        (
+            mobius_substitution(&num_s, order, mobius),
+            mobius_substitution(&den_s, order, mobius),
+        )
+    };
 
     // Normalize
+    let den_z = trim_leading_zeros(&den_z);
     let norm = den_z[0];
-    num_z.iter_mut().for_each(|c| *c /= norm);
-    den_z.iter_mut().for_each(|c| *c /= norm);
+    Ok((
+        num_z.iter().map(|&c| c / norm).collect(),
+        den_z.iter().map(|&c| c / norm).collect(),
+    ))
+}
 
-    let f_to_float = |f: &[f64]| -> Vec<T> { f.iter().map(|&x| T::from(x).unwrap()).collect() };
+// s - r = ((k - r)z - (k + r)) / (z + 1), so a root r maps to z = (k + r)/(k - r) with a
+// factor (k - r) in the gain, and the difference in root counts leaves factors of (z + 1)
+// A root at r = k maps to infinity and only contributes the constant -2k
+fn map_zpk<T: Float>(zpk: &ZeroPoleGain<T>, scale: T) -> Result<ZeroPoleGain<T>, ControlError> {
+    let (mut zeroes, zeroes_gain) = map_roots(zpk.zeroes(), scale);
+    let (mut poles, poles_gain) = map_roots(zpk.poles(), scale);
+    let minus_one = Complex::new(-T::one(), T::zero());
+    let excess = zpk.poles().len() as isize - zpk.zeroes().len() as isize;
+    if excess > 0 {
+        zeroes.extend(std::iter::repeat_n(minus_one, excess as usize));
+    } else {
+        poles.extend(std::iter::repeat_n(minus_one, (-excess) as usize));
+    }
+    let gain = zpk.gain() * (zeroes_gain / poles_gain).re;
+    ZeroPoleGain::new(&zeroes, &poles, gain)
+}
 
-    (f_to_float(&num_z), f_to_float(&den_z))
+fn map_roots<T: Float>(roots: &[Complex<T>], scale: T) -> (Vec<Complex<T>>, Complex<T>) {
+    let tol = T::epsilon().sqrt() * scale;
+    let mut gain = Complex::new(T::one(), T::zero());
+    let mut mapped = Vec::with_capacity(roots.len());
+    for &root in roots {
+        let factor = -root + scale;
+        if factor.norm() < tol {
+            gain = gain * -(root + scale);
+        } else {
+            gain = gain * factor;
+            mapped.push((root + scale) / factor);
+        }
+    }
+    (mapped, gain)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::tf::bilinear::bilinear_transform;
+    use approx::assert_relative_eq;
+    use num::complex::Complex64;
+
+    use crate::error::ControlError;
+    use crate::filter::sallenkey::butterworth_poles;
+    use crate::tf::{
+        TransferFunction,
+        bilinear::{bilinear_transform, bilinear_transform_zpk, prewarped_bilinear_transform},
+        ctf::ContinousTransferFunction,
+        dtf::DiscreteTransferFunction,
+        zpk::ZeroPoleGain,
+    };
 
     #[test]
     fn test_bilinear_transform_first_order_lowpass() {
@@ -59,10 +157,131 @@ mod tests {
         let den = vec![1.0, 1.0]; // s + 1
         let sample_time = 1.0;
 
-        let (num_z, den_z) = bilinear_transform(&num, &den, sample_time);
+        let (num_z, den_z) = bilinear_transform(&num, &den, sample_time).unwrap();
 
         assert_eq!(vec![1.0 / 3.0, 1.0 / 3.0], num_z);
         assert_eq!(vec![1.0, -1.0 / 3.0], den_z);
     }
-}
 
+    #[test]
+    fn test_prewarped_bilinear_transform_matches_at_critical_frequency() {
+        // Given
+        // H(s) = 100 / (s^2 + 10s + 100), T = 0.1, w_p = 10 rad/s (about a third of Nyquist)
+        let ctf = ContinousTransferFunction::from_numden(&[100.0], &[1.0, 10.0, 100.0]).unwrap();
+        let (sample_time, omega_p) = (0.1, 10.0);
+
+        // When
+        let (num_z, den_z) =
+            prewarped_bilinear_transform(ctf.numerator(), ctf.denominator(), sample_time, omega_p)
+                .unwrap();
+        let (num_plain, den_plain) =
+            bilinear_transform(ctf.numerator(), ctf.denominator(), sample_time).unwrap();
+
+        // Then
+        let expected = ctf.evaluate(Complex64::new(0.0, omega_p));
+        let prewarped = DiscreteTransferFunction::from_numden(num_z, den_z, sample_time)
+            .unwrap()
+            .frequency_response(&[omega_p])[0];
+        let plain = DiscreteTransferFunction::from_numden(num_plain, den_plain, sample_time)
+            .unwrap()
+            .frequency_response(&[omega_p])[0];
+        assert_relative_eq!(expected.re, prewarped.re, epsilon = 1e-9);
+        assert_relative_eq!(expected.im, prewarped.im, epsilon = 1e-9);
+        assert!((expected - plain).norm() > 1e-3);
+    }
+
+    #[test]
+    fn test_bilinear_transform_rejects_bad_input() {
+        // Then
+        assert_eq!(
+            Err(ControlError::EmptyPolynomial {
+                polynomial: "numerator"
+            }),
+            bilinear_transform(&[], &[1.0, 1.0], 0.1)
+        );
+        assert_eq!(
+            Err(ControlError::InvalidSampleTime { sample_time: 0.0 }),
+            bilinear_transform(&[1.0], &[1.0, 1.0], 0.0)
+        );
+        assert_eq!(
+            Err(ControlError::InvalidPrewarpFrequency { omega_p: 40.0 }),
+            prewarped_bilinear_transform(&[1.0], &[1.0, 1.0], 0.1, 40.0)
+        );
+    }
+
+    #[test]
+    fn test_bilinear_transform_high_order() {
+        // Given
+        // 24th-order Butterworth low-pass, w_c = 1 rad/s, T = 1
+        let poles = butterworth_poles(24, 1.0);
+        let ctf = ContinousTransferFunction::from_pz(&poles, &[]).unwrap();
+        let sample_time = 1.0;
+
+        // When
+        let (num_z, den_z) =
+            bilinear_transform(ctf.numerator(), ctf.denominator(), sample_time).unwrap();
+
+        // Then
+        // Tustin maps z = e^(jwT) to s = j(2/T)tan(wT/2)
+        let dtf = DiscreteTransferFunction::from_numden(num_z, den_z, sample_time).unwrap();
+        for w in [0.2, 0.8, 1.0, 1.2] {
+            let s = Complex64::new(0.0, 2.0 / sample_time * (w * sample_time / 2.0).tan());
+            let expected = ctf.evaluate(s);
+            let result = dtf.frequency_response(&[w])[0];
+            assert!((expected - result).norm() < 1e-7 * expected.norm());
+        }
+    }
+
+    #[test]
+    fn test_bilinear_transform_zpk_clustered_poles() {
+        // Given
+        // 24th-order Butterworth low-pass, w_c = 1 rad/s, T = 0.01
+        // The discrete poles crowd around z = 1, where the expanded polynomial loses all accuracy
+        let poles = butterworth_poles(24, 1.0);
+        let zpk = ZeroPoleGain::new(&[], &poles, 1.0).unwrap();
+        let sample_time = 0.01;
+
+        // When
+        let zpk_z = bilinear_transform_zpk(&zpk, sample_time).unwrap();
+
+        // Then
+        assert_eq!(24, zpk_z.zeroes().len());
+        assert!(
+            zpk_z
+                .zeroes()
+                .iter()
+                .all(|&z| z == Complex64::new(-1.0, 0.0))
+        );
+        assert!(zpk_z.poles().iter().all(|p| p.norm() < 1.0));
+        for w in [0.5, 1.0, 1.5] {
+            let s = Complex64::new(0.0, 2.0 / sample_time * (w * sample_time / 2.0).tan());
+            let z = Complex64::new(0.0, w * sample_time).exp();
+            let expected = zpk.evaluate(s);
+            let result = zpk_z.evaluate(z);
+            assert_relative_eq!(expected.re, result.re, epsilon = 1e-9 * expected.norm());
+            assert_relative_eq!(expected.im, result.im, epsilon = 1e-9 * expected.norm());
+        }
+    }
+
+    #[test]
+    fn test_bilinear_transform_f32_matches_f64() {
+        // Given
+        // 4th-order Butterworth low-pass, w_c = 1 rad/s, T = 0.5
+        let ctf = ContinousTransferFunction::from_pz(&butterworth_poles(4, 1.0), &[]).unwrap();
+        let num_f32: Vec<f32> = ctf.numerator().iter().map(|&c| c as f32).collect();
+        let den_f32: Vec<f32> = ctf.denominator().iter().map(|&c| c as f32).collect();
+
+        // When
+        let (num_z, den_z) = bilinear_transform(ctf.numerator(), ctf.denominator(), 0.5).unwrap();
+        let (num_z_f32, den_z_f32) = bilinear_transform(&num_f32, &den_f32, 0.5f32).unwrap();
+
+        // Then
+        for (a, &b) in num_z
+            .iter()
+            .chain(&den_z)
+            .zip(num_z_f32.iter().chain(&den_z_f32))
+        {
+            assert_relative_eq!(*a, b as f64, epsilon = 1e-5, max_relative = 1e-5);
+        }
+    }
+}
@@ -1,36 +1,124 @@
 use num::{Float, complex::Complex};
 
-use crate::util::poly::reduce_to_real;
+use crate::{error::ControlError, util::poly::Polynomial};
 
 use super::{
     TimeDomain, TransferFunction,
-    traits::{coeff_from_pz, roots},
+    delay::pade,
+    discretization::{DiscretizationMethod, inverse_discretize},
+    dtf::DiscreteTransferFunction,
+    parse::{Variable, parse_rational},
+    traits::check_numden,
+    zpk::{ZeroPoleGain, check_conjugate_pairs},
 };
 
+/// Serialized as a `TransferFunctionRecord` with the `serde` feature
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        into = "crate::tf::schema::TransferFunctionRecord<T>",
+        try_from = "crate::tf::schema::TransferFunctionRecord<T>"
+    )
+)]
 pub struct ContinousTransferFunction<T: Float> {
-    numerator: Vec<T>,
-    denominator: Vec<T>,
+    numerator: Polynomial<T>,
+    denominator: Polynomial<T>,
     poles: Vec<Complex<T>>,
     zeroes: Vec<Complex<T>>,
+    delay: T,
 }
 
 impl<T: Float> ContinousTransferFunction<T> {
-    pub fn from_numden(numerator: &[T], denominator: &[T]) -> Self {
-        Self {
-            poles: roots(denominator),
-            zeroes: roots(numerator),
-            numerator: numerator.to_vec(),
-            denominator: denominator.to_vec(),
-        }
+    pub fn from_numden(numerator: &[T], denominator: &[T]) -> Result<Self, ControlError> {
+        check_numden(numerator, denominator)?;
+        let numerator = Polynomial::new(numerator);
+        let denominator = Polynomial::new(denominator);
+        Ok(Self {
+            poles: denominator.roots()?,
+            zeroes: numerator.roots()?,
+            numerator,
+            denominator,
+            delay: T::zero(),
+        })
     }
 
-    pub fn from_pz(poles: &[Complex<T>], zeroes: &[Complex<T>]) -> Self {
-        Self {
-            numerator: reduce_to_real(&coeff_from_pz(&zeroes)),
-            denominator: reduce_to_real(&coeff_from_pz(&poles)),
+    pub fn from_pz(poles: &[Complex<T>], zeroes: &[Complex<T>]) -> Result<Self, ControlError> {
+        check_conjugate_pairs(poles)?;
+        check_conjugate_pairs(zeroes)?;
+        let numerator = Polynomial::from_roots(zeroes);
+        let denominator = Polynomial::from_roots(poles);
+        check_numden(numerator.coeffs(), denominator.coeffs())?;
+        Ok(Self {
+            numerator,
+            denominator,
             poles: poles.to_vec(),
             zeroes: zeroes.to_vec(),
+            delay: T::zero(),
+        })
+    }
+
+    pub fn from_zpk(zpk: &ZeroPoleGain<T>) -> Result<Self, ControlError> {
+        let (numerator, denominator) = zpk.to_numden();
+        check_numden(&numerator, &denominator)?;
+        Ok(Self {
+            numerator: Polynomial::new(&numerator),
+            denominator: Polynomial::new(&denominator),
+            poles: zpk.poles().to_vec(),
+            zeroes: zpk.zeroes().to_vec(),
+            delay: T::zero(),
+        })
+    }
+
+    pub fn from_dtf(
+        dtf: &DiscreteTransferFunction<T>,
+        method: DiscretizationMethod<T>,
+    ) -> Result<Self, ControlError> {
+        let (numerator, denominator) = inverse_discretize(
+            dtf.numerator(),
+            dtf.denominator(),
+            dtf.sample_time(),
+            method,
+        )?;
+        Self::from_numden(&numerator, &denominator)
+    }
+
+    /// Parses an expression in s such as `10*(s+2)/(s*(s+10))`, see `parse_transfer_function`
+    pub fn parse(text: &str) -> Result<Self, ControlError> {
+        let (_, numerator, denominator) = parse_rational(text, Some(Variable::S))?;
+        Self::from_numden(&numerator, &denominator)
+    }
+
+    /// Adds a transport delay e^(-s * delay), in seconds
+    pub fn with_delay(mut self, delay: T) -> Result<Self, ControlError> {
+        if delay < T::zero() || !delay.is_finite() {
+            return Err(ControlError::InvalidDelay {
+                delay: delay.to_f64().unwrap(),
+            });
+        }
+        self.delay = delay;
+        Ok(self)
+    }
+
+    /// Replaces the transport delay with its Pade approximation of the given order,
+    /// giving a rational transfer function with the same poles plus the approximation's
+    pub fn pade(&self, order: usize) -> Result<Self, ControlError> {
+        if self.delay == T::zero() {
+            return Ok(self.clone());
+        }
+        let (pade_num, pade_den) = pade(self.delay, order);
+        Self::from_numden(
+            (&self.numerator * &Polynomial::new(&pade_num)).coeffs(),
+            (&self.denominator * &Polynomial::new(&pade_den)).coeffs(),
+        )
+    }
+
+    // -G has the same poles and zeroes, so there is nothing to recompute or validate
+    pub(crate) fn negated(&self) -> Self {
+        Self {
+            numerator: -&self.numerator,
+            ..self.clone()
         }
     }
 }
@@ -41,11 +129,11 @@ impl<T: Float> TransferFunction<T> for ContinousTransferFunction<T> {
     }
 
     fn numerator(&self) -> &[T] {
-        &self.numerator
+        self.numerator.coeffs()
     }
 
     fn denominator(&self) -> &[T] {
-        &self.denominator
+        self.denominator.coeffs()
     }
 
     fn frequency_response(&self, omega_range: &[T]) -> Vec<Complex<T>> {
@@ -58,6 +146,19 @@ impl<T: Float> TransferFunction<T> for ContinousTransferFunction<T> {
             .collect()
     }
 
+    fn evaluate(&self, s: Complex<T>) -> Complex<T> {
+        let rational = self.numerator.eval_complex(s) / self.denominator.eval_complex(s);
+        if self.delay == T::zero() {
+            rational
+        } else {
+            rational * (-s * self.delay).exp()
+        }
+    }
+
+    fn delay(&self) -> T {
+        self.delay
+    }
+
     fn poles(&self) -> &[Complex<T>] {
         &self.poles
     }
@@ -71,7 +172,7 @@ impl<T: Float> TransferFunction<T> for ContinousTransferFunction<T> {
         let h = self.evaluate(s);
         let gain = T::one() / h.norm();
         if gain != T::one() {
-            self.numerator.iter_mut().for_each(|c| *c = *c * gain);
+            self.numerator = self.numerator.scale(gain);
         }
     }
 }
@@ -87,7 +188,7 @@ mod tests {
     fn test_evaluate() {
         // Given
         // H(s) = 1 / (s + 1), s = j
-        let tf = ContinousTransferFunction::from_numden(&vec![1.0], &vec![1.0, 1.0]);
+        let tf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0]).unwrap();
         let s = Complex64::new(0.0, 1.0);
 
         // When
@@ -99,11 +200,51 @@ mod tests {
         assert_eq!(expected, result)
     }
 
+    #[test]
+    fn test_delay_shifts_phase_only() {
+        // Given
+        // H(s) = e^(-0.5s) / (s + 1), w = 2 rad/s
+        let rational = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0]).unwrap();
+        let delayed = rational.clone().with_delay(0.5).unwrap();
+        let w = 2.0;
+
+        // When
+        let h = delayed.frequency_response(&[w])[0];
+
+        // Then
+        let expected = rational.frequency_response(&[w])[0];
+        assert_relative_eq!(expected.norm(), h.norm(), epsilon = 1e-12);
+        assert_relative_eq!(expected.arg() - w * 0.5, h.arg(), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_pade() {
+        // Given
+        // H(s) = e^(-0.2s) / (s + 1)
+        let tf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0])
+            .unwrap()
+            .with_delay(0.2)
+            .unwrap();
+        let s = Complex64::new(0.0, 1.5);
+
+        // When
+        let approx = tf.pade(4).unwrap();
+
+        // Then
+        assert_eq!(0.0, approx.delay());
+        assert_eq!(5, approx.poles().len());
+        assert_eq!(4, approx.zeroes().len());
+        let expected = tf.evaluate(s);
+        let result = approx.evaluate(s);
+        assert_relative_eq!(expected.re, result.re, epsilon = 1e-8);
+        assert_relative_eq!(expected.im, result.im, epsilon = 1e-8);
+    }
+
     #[test]
     fn test_order() {
         // Given
         // H(s) = 1 / (s^2 + s + 1)
-        let tf = ContinousTransferFunction::from_numden(&vec![1.0], &vec![1.0, 1.0, 1.0]);
+        let tf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0, 1.0]).unwrap();
 
         // When
         let order = tf.order();
@@ -113,10 +254,10 @@ mod tests {
     }
 
     #[test]
-    fn test_order_empty_denominator() {
+    fn test_order_constant() {
         // Given
         // H(s) = 1
-        let tf = ContinousTransferFunction::from_numden(&vec![1.0], &vec![]);
+        let tf = ContinousTransferFunction::from_numden(&[1.0], &[1.0]).unwrap();
 
         // When
         let order = tf.order();
@@ -125,11 +266,59 @@ mod tests {
         assert_eq!(0, order)
     }
 
+    #[test]
+    fn test_order_ignores_negligible_leading_coefficients() {
+        // Given
+        // The leading coefficients vanish relative to the others at f64 precision
+        let tf = ContinousTransferFunction::from_numden(&[1e-20, 1.0], &[0.0, 1.0, 1.0]).unwrap();
+
+        // When
+        let order = tf.order();
+
+        // Then
+        assert_eq!(1, order);
+        assert_eq!(&[1.0], tf.numerator());
+        assert_eq!(&[1.0, 1.0], tf.denominator());
+    }
+
+    #[test]
+    fn test_invalid_coefficients_rejected() {
+        // Given
+        let empty = ContinousTransferFunction::from_numden(&[1.0], &[]);
+        let zero = ContinousTransferFunction::from_numden(&[1.0], &[0.0, 0.0]);
+        let nan = ContinousTransferFunction::from_numden(&[f64::NAN], &[1.0, 1.0]);
+
+        // Then
+        assert_eq!(
+            Some(ControlError::EmptyPolynomial {
+                polynomial: "denominator"
+            }),
+            empty.err()
+        );
+        assert_eq!(Some(ControlError::ZeroDenominator), zero.err());
+        assert_eq!(Some(ControlError::NonFiniteCoefficient), nan.err());
+    }
+
+    #[test]
+    fn test_invalid_delay_rejected() {
+        // Given
+        let tf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0]).unwrap();
+
+        // When
+        let result = tf.with_delay(-0.1);
+
+        // Then
+        assert_eq!(
+            Some(ControlError::InvalidDelay { delay: -0.1 }),
+            result.err()
+        );
+    }
+
     #[test]
     fn test_poles_zeroes() {
         // Given
         // H(s) = (s + 1) / (s + 2)(s + 3) = (s + 1) / (s^2 + 5s + 6)
-        let tf = ContinousTransferFunction::from_numden(&vec![1.0, 1.0], &vec![1.0, 5.0, 6.0]);
+        let tf = ContinousTransferFunction::from_numden(&[1.0, 1.0], &[1.0, 5.0, 6.0]).unwrap();
 
         // When
         let zeroes = tf.zeroes();
@@ -141,12 +330,114 @@ mod tests {
         assert_relative_eq!(-1.0, zeroes[0].re);
         assert_relative_eq!(0.0, zeroes[0].im);
         // Denominator: (s + 2)(s + 3) => poles: -2, -3
-        // TODO: Asserted root order in Vec determined experimentally when using ndarray_linalg::eig()
-        //       Unit test should be independent of the chosen algorithm implementation
+        // Roots are sorted by ascending real part whichever eigenvalue solver is used
         assert_eq!(2, poles.len());
         assert_relative_eq!(-3.0, poles[0].re);
         assert_relative_eq!(0.0, poles[0].im);
         assert_relative_eq!(-2.0, poles[1].re);
         assert_relative_eq!(0.0, poles[1].im);
     }
+
+    #[test]
+    fn test_from_zpk_keeps_gain() {
+        // Given
+        // H(s) = 4(s + 1) / (s + 2)
+        let zpk = ZeroPoleGain::new(
+            &[Complex64::new(-1.0, 0.0)],
+            &[Complex64::new(-2.0, 0.0)],
+            4.0,
+        )
+        .unwrap();
+
+        // When
+        let tf = ContinousTransferFunction::from_zpk(&zpk).unwrap();
+
+        // Then
+        assert_eq!(&[4.0, 4.0], tf.numerator());
+        assert_eq!(&[1.0, 2.0], tf.denominator());
+        assert_relative_eq!(2.0, tf.evaluate(Complex64::new(0.0, 0.0)).re);
+        assert_eq!(zpk, tf.zpk());
+    }
+
+    #[test]
+    fn test_from_zpk_rejects_non_finite_gain() {
+        // Given
+        let zpk = ZeroPoleGain::new(&[], &[Complex64::new(-2.0, 0.0)], f64::NAN).unwrap();
+
+        // Then
+        assert_eq!(
+            Some(ControlError::NonFiniteCoefficient),
+            ContinousTransferFunction::from_zpk(&zpk).err()
+        );
+        assert_eq!(
+            Some(ControlError::NonFiniteCoefficient),
+            DiscreteTransferFunction::from_zpk(&zpk, 0.1).err()
+        );
+    }
+
+    fn assert_round_trip(method: DiscretizationMethod<f64>) {
+        // Given
+        // H(s) = (s + 3) / (s^2 + 2s + 5)
+        let num = [1.0, 3.0];
+        let den = [1.0, 2.0, 5.0];
+        let ctf = ContinousTransferFunction::from_numden(&num, &den).unwrap();
+
+        // When
+        let dtf = DiscreteTransferFunction::from_ctf_with_method(&ctf, 0.1, method).unwrap();
+        let result = ContinousTransferFunction::from_dtf(&dtf, method).unwrap();
+
+        // Then
+        assert_eq!(num.len(), result.numerator().len());
+        assert_eq!(den.len(), result.denominator().len());
+        let norm = result.denominator()[0];
+        for (expected, actual) in num.iter().zip(result.numerator()) {
+            assert_relative_eq!(*expected, actual / norm, epsilon = 1e-8);
+        }
+        for (expected, actual) in den.iter().zip(result.denominator()) {
+            assert_relative_eq!(*expected, actual / norm, epsilon = 1e-8);
+        }
+    }
+
+    #[test]
+    fn test_from_dtf_tustin_round_trip() {
+        assert_round_trip(DiscretizationMethod::Tustin);
+        assert_round_trip(DiscretizationMethod::TustinPrewarped { omega_p: 2.0 });
+    }
+
+    #[test]
+    fn test_from_dtf_zero_order_hold_round_trip() {
+        assert_round_trip(DiscretizationMethod::ZeroOrderHold);
+    }
+
+    #[test]
+    fn test_from_dtf_matched_round_trip() {
+        assert_round_trip(DiscretizationMethod::MatchedPoleZero);
+    }
+
+    #[test]
+    fn test_from_dtf_euler_round_trip() {
+        assert_round_trip(DiscretizationMethod::ForwardEuler);
+        assert_round_trip(DiscretizationMethod::BackwardEuler);
+    }
+
+    #[test]
+    fn test_from_dtf_integrator_round_trip() {
+        // Given
+        // H(s) = 2 / s(s + 4)
+        let ctf = ContinousTransferFunction::from_numden(&[2.0], &[1.0, 4.0, 0.0]).unwrap();
+
+        for method in [
+            DiscretizationMethod::ZeroOrderHold,
+            DiscretizationMethod::MatchedPoleZero,
+        ] {
+            // When
+            let dtf = DiscreteTransferFunction::from_ctf_with_method(&ctf, 0.05, method).unwrap();
+            let result = ContinousTransferFunction::from_dtf(&dtf, method).unwrap();
+
+            // Then
+            assert_relative_eq!(2.0, result.numerator()[0], epsilon = 1e-8);
+            assert_relative_eq!(4.0, result.denominator()[1], epsilon = 1e-8);
+            assert_relative_eq!(0.0, result.denominator()[2], epsilon = 1e-8);
+        }
+    }
 }
@@ -0,0 +1,116 @@
+use num::Float;
+
+use crate::error::ControlError;
+
+// Pade approximation of e^(-s * delay) with numerator and denominator of the given order
+// (see https://en.wikipedia.org/wiki/Pad%C3%A9_table#Exponential_function)
+// c_k = (2n - k)! n! / ((2n)! k! (n - k)!), N(s) = sum((-1)^k c_k (s * delay)^k), D(s) = sum(c_k (s * delay)^k)
+pub fn pade<T: Float>(delay: T, order: usize) -> (Vec<T>, Vec<T>) {
+    let mut coeffs = vec![T::one()];
+    for k in 1..=order {
+        // c_k / c_(k-1) = (n - k + 1) / (k (2n - k + 1))
+        let ratio = T::from(order - k + 1).unwrap() / T::from(k * (2 * order - k + 1)).unwrap();
+        coeffs.push(coeffs[k - 1] * ratio * delay);
+    }
+    let denominator: Vec<T> = coeffs.iter().rev().copied().collect();
+    let numerator = denominator
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| if (order - i) % 2 == 1 { -c } else { c })
+        .collect();
+    (numerator, denominator)
+}
+
+/// Number of samples of `sample_time` in `delay`
+/// Delays that are not a whole number of samples up to rounding errors give `FractionalDelay`
+/// instead of being rounded, rounding would silently drop delays below half a sample
+pub fn delay_samples<T: Float>(delay: T, sample_time: T) -> Result<usize, ControlError> {
+    let samples = delay / sample_time;
+    let whole = samples.round();
+    if (samples - whole).abs() > T::epsilon().sqrt() * whole.max(T::one()) {
+        return Err(ControlError::FractionalDelay {
+            delay: delay.to_f64().unwrap(),
+            sample_time: sample_time.to_f64().unwrap(),
+        });
+    }
+    Ok(whole.to_usize().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use num::complex::Complex64;
+
+    use super::*;
+    use crate::util::poly::horner;
+
+    #[test]
+    fn test_pade_second_order() {
+        // Given
+        // e^(-s * tau) ~= (1 - s*tau/2 + (s*tau)^2/12) / (1 + s*tau/2 + (s*tau)^2/12)
+        let tau = 0.5;
+
+        // When
+        let (num, den) = pade(tau, 2);
+
+        // Then
+        let expected_den = [tau * tau / 12.0, tau / 2.0, 1.0];
+        let expected_num = [tau * tau / 12.0, -tau / 2.0, 1.0];
+        for i in 0..3 {
+            assert_relative_eq!(expected_num[i], num[i], epsilon = 1e-15);
+            assert_relative_eq!(expected_den[i], den[i], epsilon = 1e-15);
+        }
+    }
+
+    #[test]
+    fn test_pade_phase_accuracy() {
+        // Given
+        // Higher orders follow the exact phase -w * tau further out
+        let tau = 1.0;
+        let s = Complex64::new(0.0, 2.0);
+        let exact = (-s * tau).exp();
+
+        // When
+        let errors: Vec<f64> = [1, 3, 5]
+            .iter()
+            .map(|&order| {
+                let (num, den) = pade(tau, order);
+                (horner(&num, s) / horner(&den, s) - exact).norm()
+            })
+            .collect();
+
+        // Then
+        assert!(errors[1] < errors[0]);
+        assert!(errors[2] < 1e-4);
+    }
+
+    #[test]
+    fn test_delay_samples() {
+        assert_eq!(Ok(3), delay_samples(0.3, 0.1));
+        assert_eq!(Ok(0), delay_samples(0.0, 0.1));
+        assert_eq!(
+            Err(ControlError::FractionalDelay {
+                delay: 0.24,
+                sample_time: 0.1
+            }),
+            delay_samples(0.24, 0.1)
+        );
+    }
+
+    #[test]
+    fn test_delay_below_half_a_sample_rejected() {
+        // Given
+        // 0.4 T would round to no delay at all
+        let sample_time = 0.1;
+        let delay = 0.4 * sample_time;
+
+        // When
+        let result = delay_samples(delay, sample_time);
+
+        // Then
+        assert_eq!(
+            Err(ControlError::FractionalDelay { delay, sample_time }),
+            result
+        );
+    }
+}
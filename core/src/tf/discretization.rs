@@ -0,0 +1,575 @@
+use ndarray::{Array2, s};
+use num::{Float, complex::Complex};
+
+use crate::{
+    error::ControlError,
+    ss::realization::{controllable_canonical, realization_to_numden},
+    util::{
+        linalg::{expm, logm, matmul},
+        poly::{mobius_substitution, reduce_to_real, trim_leading_zeros},
+    },
+};
+
+use super::{
+    bilinear::{bilinear_transform, prewarped_bilinear_transform},
+    traits::{check_numden, check_sample_time, coeff_from_pz, roots},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "method", rename_all = "snake_case")
+)]
+pub enum DiscretizationMethod<T: Float> {
+    /// Bilinear transform, s = (2/T)(z - 1)/(z + 1)
+    Tustin,
+    /// Bilinear transform pre-warped to match the continuous response exactly at omega_p (rad/s)
+    TustinPrewarped { omega_p: T },
+    /// Input held constant between samples
+    ZeroOrderHold,
+    /// Input interpolated linearly between samples (triangle hold)
+    FirstOrderHold,
+    /// Impulse response sampled and scaled by T, h[k] = T * h(kT)
+    ImpulseInvariant,
+    /// Poles and finite zeroes mapped through z = e^(sT), zeroes at infinity mapped to z = -1
+    MatchedPoleZero,
+    /// s = (z - 1)/T
+    ForwardEuler,
+    /// s = (z - 1)/(Tz)
+    BackwardEuler,
+}
+
+pub fn discretize<T: Float>(
+    num_s: &[T],
+    den_s: &[T],
+    t_sample: T,
+    method: DiscretizationMethod<T>,
+) -> Result<(Vec<T>, Vec<T>), ControlError> {
+    let (num_s, den_s) = &validate(num_s, den_s, t_sample)?;
+    let state_space_based = matches!(
+        method,
+        DiscretizationMethod::ZeroOrderHold
+            | DiscretizationMethod::FirstOrderHold
+            | DiscretizationMethod::ImpulseInvariant
+    );
+    if state_space_based && num_s.len() > den_s.len() {
+        return Err(ControlError::ImproperTransferFunction);
+    }
+    match method {
+        DiscretizationMethod::Tustin => bilinear_transform(num_s, den_s, t_sample),
+        DiscretizationMethod::TustinPrewarped { omega_p } => {
+            prewarped_bilinear_transform(num_s, den_s, t_sample, omega_p)
+        }
+        DiscretizationMethod::ZeroOrderHold => Ok(zero_order_hold(num_s, den_s, t_sample)),
+        DiscretizationMethod::FirstOrderHold => Ok(first_order_hold(num_s, den_s, t_sample)),
+        DiscretizationMethod::ImpulseInvariant => Ok(impulse_invariant(num_s, den_s, t_sample)),
+        DiscretizationMethod::MatchedPoleZero => matched_pole_zero(num_s, den_s, t_sample),
+        DiscretizationMethod::ForwardEuler => Ok(substitute(
+            num_s,
+            den_s,
+            (t_sample.recip(), -t_sample.recip(), T::zero(), T::one()),
+        )),
+        DiscretizationMethod::BackwardEuler => Ok(substitute(
+            num_s,
+            den_s,
+            (T::one(), -T::one(), t_sample, T::zero()),
+        )),
+    }
+}
+
+// Checks the inputs and drops leading zeroes, which only lower the degree
+fn validate<T: Float>(num: &[T], den: &[T], t_sample: T) -> Result<(Vec<T>, Vec<T>), ControlError> {
+    check_numden(num, den)?;
+    check_sample_time(t_sample)?;
+    Ok((trim_leading_zeros(num), trim_leading_zeros(den)))
+}
+
+// Applies s = (az + b)/(cz + d) to both polynomials and normalizes the denominator
+fn substitute<T: Float>(num_s: &[T], den_s: &[T], mobius: (T, T, T, T)) -> (Vec<T>, Vec<T>) {
+    let order = num_s.len().max(den_s.len()).saturating_sub(1);
+    let num_z = mobius_substitution(num_s, order, mobius);
+    let den_z = mobius_substitution(den_s, order, mobius);
+    normalize(&num_z, &den_z)
+}
+
+fn normalize<T: Float>(num: &[T], den: &[T]) -> (Vec<T>, Vec<T>) {
+    let num = trim_leading_zeros(num);
+    let den = trim_leading_zeros(den);
+    let norm = den[0];
+    (
+        num.iter().map(|&c| c / norm).collect(),
+        den.iter().map(|&c| c / norm).collect(),
+    )
+}
+
+fn zero_order_hold<T: Float>(num_s: &[T], den_s: &[T], t_sample: T) -> (Vec<T>, Vec<T>) {
+    let (a, b, c, d) = controllable_canonical(num_s, den_s);
+    let n = a.nrows();
+
+    // exp([[A, B], [0, 0]] * T) = [[Phi, Gamma], [0, I]]
+    let mut block = Array2::zeros((n + 1, n + 1));
+    block
+        .slice_mut(s![..n, ..n])
+        .assign(&a.mapv(|x| x * t_sample));
+    block
+        .slice_mut(s![..n, n..])
+        .assign(&b.mapv(|x| x * t_sample));
+    let block_exp = expm(&block);
+    let phi = block_exp.slice(s![..n, ..n]).to_owned();
+    let gamma = block_exp.slice(s![..n, n..]).to_owned();
+
+    realization_to_numden(&phi, &gamma, &c, d)
+}
+
+// Triangle hold equivalent (see Franklin, Powell, Workman - Digital Control of Dynamic Systems, 6.3.2)
+fn first_order_hold<T: Float>(num_s: &[T], den_s: &[T], t_sample: T) -> (Vec<T>, Vec<T>) {
+    let (a, b, c, d) = controllable_canonical(num_s, den_s);
+    let n = a.nrows();
+
+    // exp([[A, B, 0], [0, 0, I/T], [0, 0, 0]] * T) = [[Phi, Gamma1, Gamma2], [0, I, I], [0, 0, I]]
+    let mut block = Array2::zeros((n + 2, n + 2));
+    block
+        .slice_mut(s![..n, ..n])
+        .assign(&a.mapv(|x| x * t_sample));
+    block
+        .slice_mut(s![..n, n..n + 1])
+        .assign(&b.mapv(|x| x * t_sample));
+    block[(n, n + 1)] = T::one();
+    let block_exp = expm(&block);
+    let phi = block_exp.slice(s![..n, ..n]).to_owned();
+    let gamma_1 = block_exp.slice(s![..n, n..n + 1]).to_owned();
+    let gamma_2 = block_exp.slice(s![..n, n + 1..]).to_owned();
+
+    // Change of state x' = x - Gamma2 * u removes the dependency on u[k + 1]
+    let b_d = gamma_1 + matmul(&(&phi - &Array2::eye(n)), &gamma_2);
+    let d_d = d + matmul(&c, &gamma_2)[(0, 0)];
+
+    realization_to_numden(&phi, &b_d, &c, d_d)
+}
+
+// T * Z{C e^(AkT) B} = T * C z (zI - Phi)^-1 B = T * (C Phi (zI - Phi)^-1 B + CB)
+// A direct feedthrough term is passed through unchanged
+fn impulse_invariant<T: Float>(num_s: &[T], den_s: &[T], t_sample: T) -> (Vec<T>, Vec<T>) {
+    let (a, b, c, d) = controllable_canonical(num_s, den_s);
+    let phi = expm(&a.mapv(|x| x * t_sample));
+
+    let c_d = matmul(&c, &phi).mapv(|x| x * t_sample);
+    let d_d = d + t_sample * matmul(&c, &b)[(0, 0)];
+
+    realization_to_numden(&phi, &b, &c_d, d_d)
+}
+
+fn matched_pole_zero<T: Float>(
+    num_s: &[T],
+    den_s: &[T],
+    t_sample: T,
+) -> Result<(Vec<T>, Vec<T>), ControlError> {
+    let poles_s = roots(den_s)?;
+    let zeroes_s = roots(num_s)?;
+    let map = |r: &Complex<T>| (r * t_sample).exp();
+
+    let poles_z: Vec<Complex<T>> = poles_s.iter().map(map).collect();
+    let mut zeroes_z: Vec<Complex<T>> = zeroes_s.iter().map(map).collect();
+    if zeroes_z.len() < poles_z.len() {
+        zeroes_z.resize(poles_z.len(), Complex::new(-T::one(), T::zero()));
+    }
+
+    // Near DC s ~ (z - 1)/T, so H(s) ~ G_s * s^e and H(z) ~ G_z * (z - 1)^e match when G_z = G_s / T^e
+    let (gain_s, excess) = reduced_gain(&zeroes_s, &poles_s, T::zero());
+    let (gain_z, _) = reduced_gain(&zeroes_z, &poles_z, T::one());
+    let gain = (gain_s / gain_z).re * (num_s[0] / den_s[0]) / t_sample.powi(excess);
+
+    let num_z: Vec<T> = reduce_to_real(&coeff_from_pz(&zeroes_z))
+        .iter()
+        .map(|&c| c * gain)
+        .collect();
+    let den_z = reduce_to_real(&coeff_from_pz(&poles_z));
+    Ok((num_z, den_z))
+}
+
+// Gain of the monic factored form prod(x - z) / prod(x - p) evaluated at x = point, skipping
+// roots that lie on the point, together with the excess of zeroes over poles that were skipped
+fn reduced_gain<T: Float>(
+    zeroes: &[Complex<T>],
+    poles: &[Complex<T>],
+    point: T,
+) -> (Complex<T>, i32) {
+    let tol = T::epsilon().sqrt();
+    let point = Complex::new(point, T::zero());
+    let one = Complex::new(T::one(), T::zero());
+    let product = |roots: &[Complex<T>]| {
+        roots
+            .iter()
+            .map(|&r| point - r)
+            .fold((one, 0), |(acc, skipped), factor| {
+                if factor.norm() < tol {
+                    (acc, skipped + 1)
+                } else {
+                    (acc * factor, skipped)
+                }
+            })
+    };
+    let (zeroes_gain, zeroes_skipped) = product(zeroes);
+    let (poles_gain, poles_skipped) = product(poles);
+    (zeroes_gain / poles_gain, zeroes_skipped - poles_skipped)
+}
+
+// Converts a discrete transfer function back to continuous time by inverting the given method
+// First-order hold and impulse invariance have no closed-form inverse and give `NoInverse`,
+// poles without a real logarithm give `NoContinuousEquivalent`
+pub fn inverse_discretize<T: Float>(
+    num_z: &[T],
+    den_z: &[T],
+    t_sample: T,
+    method: DiscretizationMethod<T>,
+) -> Result<(Vec<T>, Vec<T>), ControlError> {
+    let (num_z, den_z) = &validate(num_z, den_z, t_sample)?;
+    let two = T::from(2.0).unwrap();
+    match method {
+        // z = (1 + sT/2)/(1 - sT/2)
+        DiscretizationMethod::Tustin => Ok(substitute(
+            num_z,
+            den_z,
+            (t_sample / two, T::one(), -t_sample / two, T::one()),
+        )),
+        DiscretizationMethod::TustinPrewarped { omega_p } => {
+            let k = if omega_p == T::zero() {
+                t_sample / two
+            } else {
+                (omega_p * t_sample / two).tan() / omega_p
+            };
+            Ok(substitute(num_z, den_z, (k, T::one(), -k, T::one())))
+        }
+        DiscretizationMethod::ZeroOrderHold => {
+            if num_z.len() > den_z.len() {
+                return Err(ControlError::ImproperTransferFunction);
+            }
+            inverse_zero_order_hold(num_z, den_z, t_sample)
+        }
+        DiscretizationMethod::MatchedPoleZero => inverse_matched_pole_zero(num_z, den_z, t_sample),
+        // z = 1 + sT
+        DiscretizationMethod::ForwardEuler => Ok(substitute(
+            num_z,
+            den_z,
+            (t_sample, T::one(), T::zero(), T::one()),
+        )),
+        // z = 1/(1 - sT)
+        DiscretizationMethod::BackwardEuler => Ok(substitute(
+            num_z,
+            den_z,
+            (T::zero(), T::one(), -t_sample, T::one()),
+        )),
+        DiscretizationMethod::FirstOrderHold => Err(ControlError::NoInverse {
+            method: "first-order hold",
+        }),
+        DiscretizationMethod::ImpulseInvariant => Err(ControlError::NoInverse {
+            method: "impulse invariance",
+        }),
+    }
+}
+
+// log([[Phi, Gamma], [0, I]]) / T = [[A, B], [0, 0]]
+fn inverse_zero_order_hold<T: Float>(
+    num_z: &[T],
+    den_z: &[T],
+    t_sample: T,
+) -> Result<(Vec<T>, Vec<T>), ControlError> {
+    let (phi, gamma, c, d) = controllable_canonical(num_z, den_z);
+    let n = phi.nrows();
+
+    let mut block = Array2::<T>::eye(n + 1);
+    block.slice_mut(s![..n, ..n]).assign(&phi);
+    block.slice_mut(s![..n, n..]).assign(&gamma);
+    let block_log = logm(&block)
+        .ok_or(ControlError::NoContinuousEquivalent)?
+        .mapv(|x| x / t_sample);
+    let a = block_log.slice(s![..n, ..n]).to_owned();
+    let b = block_log.slice(s![..n, n..]).to_owned();
+
+    Ok(realization_to_numden(&a, &b, &c, d))
+}
+
+// Poles and zeroes mapped through s = ln(z)/T, zeroes at z = -1 and z = 0 are returned to infinity
+fn inverse_matched_pole_zero<T: Float>(
+    num_z: &[T],
+    den_z: &[T],
+    t_sample: T,
+) -> Result<(Vec<T>, Vec<T>), ControlError> {
+    let tol = T::epsilon().sqrt();
+    let poles_z = roots(den_z)?;
+    let (zeroes_at_minus_one, zeroes_z): (Vec<Complex<T>>, Vec<Complex<T>>) = roots(num_z)?
+        .into_iter()
+        .filter(|z| z.norm() >= tol)
+        .partition(|z| (z + T::one()).norm() < tol);
+    // ln(z) is undefined at 0 and complex on the negative real axis
+    let no_equivalent = |r: &Complex<T>| r.norm() < tol || (r.re < T::zero() && r.im.abs() < tol);
+    if poles_z.iter().chain(&zeroes_z).any(no_equivalent) {
+        return Err(ControlError::NoContinuousEquivalent);
+    }
+    let map = |r: &Complex<T>| r.ln() / t_sample;
+    let poles_s: Vec<Complex<T>> = poles_z.iter().map(map).collect();
+    let zeroes_s: Vec<Complex<T>> = zeroes_z.iter().map(map).collect();
+
+    // Same low-frequency matching as the forward mapping, G_s = G_z * T^e
+    let zeroes_at_minus_one = zeroes_at_minus_one.len();
+    let (gain_z, excess) = reduced_gain(&zeroes_z, &poles_z, T::one());
+    let gain = (gain_z / reduced_gain(&zeroes_s, &poles_s, T::zero()).0).re
+        * (num_z[0] / den_z[0])
+        * T::from(2.0).unwrap().powi(zeroes_at_minus_one as i32)
+        * t_sample.powi(excess);
+
+    let num_s: Vec<T> = reduce_to_real(&coeff_from_pz(&zeroes_s))
+        .iter()
+        .map(|&c| c * gain)
+        .collect();
+    let den_s = reduce_to_real(&coeff_from_pz(&poles_s));
+    Ok((num_s, den_s))
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use num::complex::Complex64;
+
+    use super::*;
+    use crate::tf::TransferFunction;
+    use crate::tf::ctf::ContinousTransferFunction;
+    use crate::tf::dtf::DiscreteTransferFunction;
+
+    const T_SAMPLE: f64 = 0.1;
+
+    // H(s) = 2 / (s + 1)(s + 2), unity DC gain
+    fn plant() -> ContinousTransferFunction<f64> {
+        ContinousTransferFunction::from_numden(&[2.0], &[1.0, 3.0, 2.0]).unwrap()
+    }
+
+    fn dc_gain(dtf: &DiscreteTransferFunction<f64>) -> f64 {
+        dtf.evaluate(Complex64::new(1.0, 0.0)).re
+    }
+
+    fn assert_poles(dtf: &DiscreteTransferFunction<f64>, expected: &[f64]) {
+        let mut poles: Vec<f64> = dtf.poles().iter().map(|p| p.re).collect();
+        poles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mut expected = expected.to_vec();
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(expected.len(), poles.len());
+        for (p, e) in poles.iter().zip(expected.iter()) {
+            assert_relative_eq!(e, p, epsilon = 1e-9);
+        }
+        dtf.poles()
+            .iter()
+            .for_each(|p| assert_relative_eq!(0.0, p.im, epsilon = 1e-9));
+    }
+
+    fn discretize_plant(method: DiscretizationMethod<f64>) -> DiscreteTransferFunction<f64> {
+        DiscreteTransferFunction::from_ctf_with_method(&plant(), T_SAMPLE, method).unwrap()
+    }
+
+    fn exp_poles() -> [f64; 2] {
+        [(-T_SAMPLE).exp(), (-2.0 * T_SAMPLE).exp()]
+    }
+
+    #[test]
+    fn test_tustin() {
+        let dtf = discretize_plant(DiscretizationMethod::Tustin);
+
+        let tustin = |p: f64| (1.0 + p * T_SAMPLE / 2.0) / (1.0 - p * T_SAMPLE / 2.0);
+        assert_relative_eq!(1.0, dc_gain(&dtf), epsilon = 1e-12);
+        assert_poles(&dtf, &[tustin(-1.0), tustin(-2.0)]);
+    }
+
+    #[test]
+    fn test_zero_order_hold() {
+        let dtf = discretize_plant(DiscretizationMethod::ZeroOrderHold);
+
+        assert_relative_eq!(1.0, dc_gain(&dtf), epsilon = 1e-12);
+        assert_poles(&dtf, &exp_poles());
+    }
+
+    #[test]
+    fn test_zero_order_hold_first_order() {
+        // Given
+        // H(s) = 1 / (s + 1)
+        let ctf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0]).unwrap();
+
+        // When
+        let (num, den) = discretize(
+            ctf.numerator(),
+            ctf.denominator(),
+            T_SAMPLE,
+            DiscretizationMethod::ZeroOrderHold,
+        )
+        .unwrap();
+
+        // Then
+        // H(z) = (1 - e^-T) / (z - e^-T)
+        let e = (-T_SAMPLE).exp();
+        assert_eq!(1, num.len());
+        assert_relative_eq!(1.0 - e, num[0], epsilon = 1e-12);
+        assert_relative_eq!(1.0, den[0]);
+        assert_relative_eq!(-e, den[1], epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_first_order_hold() {
+        let dtf = discretize_plant(DiscretizationMethod::FirstOrderHold);
+
+        assert_relative_eq!(1.0, dc_gain(&dtf), epsilon = 1e-12);
+        assert_poles(&dtf, &exp_poles());
+    }
+
+    #[test]
+    fn test_first_order_hold_first_order() {
+        // Given
+        // H(s) = 1 / (s + 1)
+        let ctf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0]).unwrap();
+
+        // When
+        let (num, den) = discretize(
+            ctf.numerator(),
+            ctf.denominator(),
+            T_SAMPLE,
+            DiscretizationMethod::FirstOrderHold,
+        )
+        .unwrap();
+
+        // Then
+        // H(z) = ((T + e - 1)z + (1 - e - Te)) / (T(z - e)), e = e^-T
+        let e = (-T_SAMPLE).exp();
+        assert_relative_eq!((T_SAMPLE + e - 1.0) / T_SAMPLE, num[0], epsilon = 1e-12);
+        assert_relative_eq!((1.0 - e - T_SAMPLE * e) / T_SAMPLE, num[1], epsilon = 1e-12);
+        assert_relative_eq!(-e, den[1], epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_impulse_invariant() {
+        let dtf = discretize_plant(DiscretizationMethod::ImpulseInvariant);
+
+        // h(t) = 2e^-t - 2e^-2t, so H(1) = T * sum(h(kT)) = 2T/(1 - e^-T) - 2T/(1 - e^-2T)
+        let [e1, e2] = exp_poles();
+        let expected_dc = 2.0 * T_SAMPLE / (1.0 - e1) - 2.0 * T_SAMPLE / (1.0 - e2);
+        assert_relative_eq!(expected_dc, dc_gain(&dtf), epsilon = 1e-12);
+        assert_poles(&dtf, &exp_poles());
+    }
+
+    #[test]
+    fn test_matched_pole_zero() {
+        let dtf = discretize_plant(DiscretizationMethod::MatchedPoleZero);
+
+        assert_relative_eq!(1.0, dc_gain(&dtf), epsilon = 1e-12);
+        assert_poles(&dtf, &exp_poles());
+        // Both zeroes at infinity are mapped to z = -1
+        assert_eq!(2, dtf.zeroes().len());
+        dtf.zeroes()
+            .iter()
+            .for_each(|z| assert_relative_eq!(-1.0, z.re, epsilon = 1e-6));
+    }
+
+    #[test]
+    fn test_matched_pole_zero_integrator() {
+        // Given
+        // H(s) = (s + 2) / s(s + 1)
+        let ctf = ContinousTransferFunction::from_numden(&[1.0, 2.0], &[1.0, 1.0, 0.0]).unwrap();
+
+        // When
+        let dtf = DiscreteTransferFunction::from_ctf_with_method(
+            &ctf,
+            T_SAMPLE,
+            DiscretizationMethod::MatchedPoleZero,
+        )
+        .unwrap();
+
+        // Then
+        // Near DC H(s) ~ 2/s and H(z) ~ 2T/(z - 1)
+        let z = Complex64::new(1.0 + 1e-7, 0.0);
+        let h = dtf.evaluate(z) * (z - 1.0);
+        assert_relative_eq!(2.0 * T_SAMPLE, h.re, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_forward_euler() {
+        let dtf = discretize_plant(DiscretizationMethod::ForwardEuler);
+
+        assert_relative_eq!(1.0, dc_gain(&dtf), epsilon = 1e-12);
+        assert_poles(&dtf, &[1.0 - T_SAMPLE, 1.0 - 2.0 * T_SAMPLE]);
+    }
+
+    #[test]
+    fn test_backward_euler() {
+        let dtf = discretize_plant(DiscretizationMethod::BackwardEuler);
+
+        assert_relative_eq!(1.0, dc_gain(&dtf), epsilon = 1e-12);
+        assert_poles(
+            &dtf,
+            &[1.0 / (1.0 + T_SAMPLE), 1.0 / (1.0 + 2.0 * T_SAMPLE)],
+        );
+    }
+
+    #[test]
+    fn test_no_inverse_rejected() {
+        // Given
+        let dtf = discretize_plant(DiscretizationMethod::ZeroOrderHold);
+
+        // When
+        let foh = inverse_discretize(
+            dtf.numerator(),
+            dtf.denominator(),
+            T_SAMPLE,
+            DiscretizationMethod::FirstOrderHold,
+        );
+        let impulse = inverse_discretize(
+            dtf.numerator(),
+            dtf.denominator(),
+            T_SAMPLE,
+            DiscretizationMethod::ImpulseInvariant,
+        );
+        let origin_pole = inverse_discretize(
+            &[1.0],
+            &[1.0, 0.0],
+            T_SAMPLE,
+            DiscretizationMethod::MatchedPoleZero,
+        );
+        // A pole at z = -0.5 has no real logarithm
+        let negative_pole = inverse_discretize(
+            &[1.0],
+            &[1.0, 0.5],
+            T_SAMPLE,
+            DiscretizationMethod::ZeroOrderHold,
+        );
+
+        // Then
+        assert_eq!(
+            Some(ControlError::NoInverse {
+                method: "first-order hold"
+            }),
+            foh.err()
+        );
+        assert_eq!(
+            Some(ControlError::NoInverse {
+                method: "impulse invariance"
+            }),
+            impulse.err()
+        );
+        assert_eq!(
+            Some(ControlError::NoContinuousEquivalent),
+            origin_pole.err()
+        );
+        assert_eq!(
+            Some(ControlError::NoContinuousEquivalent),
+            negative_pole.err()
+        );
+    }
+
+    #[test]
+    fn test_invalid_sample_time_rejected() {
+        // When
+        let result = discretize(&[1.0], &[1.0, 1.0], -0.1, DiscretizationMethod::Tustin);
+
+        // Then
+        assert_eq!(
+            Some(ControlError::InvalidSampleTime { sample_time: -0.1 }),
+            result.err()
+        );
+    }
+}
@@ -1,40 +1,145 @@
 use num::{Float, complex::Complex};
 
+use crate::{error::ControlError, util::poly::Polynomial};
+
 use super::{
     TimeDomain, TransferFunction,
-    bilinear::bilinear_transform,
-    ctf::{self, ContinousTransferFunction},
-    traits::roots,
+    ctf::ContinousTransferFunction,
+    delay::delay_samples,
+    discretization::{DiscretizationMethod, discretize},
+    parse::{Variable, parse_rational},
+    traits::{check_numden, check_sample_time},
+    zpk::ZeroPoleGain,
 };
 
+/// Serialized as a `TransferFunctionRecord` with the `serde` feature
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        into = "crate::tf::schema::TransferFunctionRecord<T>",
+        try_from = "crate::tf::schema::TransferFunctionRecord<T>"
+    )
+)]
 pub struct DiscreteTransferFunction<T: Float> {
-    numerator: Vec<T>,
-    denominator: Vec<T>,
+    numerator: Polynomial<T>,
+    denominator: Polynomial<T>,
     poles: Vec<Complex<T>>,
     zeroes: Vec<Complex<T>>,
     sample_time: T,
 }
 
 impl<T: Float> DiscreteTransferFunction<T> {
-    pub fn from_numden(numerator: Vec<T>, denominator: Vec<T>, sample_time: T) -> Self {
-        Self {
-            poles: roots(&denominator),
-            zeroes: roots(&numerator),
+    pub fn from_numden(
+        numerator: Vec<T>,
+        denominator: Vec<T>,
+        sample_time: T,
+    ) -> Result<Self, ControlError> {
+        check_numden(&numerator, &denominator)?;
+        check_sample_time(sample_time)?;
+        let numerator = Polynomial::from(numerator);
+        let denominator = Polynomial::from(denominator);
+        Ok(Self {
+            poles: denominator.roots()?,
+            zeroes: numerator.roots()?,
             numerator,
             denominator,
             sample_time,
-        }
+        })
+    }
+
+    pub fn from_zpk(zpk: &ZeroPoleGain<T>, sample_time: T) -> Result<Self, ControlError> {
+        check_sample_time(sample_time)?;
+        let (numerator, denominator) = zpk.to_numden();
+        check_numden(&numerator, &denominator)?;
+        Ok(Self {
+            numerator: Polynomial::from(numerator),
+            denominator: Polynomial::from(denominator),
+            poles: zpk.poles().to_vec(),
+            zeroes: zpk.zeroes().to_vec(),
+            sample_time,
+        })
+    }
+
+    pub fn from_ctf(
+        ctf: &ContinousTransferFunction<T>,
+        sample_time: T,
+    ) -> Result<Self, ControlError> {
+        Self::from_ctf_with_method(ctf, sample_time, DiscretizationMethod::Tustin)
     }
 
-    pub fn from_ctf(ctf: &ContinousTransferFunction<T>, sample_time: T) -> Self {
-        let (numerator, denominator) =
-            bilinear_transform(ctf.numerator(), ctf.denominator(), sample_time);
+    pub fn from_ctf_with_method(
+        ctf: &ContinousTransferFunction<T>,
+        sample_time: T,
+        method: DiscretizationMethod<T>,
+    ) -> Result<Self, ControlError> {
+        let (numerator, mut denominator) =
+            discretize(ctf.numerator(), ctf.denominator(), sample_time, method)?;
+        // The transport delay becomes z^-N, it must be a whole number of samples
+        let delay = delay_samples(ctf.delay(), sample_time)?;
+        denominator.extend(std::iter::repeat_n(T::zero(), delay));
         Self::from_numden(numerator, denominator, sample_time)
     }
 
+    /// Parses an expression in z or z^-1 such as `(1 - 0.5z^-1)/(1 - 0.9z^-1)`,
+    /// see `parse_transfer_function`
+    pub fn parse(text: &str, sample_time: T) -> Result<Self, ControlError> {
+        let (_, numerator, denominator) = parse_rational(text, Some(Variable::Z))?;
+        Self::from_numden(numerator, denominator, sample_time)
+    }
+
+    // Moves the system to a new sample time through its continuous equivalent under `method`
+    // First-order hold and impulse invariance have no inverse and give `NoInverse`
+    pub fn resample(
+        &self,
+        sample_time: T,
+        method: DiscretizationMethod<T>,
+    ) -> Result<Self, ControlError> {
+        // Poles at z = 0 beyond the numerator degree are the z^-N of a transport delay, they are
+        // carried over in seconds rather than mapped through the continuous equivalent
+        let denominator = self.denominator.coeffs();
+        let delay = denominator
+            .iter()
+            .rev()
+            .take_while(|c| c.is_zero())
+            .count()
+            .min(
+                self.denominator
+                    .degree()
+                    .saturating_sub(self.numerator.degree()),
+            );
+        let rational = Self::from_numden(
+            self.numerator.coeffs().to_vec(),
+            denominator[..denominator.len() - delay].to_vec(),
+            self.sample_time,
+        )?;
+        let ctf = ContinousTransferFunction::from_dtf(&rational, method)?
+            .with_delay(T::from(delay).unwrap() * self.sample_time)?;
+        Self::from_ctf_with_method(&ctf, sample_time, method)
+    }
+
     pub fn sample_time(&self) -> T {
         self.sample_time
     }
+
+    /// Nyquist frequency pi / T in rad/s
+    pub fn nyquist_frequency(&self) -> T {
+        T::from(std::f64::consts::PI).unwrap() / self.sample_time
+    }
+
+    /// Maps an angular frequency onto the unit circle, z = e^(jwT)
+    fn unit_circle_point(&self, w: T) -> Complex<T> {
+        Complex::from_polar(T::one(), w * self.sample_time)
+    }
+
+    // -G has the same poles and zeroes, so there is nothing to recompute or validate
+    pub(crate) fn negated(&self) -> Self {
+        Self {
+            numerator: -&self.numerator,
+            ..self.clone()
+        }
+    }
 }
 
 impl<T: Float> TransferFunction<T> for DiscreteTransferFunction<T> {
@@ -45,11 +150,11 @@ impl<T: Float> TransferFunction<T> for DiscreteTransferFunction<T> {
     }
 
     fn numerator(&self) -> &[T] {
-        &self.numerator
+        self.numerator.coeffs()
     }
 
     fn denominator(&self) -> &[T] {
-        &self.denominator
+        self.denominator.coeffs()
     }
 
     fn poles(&self) -> &[Complex<T>] {
@@ -60,11 +165,235 @@ impl<T: Float> TransferFunction<T> for DiscreteTransferFunction<T> {
         &self.zeroes
     }
 
+    // The response of a sampled system is periodic in 2pi / T, so frequencies above
+    // Nyquist wrap around the unit circle and return the aliased response
     fn frequency_response(&self, omega: &[T]) -> Vec<Complex<T>> {
-        todo!()
+        omega
+            .iter()
+            .map(|&w| self.evaluate(self.unit_circle_point(w)))
+            .collect()
     }
 
     fn normalize_at_w(&mut self, w: T) {
-        todo!()
+        let h = self.evaluate(self.unit_circle_point(w));
+        let gain = T::one() / h.norm();
+        if gain != T::one() {
+            self.numerator = self.numerator.scale(gain);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use num::complex::Complex64;
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    #[test]
+    fn test_frequency_response_matches_prewarped_prototype() {
+        // Given
+        // H(s) = 1 / (s^2 + 2s + 1), T = 0.1
+        // Tustin maps z = e^(jwT) to s = j(2/T)tan(wT/2)
+        let ctf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 2.0, 1.0]).unwrap();
+        let sample_time = 0.1;
+        let dtf = DiscreteTransferFunction::from_ctf(&ctf, sample_time).unwrap();
+        let omega = vec![0.0, 0.5, 1.0, 5.0, 20.0];
+
+        // When
+        let response = dtf.frequency_response(&omega);
+
+        // Then
+        for (h, &w) in response.iter().zip(omega.iter()) {
+            let w_c = 2.0 / sample_time * (w * sample_time / 2.0).tan();
+            let expected = ctf.evaluate(Complex64::new(0.0, w_c));
+            assert_relative_eq!(expected.re, h.re, epsilon = 1e-9);
+            assert_relative_eq!(expected.im, h.im, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_frequency_response_wraps_above_nyquist() {
+        // Given
+        // H(z) = 0.5 / (z - 0.5), T = 0.01
+        let dtf = DiscreteTransferFunction::from_numden(vec![0.5], vec![1.0, -0.5], 0.01).unwrap();
+        let w = 30.0;
+        let w_alias = w + 2.0 * dtf.nyquist_frequency();
+
+        // When
+        let response = dtf.frequency_response(&[w, w_alias]);
+
+        // Then
+        assert_relative_eq!(response[0].re, response[1].re, epsilon = 1e-9);
+        assert_relative_eq!(response[0].im, response[1].im, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_nyquist_frequency() {
+        // Given
+        let dtf = DiscreteTransferFunction::from_numden(vec![1.0], vec![1.0, -0.5], 0.1).unwrap();
+
+        // When
+        let w_n = dtf.nyquist_frequency();
+
+        // Then
+        assert_relative_eq!(10.0 * PI, w_n);
+    }
+
+    #[test]
+    fn test_normalize_at_w() {
+        // Given
+        // H(s) = 1 / (s + 1), discretized with T = 0.1
+        let ctf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0]).unwrap();
+        let mut dtf = DiscreteTransferFunction::from_ctf(&ctf, 0.1).unwrap();
+        dtf.numerator = dtf.numerator.scale(4.0);
+
+        // When
+        dtf.normalize_at_w(0.0);
+
+        // Then
+        // DC gain of the discretized system is preserved by the bilinear transform
+        let dc_gain = dtf.frequency_response(&[0.0])[0];
+        assert_relative_eq!(1.0, dc_gain.norm(), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_resample_matches_direct_discretization() {
+        // Given
+        // H(s) = 10 / (s^2 + 4s + 10), 1 kHz controller moved to a 4 kHz loop
+        let ctf = ContinousTransferFunction::from_numden(&[10.0], &[1.0, 4.0, 10.0]).unwrap();
+        let (t_old, t_new) = (1e-3, 2.5e-4);
+
+        for method in [
+            DiscretizationMethod::Tustin,
+            DiscretizationMethod::ZeroOrderHold,
+            DiscretizationMethod::MatchedPoleZero,
+        ] {
+            // When
+            let resampled = DiscreteTransferFunction::from_ctf_with_method(&ctf, t_old, method)
+                .unwrap()
+                .resample(t_new, method)
+                .unwrap();
+
+            // Then
+            let direct =
+                DiscreteTransferFunction::from_ctf_with_method(&ctf, t_new, method).unwrap();
+            assert_relative_eq!(t_new, resampled.sample_time());
+            assert_eq!(direct.denominator().len(), resampled.denominator().len());
+            for (expected, actual) in direct.numerator().iter().zip(resampled.numerator()) {
+                assert_relative_eq!(*expected, *actual, epsilon = 1e-8);
+            }
+            for (expected, actual) in direct.denominator().iter().zip(resampled.denominator()) {
+                assert_relative_eq!(*expected, *actual, epsilon = 1e-8);
+            }
+        }
+    }
+
+    #[test]
+    fn test_resample_keeps_delay() {
+        // Given
+        // H(s) = e^(-0.3s) / (s + 1), three samples of delay at T = 0.1 s and six at T = 0.05 s
+        let ctf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0])
+            .unwrap()
+            .with_delay(0.3)
+            .unwrap();
+        for method in [
+            DiscretizationMethod::Tustin,
+            DiscretizationMethod::ZeroOrderHold,
+            DiscretizationMethod::MatchedPoleZero,
+        ] {
+            let dtf = DiscreteTransferFunction::from_ctf_with_method(&ctf, 0.1, method).unwrap();
+
+            // When
+            let resampled = dtf.resample(0.05, method).unwrap();
+
+            // Then
+            let direct =
+                DiscreteTransferFunction::from_ctf_with_method(&ctf, 0.05, method).unwrap();
+            assert_eq!(8, resampled.denominator().len());
+            assert_eq!(direct.denominator().len(), resampled.denominator().len());
+            for (expected, actual) in direct.numerator().iter().zip(resampled.numerator()) {
+                assert_relative_eq!(*expected, *actual, epsilon = 1e-8);
+            }
+            for (expected, actual) in direct.denominator().iter().zip(resampled.denominator()) {
+                assert_relative_eq!(*expected, *actual, epsilon = 1e-8);
+            }
+        }
+    }
+
+    #[test]
+    fn test_resample_without_inverse_rejected() {
+        // Given
+        let ctf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 2.0]).unwrap();
+        let dtf = DiscreteTransferFunction::from_ctf_with_method(
+            &ctf,
+            0.1,
+            DiscretizationMethod::ImpulseInvariant,
+        )
+        .unwrap();
+
+        // When
+        let impulse = dtf.resample(0.05, DiscretizationMethod::ImpulseInvariant);
+        let foh = dtf.resample(0.05, DiscretizationMethod::FirstOrderHold);
+
+        // Then
+        assert_eq!(
+            Some(ControlError::NoInverse {
+                method: "impulse invariance"
+            }),
+            impulse.err()
+        );
+        assert_eq!(
+            Some(ControlError::NoInverse {
+                method: "first-order hold"
+            }),
+            foh.err()
+        );
+    }
+
+    #[test]
+    fn test_delay_becomes_whole_samples() {
+        // Given
+        // H(s) = e^(-0.3s) / (s + 1), T = 0.1
+        let ctf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0])
+            .unwrap()
+            .with_delay(0.3)
+            .unwrap();
+
+        // When
+        let dtf = DiscreteTransferFunction::from_ctf_with_method(
+            &ctf,
+            0.1,
+            DiscretizationMethod::ZeroOrderHold,
+        )
+        .unwrap();
+
+        // Then
+        // Three extra poles at the origin, z^-3
+        let origin_poles = dtf.poles().iter().filter(|p| p.norm() < 1e-12).count();
+        assert_eq!(3, origin_poles);
+        assert_eq!(4, dtf.order());
+        assert_relative_eq!(1.0, dtf.frequency_response(&[0.0])[0].re, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_resample_rejects_fractional_delay() {
+        // Given
+        // Three samples of delay at T = 0.1 s are 0.75 samples at T = 0.4 s
+        let ctf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0])
+            .unwrap()
+            .with_delay(0.3)
+            .unwrap();
+        let dtf = DiscreteTransferFunction::from_ctf(&ctf, 0.1).unwrap();
+
+        // When
+        let result = dtf.resample(0.4, DiscretizationMethod::Tustin);
+
+        // Then
+        assert!(matches!(
+            result.unwrap_err(),
+            ControlError::FractionalDelay { .. }
+        ));
     }
 }
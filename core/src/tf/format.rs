@@ -0,0 +1,589 @@
+use std::fmt;
+
+use num::{Float, complex::Complex};
+
+use super::{
+    TimeDomain, TransferFunction, ctf::ContinousTransferFunction, dtf::DiscreteTransferFunction,
+    zpk::ZeroPoleGain,
+};
+
+const DEFAULT_SIGNIFICANT_DIGITS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    /// Single line such as `(s + 1)/(s^2 + 2s + 5)`, which `parse_transfer_function` reads
+    /// back unless a transport delay is appended as `* e^(-0.5s)`
+    Plain,
+    /// Numerator over denominator on three lines with superscript powers, for monospace fonts
+    Unicode,
+    /// `\frac{s + 1}{s^{2} + 2s + 5}`
+    Latex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FormatOptions {
+    pub style: Style,
+    pub significant_digits: usize,
+    /// Discrete systems in powers of z^-1 instead of z
+    pub negative_powers: bool,
+    /// Pole-zero-gain form with first and second order factors instead of coefficients
+    pub factored: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            style: Style::Plain,
+            significant_digits: DEFAULT_SIGNIFICANT_DIGITS,
+            negative_powers: false,
+            factored: false,
+        }
+    }
+}
+
+/// Transfer function in the variable of its time domain, including any transport delay
+pub fn format_tf<T: Float>(tf: &dyn TransferFunction<T>, options: &FormatOptions) -> String {
+    let text = if options.factored {
+        format_zpk(&ZeroPoleGain::from_tf(tf), tf.time_domain(), options)
+    } else {
+        format_numden(tf.numerator(), tf.denominator(), tf.time_domain(), options)
+    };
+    let delay = tf.delay();
+    if delay.is_zero() {
+        return text;
+    }
+    let delay = format_number(delay.to_f64().unwrap(), options);
+    match options.style {
+        Style::Plain => format!("{} * e^(-{}s)", text, delay),
+        Style::Unicode => append_to_fraction_bar(&text, &format!(" · e^(−{}s)", delay)),
+        Style::Latex => format!("{} e^{{-{}s}}", text, delay),
+    }
+}
+
+/// Numerator and denominator coefficients, highest power first
+pub fn format_numden<T: Float>(
+    numerator: &[T],
+    denominator: &[T],
+    time_domain: TimeDomain<T>,
+    options: &FormatOptions,
+) -> String {
+    let variable = variable(time_domain);
+    let numerator: Vec<f64> = numerator.iter().map(|c| c.to_f64().unwrap()).collect();
+    let denominator: Vec<f64> = denominator.iter().map(|c| c.to_f64().unwrap()).collect();
+    // In z^-1 form both sides are divided by the highest power of z
+    let shift = if negative_powers(time_domain, options) {
+        numerator.len().max(denominator.len()) as i32 - 1
+    } else {
+        0
+    };
+    let powers = |coeffs: &[f64]| -> Vec<(f64, i32)> {
+        let degree = coeffs.len() as i32 - 1;
+        coeffs
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| (c, degree - i as i32 - shift))
+            .collect()
+    };
+    let numerator = powers(&numerator);
+    let denominator = powers(&denominator);
+    let den_text = format_terms(&denominator, variable, options);
+    fraction(
+        &format_terms(&numerator, variable, options),
+        nonzero_terms(&numerator) > 1,
+        &den_text,
+        nonzero_terms(&denominator) > 1 || !is_atomic(&den_text, variable),
+        options.style,
+    )
+}
+
+/// Gain times first order factors for real roots and second order factors for complex pairs
+pub fn format_zpk<T: Float>(
+    zpk: &ZeroPoleGain<T>,
+    time_domain: TimeDomain<T>,
+    options: &FormatOptions,
+) -> String {
+    let variable = variable(time_domain);
+    let negative_powers = negative_powers(time_domain, options);
+    let mut numerator = factors(zpk.zeroes(), variable, negative_powers, options);
+    let denominator = factors(zpk.poles(), variable, negative_powers, options);
+    // x - r = x(1 - r x^-1), the leftover powers of x form one more factor
+    if negative_powers {
+        let excess = zpk.zeroes().len() as i32 - zpk.poles().len() as i32;
+        if excess != 0 {
+            numerator.insert(0, (power(variable, excess, options.style), 1));
+        }
+    }
+
+    let gain = zpk.gain().to_f64().unwrap();
+    let num_text = match format_number(gain, options).as_str() {
+        _ if numerator.is_empty() => format_number(gain, options),
+        "1" => join_factors(&numerator, options.style),
+        "-1" | "−1" => format!(
+            "{}{}",
+            minus(options.style),
+            join_factors(&numerator, options.style)
+        ),
+        gain => format!("{}{}", gain, join_factors(&numerator, options.style)),
+    };
+    let den_text = if denominator.is_empty() {
+        "1".to_string()
+    } else {
+        join_factors(&denominator, options.style)
+    };
+    fraction(
+        &num_text,
+        false,
+        &den_text,
+        denominator.len() > 1,
+        options.style,
+    )
+}
+
+impl<T: Float> fmt::Display for ContinousTransferFunction<T> {
+    /// Plain form, the precision sets the significant digits and `{:#}` gives the factored form
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_tf(self, &display_options(f)))
+    }
+}
+
+impl<T: Float> fmt::Display for DiscreteTransferFunction<T> {
+    /// Plain form in z, the precision sets the significant digits and `{:#}` gives the factored form
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_tf(self, &display_options(f)))
+    }
+}
+
+fn display_options(f: &fmt::Formatter<'_>) -> FormatOptions {
+    FormatOptions {
+        significant_digits: f.precision().unwrap_or(DEFAULT_SIGNIFICANT_DIGITS),
+        factored: f.alternate(),
+        ..Default::default()
+    }
+}
+
+fn variable<T: Float>(time_domain: TimeDomain<T>) -> &'static str {
+    match time_domain {
+        TimeDomain::Continous => "s",
+        TimeDomain::Discrete { .. } => "z",
+    }
+}
+
+fn negative_powers<T: Float>(time_domain: TimeDomain<T>, options: &FormatOptions) -> bool {
+    options.negative_powers && matches!(time_domain, TimeDomain::Discrete { .. })
+}
+
+fn minus(style: Style) -> &'static str {
+    match style {
+        Style::Unicode => "−",
+        Style::Plain | Style::Latex => "-",
+    }
+}
+
+/// Rounds to the requested significant digits and drops trailing zeroes, switching to
+/// scientific notation for very large or small magnitudes
+pub fn format_number(value: f64, options: &FormatOptions) -> String {
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    let digits = options.significant_digits.max(1);
+    let sign = if value < 0.0 {
+        minus(options.style)
+    } else {
+        ""
+    };
+    let magnitude = value.abs();
+    // Rounding first decides the exponent, so 9.99996 becomes 10 rather than 10.00
+    let scientific = format!("{:.*e}", digits - 1, magnitude);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let body = if (-4..digits as i32).contains(&exponent) {
+        let decimals = (digits as i32 - 1 - exponent).max(0) as usize;
+        trim_zeros(&format!("{:.*}", decimals, magnitude))
+    } else {
+        let mantissa = trim_zeros(mantissa);
+        match options.style {
+            Style::Plain => format!("{}e{}", mantissa, exponent),
+            Style::Unicode => format!("{}×10{}", mantissa, superscript(exponent)),
+            Style::Latex => format!("{} \\times 10^{{{}}}", mantissa, exponent),
+        }
+    };
+    format!("{}{}", sign, body)
+}
+
+fn trim_zeros(number: &str) -> String {
+    if number.contains('.') {
+        number
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    } else {
+        number.to_string()
+    }
+}
+
+fn superscript(exponent: i32) -> String {
+    exponent
+        .to_string()
+        .chars()
+        .map(|c| match c {
+            '-' => '⁻',
+            '0' => '⁰',
+            '1' => '¹',
+            '2' => '²',
+            '3' => '³',
+            '4' => '⁴',
+            '5' => '⁵',
+            '6' => '⁶',
+            '7' => '⁷',
+            '8' => '⁸',
+            _ => '⁹',
+        })
+        .collect()
+}
+
+fn power(variable: &str, exponent: i32, style: Style) -> String {
+    match (exponent, style) {
+        (0, _) => String::new(),
+        (1, _) => variable.to_string(),
+        (_, Style::Plain) => format!("{}^{}", variable, exponent),
+        (_, Style::Unicode) => format!("{}{}", variable, superscript(exponent)),
+        (_, Style::Latex) => format!("{}^{{{}}}", variable, exponent),
+    }
+}
+
+fn nonzero_terms(terms: &[(f64, i32)]) -> usize {
+    terms.iter().filter(|&&(c, _)| c != 0.0).count()
+}
+
+// A lone number or power of the variable needs no parentheses as a denominator
+fn is_atomic(text: &str, variable: &str) -> bool {
+    text.parse::<f64>().is_ok()
+        || text
+            .strip_prefix(variable)
+            .is_some_and(|rest| !rest.contains(' '))
+}
+
+/// Sum of coefficient and power pairs, with signs folded into the operators and unit
+/// coefficients left out
+fn format_terms(terms: &[(f64, i32)], variable: &str, options: &FormatOptions) -> String {
+    let mut text = String::new();
+    for &(coeff, exponent) in terms.iter().filter(|&&(c, _)| c != 0.0) {
+        let magnitude = format_number(coeff.abs(), options);
+        let power = power(variable, exponent, options.style);
+        let term = match (magnitude.as_str(), power.is_empty()) {
+            (_, true) => magnitude,
+            ("1", false) => power,
+            (_, false) => format!("{}{}", magnitude, power),
+        };
+        let negative = coeff < 0.0;
+        if text.is_empty() {
+            if negative {
+                text.push_str(minus(options.style));
+            }
+        } else if negative {
+            text.push_str(&format!(" {} ", minus(options.style)));
+        } else {
+            text.push_str(" + ");
+        }
+        text.push_str(&term);
+    }
+    if text.is_empty() {
+        "0".to_string()
+    } else {
+        text
+    }
+}
+
+// Repeated factors are merged into powers, origin roots become bare powers of the variable
+fn factors<T: Float>(
+    roots: &[Complex<T>],
+    variable: &str,
+    negative_powers: bool,
+    options: &FormatOptions,
+) -> Vec<(String, i32)> {
+    let mut factors: Vec<(String, i32)> = Vec::new();
+    let mut origin = 0;
+    for root in roots {
+        let (re, im) = (root.re.to_f64().unwrap(), root.im.to_f64().unwrap());
+        if im < 0.0 {
+            continue;
+        }
+        if re == 0.0 && im == 0.0 {
+            origin += 1;
+            continue;
+        }
+        let terms = match (im > 0.0, negative_powers) {
+            (false, false) => vec![(1.0, 1), (-re, 0)],
+            (false, true) => vec![(1.0, 0), (-re, -1)],
+            (true, false) => vec![(1.0, 2), (-2.0 * re, 1), (re * re + im * im, 0)],
+            (true, true) => vec![(1.0, 0), (-2.0 * re, -1), (re * re + im * im, -2)],
+        };
+        let factor = format!("({})", format_terms(&terms, variable, options));
+        match factors.iter_mut().find(|(f, _)| *f == factor) {
+            Some((_, count)) => *count += 1,
+            None => factors.push((factor, 1)),
+        }
+    }
+    if origin > 0 && !negative_powers {
+        factors.insert(0, (power(variable, origin, options.style), 1));
+    }
+    factors
+}
+
+fn join_factors(factors: &[(String, i32)], style: Style) -> String {
+    factors
+        .iter()
+        .map(|(factor, count)| match (count, style) {
+            (1, _) => factor.clone(),
+            (_, Style::Plain) => format!("{}^{}", factor, count),
+            (_, Style::Unicode) => format!("{}{}", factor, superscript(*count)),
+            (_, Style::Latex) => format!("{}^{{{}}}", factor, count),
+        })
+        .collect()
+}
+
+fn fraction(
+    numerator: &str,
+    numerator_sum: bool,
+    denominator: &str,
+    denominator_compound: bool,
+    style: Style,
+) -> String {
+    if denominator == "1" {
+        return numerator.to_string();
+    }
+    match style {
+        Style::Plain => {
+            let wrap = |text: &str, wrap: bool| {
+                if wrap {
+                    format!("({})", text)
+                } else {
+                    text.to_string()
+                }
+            };
+            format!(
+                "{}/{}",
+                wrap(numerator, numerator_sum),
+                wrap(denominator, denominator_compound)
+            )
+        }
+        Style::Unicode => {
+            let width = numerator.chars().count().max(denominator.chars().count());
+            let centered = |text: &str| {
+                let padding = (width - text.chars().count()) / 2;
+                format!("{:padding$}{}", "", text, padding = padding)
+            };
+            format!(
+                "{}\n{}\n{}",
+                centered(numerator),
+                "─".repeat(width),
+                centered(denominator)
+            )
+        }
+        Style::Latex => format!("\\frac{{{}}}{{{}}}", numerator, denominator),
+    }
+}
+
+// Puts a trailing factor next to the fraction bar of a three line fraction
+fn append_to_fraction_bar(text: &str, suffix: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.len() != 3 {
+        return format!("{}{}", text, suffix);
+    }
+    format!("{}\n{}{}\n{}", lines[0], lines[1], suffix, lines[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tf::parse::{ParsedTransferFunction, parse_transfer_function};
+
+    fn options(style: Style) -> FormatOptions {
+        FormatOptions {
+            style,
+            ..Default::default()
+        }
+    }
+
+    fn options_with_digits(style: Style, significant_digits: usize) -> FormatOptions {
+        FormatOptions {
+            style,
+            significant_digits,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_format_number() {
+        // Given
+        let options = FormatOptions::default();
+
+        // Then
+        assert_eq!("2", format_number(2.0, &options));
+        assert_eq!("-0.5", format_number(-0.5, &options));
+        assert_eq!("3.142", format_number(std::f64::consts::PI, &options));
+        assert_eq!("10", format_number(9.99996, &options));
+        assert_eq!("1235", format_number(1234.56, &options));
+        assert_eq!("1.5e-7", format_number(1.5e-7, &options));
+        assert_eq!("1.235e4", format_number(12346.0, &options));
+        assert_eq!(
+            "1.5×10⁻⁷",
+            format_number(1.5e-7, &options_with_digits(Style::Unicode, 4))
+        );
+    }
+
+    #[test]
+    fn test_display_continuous() {
+        // Given
+        let ctf =
+            ContinousTransferFunction::from_numden(&[-2.0, 0.0, 1.0], &[1.0, 2.0, 5.0]).unwrap();
+
+        // Then
+        assert_eq!("(-2s^2 + 1)/(s^2 + 2s + 5)", ctf.to_string());
+        assert_eq!(
+            "1/(2s)",
+            ContinousTransferFunction::from_numden(&[1.0], &[2.0, 0.0])
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(
+            "1/s^2",
+            ContinousTransferFunction::from_numden(&[1.0], &[1.0, 0.0, 0.0])
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(
+            "0.3333/(s + 1) * e^(-0.5s)",
+            ContinousTransferFunction::from_numden(&[1.0 / 3.0], &[1.0, 1.0])
+                .unwrap()
+                .with_delay(0.5)
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(
+            "0.33/(s + 1)",
+            format!(
+                "{:.2}",
+                ContinousTransferFunction::from_numden(&[1.0 / 3.0], &[1.0, 1.0]).unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_display_discrete_notation() {
+        // Given
+        let dtf = DiscreteTransferFunction::from_numden(vec![1.0, -0.5], vec![1.0, -0.9, 0.0], 0.1)
+            .unwrap();
+        let negative = FormatOptions {
+            negative_powers: true,
+            ..Default::default()
+        };
+
+        // Then
+        assert_eq!("(z - 0.5)/(z^2 - 0.9z)", dtf.to_string());
+        assert_eq!("(z^-1 - 0.5z^-2)/(1 - 0.9z^-1)", format_tf(&dtf, &negative));
+        assert_eq!(
+            "z^-1(1 - 0.5z^-1)/(1 - 0.9z^-1)",
+            format_tf(
+                &dtf,
+                &FormatOptions {
+                    factored: true,
+                    ..negative
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_plain_output_parses_back() {
+        // Given
+        let ctf = ContinousTransferFunction::from_numden(&[10.0, 20.0], &[2.0, 20.0, 0.0]).unwrap();
+        let dtf =
+            DiscreteTransferFunction::from_numden(vec![1.0, -0.5], vec![1.0, -0.9], 0.1).unwrap();
+        let negative = FormatOptions {
+            negative_powers: true,
+            ..Default::default()
+        };
+
+        // When
+        let ctf_text = format!("{}", ctf);
+        let ctf_factored = format!("{:#}", ctf);
+        let dtf_text = format_tf(&dtf, &negative);
+
+        // Then
+        assert_eq!("(10s + 20)/(2s^2 + 20s)", ctf_text);
+        assert_eq!("5(s + 2)/(s(s + 10))", ctf_factored);
+        for text in [ctf_text, ctf_factored] {
+            let Ok(ParsedTransferFunction::Continuous(parsed)) =
+                parse_transfer_function(&text, 0.1)
+            else {
+                panic!("{} did not parse", text);
+            };
+            let s = Complex::new(0.3, 1.0);
+            assert!((ctf.evaluate(s) - parsed.evaluate(s)).norm() < 1e-12);
+        }
+        let Ok(ParsedTransferFunction::Discrete(parsed)) = parse_transfer_function(&dtf_text, 0.1)
+        else {
+            panic!("{} did not parse", dtf_text);
+        };
+        assert_eq!(dtf.numerator(), parsed.numerator());
+        assert_eq!(dtf.denominator(), parsed.denominator());
+    }
+
+    #[test]
+    fn test_factored_form() {
+        // Given
+        // -2(s + 1)^2 / (s^2 (s^2 + 2s + 5))
+        let zpk = ZeroPoleGain::new(
+            &[Complex::new(-1.0, 0.0), Complex::new(-1.0, 0.0)],
+            &[
+                Complex::new(0.0, 0.0),
+                Complex::new(0.0, 0.0),
+                Complex::new(-1.0, 2.0),
+                Complex::new(-1.0, -2.0),
+            ],
+            -2.0,
+        )
+        .unwrap();
+        let ctf = ContinousTransferFunction::from_zpk(&zpk).unwrap();
+        let factored = FormatOptions {
+            factored: true,
+            ..Default::default()
+        };
+
+        // Then
+        assert_eq!(
+            "-2(s + 1)^2/(s^2(s^2 + 2s + 5))",
+            format_tf(&ctf, &factored)
+        );
+        assert_eq!(
+            "\\frac{-2(s + 1)^{2}}{s^{2}(s^{2} + 2s + 5)}",
+            format_tf(
+                &ctf,
+                &FormatOptions {
+                    style: Style::Latex,
+                    ..factored
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_unicode_and_latex() {
+        // Given
+        let ctf = ContinousTransferFunction::from_numden(&[-1.0, 2.0], &[1.0, 2.0, 5.0]).unwrap();
+
+        // Then
+        assert_eq!(
+            "  −s + 2\n───────────\ns² + 2s + 5",
+            format_tf(&ctf, &options(Style::Unicode))
+        );
+        assert_eq!(
+            "\\frac{-s + 2}{s^{2} + 2s + 5}",
+            format_tf(&ctf, &options(Style::Latex))
+        );
+    }
+}
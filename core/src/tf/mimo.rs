@@ -0,0 +1,423 @@
+use std::marker::PhantomData;
+
+use ndarray::Array2;
+use num::{Float, complex::Complex};
+
+use crate::{
+    error::ControlError,
+    ss::{CanonicalForm, StateSpace},
+};
+
+use super::{
+    TimeDomain, TransferFunction,
+    algebra::{self, FeedbackSign, Interconnect, check_time_domains},
+    ctf::ContinousTransferFunction,
+    dtf::DiscreteTransferFunction,
+};
+
+/// Matrix of SISO transfer functions, entry (i, j) maps input j to output i
+#[derive(Debug, Clone)]
+pub struct TransferFunctionMatrix<T: Float, S: TransferFunction<T>> {
+    entries: Vec<Vec<S>>,
+    _scalar: PhantomData<T>,
+}
+
+impl<T: Float, S: TransferFunction<T>> TransferFunctionMatrix<T, S> {
+    /// Rows are outputs, columns are inputs. All entries must share one time domain
+    pub fn new(entries: Vec<Vec<S>>) -> Result<Self, ControlError> {
+        let outputs = entries.len();
+        let inputs = entries.first().map_or(0, Vec::len);
+        if outputs == 0 || inputs == 0 {
+            return Err(ControlError::DimensionMismatch {
+                matrix: "G",
+                expected: (outputs.max(1), inputs.max(1)),
+                found: (outputs, inputs),
+            });
+        }
+        for row in &entries {
+            if row.len() != inputs {
+                return Err(ControlError::DimensionMismatch {
+                    matrix: "G",
+                    expected: (outputs, inputs),
+                    found: (outputs, row.len()),
+                });
+            }
+            for entry in row {
+                check_time_domains(&entries[0][0], entry)?;
+            }
+        }
+        Ok(Self {
+            entries,
+            _scalar: PhantomData,
+        })
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.entries[0].len()
+    }
+
+    pub fn entry(&self, output: usize, input: usize) -> &S {
+        &self.entries[output][input]
+    }
+
+    pub fn entries(&self) -> &[Vec<S>] {
+        &self.entries
+    }
+
+    pub fn time_domain(&self) -> TimeDomain<T> {
+        self.entries[0][0].time_domain()
+    }
+
+    pub fn channel_frequency_response(
+        &self,
+        output: usize,
+        input: usize,
+        omega: &[T],
+    ) -> Vec<Complex<T>> {
+        self.entries[output][input].frequency_response(omega)
+    }
+
+    /// G(jw) (or G(e^jwT)) as an outputs x inputs matrix for every frequency
+    pub fn frequency_response(&self, omega: &[T]) -> Vec<Array2<Complex<T>>> {
+        let channels: Vec<Vec<Vec<Complex<T>>>> = self
+            .entries
+            .iter()
+            .map(|row| row.iter().map(|tf| tf.frequency_response(omega)).collect())
+            .collect();
+        (0..omega.len())
+            .map(|k| {
+                Array2::from_shape_fn((self.outputs(), self.inputs()), |(i, j)| channels[i][j][k])
+            })
+            .collect()
+    }
+
+    // Every entry gets its own controllable canonical block, so the realization is
+    // generally not minimal when entries share poles
+    pub fn to_state_space(&self) -> Result<StateSpace<T>, ControlError> {
+        let blocks = self
+            .entries
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|tf| StateSpace::from_tf(tf, CanonicalForm::Controllable))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let states = blocks.iter().flatten().map(StateSpace::states).sum();
+
+        let mut a = Array2::zeros((states, states));
+        let mut b = Array2::zeros((states, self.inputs()));
+        let mut c = Array2::zeros((self.outputs(), states));
+        let mut d = Array2::zeros((self.outputs(), self.inputs()));
+        let mut offset = 0;
+        for (i, row) in blocks.iter().enumerate() {
+            for (j, block) in row.iter().enumerate() {
+                for r in 0..block.states() {
+                    for k in 0..block.states() {
+                        a[(offset + r, offset + k)] = block.a()[(r, k)];
+                    }
+                    b[(offset + r, j)] = block.b()[(r, 0)];
+                    c[(i, offset + r)] = block.c()[(0, r)];
+                }
+                d[(i, j)] = block.d()[(0, 0)];
+                offset += block.states();
+            }
+        }
+        StateSpace::new(a, b, c, d, self.time_domain())
+    }
+}
+
+impl<T: Float> TransferFunctionMatrix<T, ContinousTransferFunction<T>> {
+    pub fn from_state_space(ss: &StateSpace<T>) -> Result<Self, ControlError> {
+        match ss.time_domain() {
+            TimeDomain::Continous => from_channels(ss, |num, den| {
+                ContinousTransferFunction::from_numden(num, den)
+            }),
+            TimeDomain::Discrete { .. } => Err(ControlError::TimeDomainMismatch),
+        }
+    }
+}
+
+impl<T: Float> TransferFunctionMatrix<T, DiscreteTransferFunction<T>> {
+    pub fn from_state_space(ss: &StateSpace<T>) -> Result<Self, ControlError> {
+        match ss.time_domain() {
+            TimeDomain::Discrete { sample_time } => from_channels(ss, |num, den| {
+                DiscreteTransferFunction::from_numden(num.to_vec(), den.to_vec(), sample_time)
+            }),
+            TimeDomain::Continous => Err(ControlError::TimeDomainMismatch),
+        }
+    }
+}
+
+fn from_channels<T: Float, S: TransferFunction<T>>(
+    ss: &StateSpace<T>,
+    build: impl Fn(&[T], &[T]) -> Result<S, ControlError>,
+) -> Result<TransferFunctionMatrix<T, S>, ControlError> {
+    let entries = (0..ss.outputs())
+        .map(|i| {
+            (0..ss.inputs())
+                .map(|j| {
+                    let (num, den) = ss.channel_numden(i, j)?;
+                    build(&num, &den)
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(TransferFunctionMatrix {
+        entries,
+        _scalar: PhantomData,
+    })
+}
+
+fn check_dimensions(expected: (usize, usize), found: (usize, usize)) -> Result<(), ControlError> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(ControlError::DimensionMismatch {
+            matrix: "G",
+            expected,
+            found,
+        })
+    }
+}
+
+/// a * b, the outputs of b drive the inputs of a
+pub fn series<T: Float, S: Interconnect<T>>(
+    a: &TransferFunctionMatrix<T, S>,
+    b: &TransferFunctionMatrix<T, S>,
+) -> Result<TransferFunctionMatrix<T, S>, ControlError> {
+    check_dimensions((a.inputs(), b.inputs()), (b.outputs(), b.inputs()))?;
+    let entries = (0..a.outputs())
+        .map(|i| {
+            (0..b.inputs())
+                .map(|j| {
+                    let mut sum = algebra::series(a.entry(i, 0), b.entry(0, j))?;
+                    for k in 1..a.inputs() {
+                        let term = algebra::series(a.entry(i, k), b.entry(k, j))?;
+                        sum = algebra::parallel(&sum, &term)?;
+                    }
+                    Ok(sum)
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    TransferFunctionMatrix::new(entries)
+}
+
+/// a + b, entry by entry
+pub fn parallel<T: Float, S: Interconnect<T>>(
+    a: &TransferFunctionMatrix<T, S>,
+    b: &TransferFunctionMatrix<T, S>,
+) -> Result<TransferFunctionMatrix<T, S>, ControlError> {
+    check_dimensions((a.outputs(), a.inputs()), (b.outputs(), b.inputs()))?;
+    let entries = a
+        .entries
+        .iter()
+        .zip(b.entries.iter())
+        .map(|(row_a, row_b)| {
+            row_a
+                .iter()
+                .zip(row_b.iter())
+                .map(|(x, y)| algebra::parallel(x, y))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    TransferFunctionMatrix::new(entries)
+}
+
+/// Closed loop of g in the forward path and h in the return path, (I + GH)^-1 G for negative feedback
+pub fn feedback<T: Float, S: Interconnect<T>>(
+    g: &TransferFunctionMatrix<T, S>,
+    h: &TransferFunctionMatrix<T, S>,
+    sign: FeedbackSign,
+) -> Result<TransferFunctionMatrix<T, S>, ControlError> {
+    // (I + GH)^-1 is formed on the state-space realizations, so every entry of the
+    // result has the closed-loop characteristic polynomial as its denominator
+    let closed_loop = g.to_state_space()?.feedback(&h.to_state_space()?, sign)?;
+    let like = g.entry(0, 0);
+    from_channels(&closed_loop, |num, den| {
+        like.with_numden(num, den, T::zero())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use num::complex::Complex64;
+
+    use super::*;
+
+    fn ctf(num: &[f64], den: &[f64]) -> ContinousTransferFunction<f64> {
+        ContinousTransferFunction::from_numden(num, den).unwrap()
+    }
+
+    fn assert_complex(expected: Complex64, actual: Complex64) {
+        assert_relative_eq!(expected.re, actual.re, epsilon = 1e-10);
+        assert_relative_eq!(expected.im, actual.im, epsilon = 1e-10);
+    }
+
+    fn plant() -> TransferFunctionMatrix<f64, ContinousTransferFunction<f64>> {
+        // G(s) = [[1 / (s + 1), 2 / (s + 2)], [0, (s + 1) / (s + 3)]]
+        TransferFunctionMatrix::new(vec![
+            vec![ctf(&[1.0], &[1.0, 1.0]), ctf(&[2.0], &[1.0, 2.0])],
+            vec![ctf(&[0.0], &[1.0]), ctf(&[1.0, 1.0], &[1.0, 3.0])],
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_ragged_and_mixed_sample_times_rejected() {
+        // Given
+        let ragged = vec![
+            vec![ctf(&[1.0], &[1.0, 1.0]), ctf(&[1.0], &[1.0, 2.0])],
+            vec![ctf(&[1.0], &[1.0, 3.0])],
+        ];
+        let mixed = vec![vec![
+            DiscreteTransferFunction::from_numden(vec![1.0], vec![1.0, -0.5], 0.1).unwrap(),
+            DiscreteTransferFunction::from_numden(vec![1.0], vec![1.0, -0.5], 0.2).unwrap(),
+        ]];
+
+        // Then
+        assert_eq!(
+            ControlError::DimensionMismatch {
+                matrix: "G",
+                expected: (2, 2),
+                found: (2, 1)
+            },
+            TransferFunctionMatrix::new(ragged).unwrap_err()
+        );
+        assert_eq!(
+            ControlError::SampleTimeMismatch {
+                left: 0.1,
+                right: 0.2
+            },
+            TransferFunctionMatrix::new(mixed).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_frequency_response() {
+        // Given
+        let g = plant();
+        let omega = [0.0, 2.0];
+
+        // When
+        let response = g.frequency_response(&omega);
+
+        // Then
+        for (k, &w) in omega.iter().enumerate() {
+            let s = Complex64::new(0.0, w);
+            assert_complex(g.entry(0, 1).evaluate(s), response[k][(0, 1)]);
+            assert_complex(g.entry(1, 1).evaluate(s), response[k][(1, 1)]);
+            assert_complex(Complex64::new(0.0, 0.0), response[k][(1, 0)]);
+        }
+        assert_complex(
+            response[1][(0, 0)],
+            g.channel_frequency_response(0, 0, &omega)[1],
+        );
+    }
+
+    #[test]
+    fn test_state_space_round_trip() {
+        // Given
+        let g = plant();
+        let s = Complex64::new(0.3, 1.7);
+
+        // When
+        let ss = g.to_state_space().unwrap();
+        let result =
+            TransferFunctionMatrix::<f64, ContinousTransferFunction<f64>>::from_state_space(&ss)
+                .unwrap();
+
+        // Then
+        // The zero entry adds no states
+        assert_eq!((2, 2, 3), (ss.outputs(), ss.inputs(), ss.states()));
+        for i in 0..2 {
+            for j in 0..2 {
+                assert_complex(g.entry(i, j).evaluate(s), result.entry(i, j).evaluate(s));
+            }
+        }
+    }
+
+    #[test]
+    fn test_series_is_matrix_product() {
+        // Given
+        // Row [1 / (s + 1), 2 / (s + 2)] times the plant
+        let a = TransferFunctionMatrix::new(vec![vec![
+            ctf(&[1.0], &[1.0, 1.0]),
+            ctf(&[2.0], &[1.0, 2.0]),
+        ]])
+        .unwrap();
+        let b = plant();
+        let s = Complex64::new(0.0, 0.5);
+
+        // When
+        let result = series(&a, &b).unwrap();
+
+        // Then
+        assert_eq!((1, 2), (result.outputs(), result.inputs()));
+        for j in 0..2 {
+            let expected = a.entry(0, 0).evaluate(s) * b.entry(0, j).evaluate(s)
+                + a.entry(0, 1).evaluate(s) * b.entry(1, j).evaluate(s);
+            assert_complex(expected, result.entry(0, j).evaluate(s));
+        }
+        assert_eq!(
+            ControlError::DimensionMismatch {
+                matrix: "G",
+                expected: (2, 2),
+                found: (1, 2)
+            },
+            series(&b, &a).map(|_| ()).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_parallel() {
+        // Given
+        let g = plant();
+        let s = Complex64::new(0.0, 1.0);
+
+        // When
+        let result = parallel(&g, &g).unwrap();
+
+        // Then
+        assert_complex(
+            g.entry(0, 1).evaluate(s) * 2.0,
+            result.entry(0, 1).evaluate(s),
+        );
+    }
+
+    #[test]
+    fn test_feedback() {
+        // Given
+        // Unity negative feedback around the plant: (I + G)^-1 G
+        let g = plant();
+        let identity = TransferFunctionMatrix::new(vec![
+            vec![ctf(&[1.0], &[1.0]), ctf(&[0.0], &[1.0])],
+            vec![ctf(&[0.0], &[1.0]), ctf(&[1.0], &[1.0])],
+        ])
+        .unwrap();
+        let s = Complex64::new(0.0, 0.8);
+
+        // When
+        let result = feedback(&g, &identity, FeedbackSign::Negative).unwrap();
+
+        // Then
+        // G is upper triangular, so (I + G)^-1 G can be checked entry by entry
+        let g11 = g.entry(0, 0).evaluate(s);
+        let g12 = g.entry(0, 1).evaluate(s);
+        let g22 = g.entry(1, 1).evaluate(s);
+        let one = Complex64::new(1.0, 0.0);
+        let t11 = g11 / (one + g11);
+        let t22 = g22 / (one + g22);
+        let t12 = g12 / ((one + g11) * (one + g22));
+        assert_complex(t11, result.entry(0, 0).evaluate(s));
+        assert_complex(t12, result.entry(0, 1).evaluate(s));
+        assert_complex(Complex64::new(0.0, 0.0), result.entry(1, 0).evaluate(s));
+        assert_complex(t22, result.entry(1, 1).evaluate(s));
+    }
+}
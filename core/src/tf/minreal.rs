@@ -0,0 +1,263 @@
+use num::{Float, complex::Complex};
+
+use crate::{error::ControlError, util::poly::reduce_to_real};
+
+use super::{
+    TransferFunction, algebra::Interconnect, traits::coeff_from_pz, zpk::check_conjugate_pairs,
+};
+
+/// A pole and zero close enough to cancel each other
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CancelledPair<T: Float> {
+    pub pole: Complex<T>,
+    pub zero: Complex<T>,
+}
+
+// A pole and zero cancel when |p - z| <= tol * max(1, |p|), closest pairs are matched first
+// A pair with a complex pole or zero only cancels together with its conjugate pair, otherwise
+// the remaining roots would give complex coefficients, e.g. a real zero at -1 next to poles at
+// -1 +- 1e-4j cancels neither pole
+pub fn find_cancellations<T: Float>(tf: &dyn TransferFunction<T>, tol: T) -> Vec<CancelledPair<T>> {
+    let poles = tf.poles();
+    let zeroes = tf.zeroes();
+
+    let mut candidates: Vec<(T, usize, usize)> = poles
+        .iter()
+        .enumerate()
+        .flat_map(|(i, p)| {
+            zeroes
+                .iter()
+                .enumerate()
+                .map(move |(j, z)| ((p - z).norm(), i, j))
+        })
+        .filter(|&(distance, i, _)| distance <= tol * poles[i].norm().max(T::one()))
+        .collect();
+    candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let mut pole_used = vec![false; poles.len()];
+    let mut zero_used = vec![false; zeroes.len()];
+    let mut matched = Vec::new();
+    for (_, i, j) in candidates {
+        if !pole_used[i] && !zero_used[j] {
+            pole_used[i] = true;
+            zero_used[j] = true;
+            matched.push(CancelledPair {
+                pole: poles[i],
+                zero: zeroes[j],
+            });
+        }
+    }
+    keep_conjugate_pairs(&matched)
+}
+
+// Same tolerance as `check_conjugate_pairs`
+fn keep_conjugate_pairs<T: Float>(matched: &[CancelledPair<T>]) -> Vec<CancelledPair<T>> {
+    let tol = |r: &Complex<T>| T::epsilon().sqrt() * r.norm().max(T::one());
+    let is_real = |r: &Complex<T>| r.im.abs() <= tol(r);
+    let mut kept = vec![false; matched.len()];
+    for (i, pair) in matched.iter().enumerate() {
+        if kept[i] {
+            continue;
+        }
+        if is_real(&pair.pole) && is_real(&pair.zero) {
+            kept[i] = true;
+            continue;
+        }
+        let conjugate = (i + 1..matched.len()).find(|&j| {
+            !kept[j]
+                && (matched[j].pole - pair.pole.conj()).norm() <= tol(&pair.pole)
+                && (matched[j].zero - pair.zero.conj()).norm() <= tol(&pair.zero)
+        });
+        if let Some(j) = conjugate {
+            kept[i] = true;
+            kept[j] = true;
+        }
+    }
+    matched
+        .iter()
+        .zip(kept)
+        .filter_map(|(&pair, kept)| kept.then_some(pair))
+        .collect()
+}
+
+/// Removes cancelling pole-zero pairs, returning the reduced system and the pairs removed
+pub fn minreal<T: Float, S: Interconnect<T>>(
+    tf: &S,
+    tol: T,
+) -> Result<(S, Vec<CancelledPair<T>>), ControlError> {
+    let cancelled = find_cancellations(tf, tol);
+    if cancelled.is_empty() {
+        return Ok((
+            tf.with_numden(tf.numerator(), tf.denominator(), tf.delay())?,
+            cancelled,
+        ));
+    }
+
+    let poles = remaining(tf.poles(), cancelled.iter().map(|pair| pair.pole));
+    let zeroes = remaining(tf.zeroes(), cancelled.iter().map(|pair| pair.zero));
+    // Dropping the imaginary parts below is only exact for conjugate-symmetric roots
+    check_conjugate_pairs(&poles)?;
+    check_conjugate_pairs(&zeroes)?;
+    let gain = tf.numerator()[0] / tf.denominator()[0];
+    let numerator: Vec<T> = reduce_to_real(&coeff_from_pz(&zeroes))
+        .iter()
+        .map(|&c| c * gain)
+        .collect();
+    let denominator = reduce_to_real(&coeff_from_pz(&poles));
+    Ok((
+        tf.with_numden(&numerator, &denominator, tf.delay())?,
+        cancelled,
+    ))
+}
+
+fn remaining<T: Float>(
+    roots: &[Complex<T>],
+    removed: impl Iterator<Item = Complex<T>>,
+) -> Vec<Complex<T>> {
+    let mut roots = roots.to_vec();
+    for root in removed {
+        if let Some(index) = roots.iter().position(|&r| r == root) {
+            roots.remove(index);
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use num::complex::Complex64;
+
+    use super::*;
+    use crate::tf::{
+        algebra::{FeedbackSign, feedback},
+        ctf::ContinousTransferFunction,
+        dtf::DiscreteTransferFunction,
+    };
+
+    fn assert_coeffs(expected: &[f64], actual: &[f64]) {
+        assert_eq!(expected.len(), actual.len());
+        for (e, a) in expected.iter().zip(actual.iter()) {
+            assert_relative_eq!(e, a, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_series_cancellation() {
+        // Given
+        // (s + 1) / (s + 2) * 3 / ((s + 1)(s + 3))
+        let g1 = ContinousTransferFunction::from_numden(&[1.0, 1.0], &[1.0, 2.0]).unwrap();
+        let g2 = ContinousTransferFunction::from_numden(&[3.0], &[1.0, 4.0, 3.0]).unwrap();
+        let product = (&g1 * &g2).unwrap();
+
+        // When
+        let (reduced, cancelled) = minreal(&product, 1e-8).unwrap();
+
+        // Then
+        // 3 / ((s + 2)(s + 3))
+        assert_coeffs(&[3.0], reduced.numerator());
+        assert_coeffs(&[1.0, 5.0, 6.0], reduced.denominator());
+        assert_eq!(1, cancelled.len());
+        assert_relative_eq!(-1.0, cancelled[0].pole.re, epsilon = 1e-9);
+        assert_relative_eq!(-1.0, cancelled[0].zero.re, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_conjugate_pair_cancellation() {
+        // Given
+        // Unity feedback of G(s) = (s^2 + 2s + 5) / ((s^2 + 2s + 5)(s + 1)) keeps the
+        // lightly damped factor in both numerator and denominator
+        let g = ContinousTransferFunction::from_numden(&[1.0, 2.0, 5.0], &[1.0, 3.0, 7.0, 5.0])
+            .unwrap();
+        let h = ContinousTransferFunction::from_numden(&[1.0], &[1.0]).unwrap();
+        let closed_loop = feedback(&g, &h, FeedbackSign::Negative).unwrap();
+
+        // When
+        let (reduced, cancelled) = minreal(&closed_loop, 1e-6).unwrap();
+
+        // Then
+        // 1 / (s + 2)
+        assert_eq!(2, cancelled.len());
+        assert_relative_eq!(
+            cancelled[0].pole.conj().im,
+            cancelled[1].pole.im,
+            epsilon = 1e-9
+        );
+        assert_coeffs(&[1.0], reduced.numerator());
+        assert_coeffs(&[1.0, 2.0], reduced.denominator());
+    }
+
+    #[test]
+    fn test_real_zero_does_not_split_conjugate_pair() {
+        // Given
+        // (s + 1) / (((s + 1)^2 + 1e-8)(s + 2)), the poles -1 +- 1e-4j are within the
+        // tolerance of the zero, but cancelling only one of them leaves complex coefficients
+        let tf = ContinousTransferFunction::from_numden(
+            &[1.0, 1.0],
+            &[1.0, 4.0, 5.0 + 1e-8, 2.0 + 2e-8],
+        )
+        .unwrap();
+
+        // When
+        let (reduced, cancelled) = minreal(&tf, 1e-3).unwrap();
+
+        // Then
+        assert!(cancelled.is_empty());
+        assert_coeffs(tf.numerator(), reduced.numerator());
+        assert_coeffs(tf.denominator(), reduced.denominator());
+    }
+
+    #[test]
+    fn test_tolerance() {
+        // Given
+        // H(z) = (z + 1.001) / ((z + 1)(z + 2)), T = 0.1
+        let tf = DiscreteTransferFunction::from_numden(vec![1.0, 1.001], vec![1.0, 3.0, 2.0], 0.1)
+            .unwrap();
+
+        // When
+        let (_, strict) = minreal(&tf, 1e-6).unwrap();
+        let (reduced, loose) = minreal(&tf, 1e-2).unwrap();
+
+        // Then
+        assert!(strict.is_empty());
+        assert_eq!(1, loose.len());
+        assert_coeffs(&[1.0, 2.0], reduced.denominator());
+        assert_relative_eq!(0.1, reduced.sample_time());
+    }
+
+    #[test]
+    fn test_keeps_delay() {
+        // Given
+        let tf = ContinousTransferFunction::from_numden(&[1.0, 1.0], &[1.0, 3.0, 2.0])
+            .unwrap()
+            .with_delay(0.5)
+            .unwrap();
+
+        // When
+        let (reduced, _) = minreal(&tf, 1e-8).unwrap();
+
+        // Then
+        assert_relative_eq!(0.5, reduced.delay());
+        let s = Complex64::new(0.0, 1.0);
+        let expected = tf.evaluate(s);
+        let result = reduced.evaluate(s);
+        assert_relative_eq!(expected.re, result.re, epsilon = 1e-9);
+        assert_relative_eq!(expected.im, result.im, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_leading_zeros_in_denominator() {
+        // Given
+        // 2(s + 1)/((s + 1)(s + 2)) stored with a padded denominator
+        let tf =
+            ContinousTransferFunction::from_numden(&[2.0, 2.0], &[0.0, 1.0, 3.0, 2.0]).unwrap();
+
+        // When
+        let (reduced, cancelled) = minreal(&tf, 1e-8).unwrap();
+
+        // Then
+        assert_eq!(1, cancelled.len());
+        assert_coeffs(&[2.0], reduced.numerator());
+        assert_coeffs(&[1.0, 2.0], reduced.denominator());
+    }
+}
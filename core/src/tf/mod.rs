@@ -2,5 +2,19 @@ pub mod traits;
 pub mod ctf;
 pub mod dtf;
 pub mod bilinear;
+pub mod discretization;
+pub mod algebra;
+pub mod zpk;
+pub mod delay;
+pub mod minreal;
+pub mod mimo;
+pub mod parse;
+pub mod format;
+#[cfg(feature = "serde")]
+pub mod schema;
 
 pub use traits::{TimeDomain, TransferFunction};
+
+/// Written to every serialized record, bumped whenever the layout changes incompatibly
+/// Defined outside `schema` so that errors can name it without the serde feature
+pub const SCHEMA_VERSION: u32 = 1;
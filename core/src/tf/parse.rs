@@ -0,0 +1,469 @@
+use num::Float;
+
+use crate::{error::ControlError, util::poly::Polynomial};
+
+use super::{ctf::ContinousTransferFunction, dtf::DiscreteTransferFunction};
+
+/// Transfer function read from a text expression, the variable decides the time domain
+#[derive(Debug, Clone)]
+pub enum ParsedTransferFunction<T: Float> {
+    Continuous(ContinousTransferFunction<T>),
+    Discrete(DiscreteTransferFunction<T>),
+}
+
+/// Parses expressions such as `(s+1)/(s^2+2*s+5)`, `10*(s+2)/(s*(s+10))` or
+/// `(1 - 0.5z^-1)/(1 - 0.9z^-1)`
+/// Expressions in s give a continuous transfer function, expressions in z or z^-1 a discrete one
+/// with the given sample time, and constants a continuous gain
+/// Multiplication may be implicit (`2s`, `s(s+1)`) and powers must be integers that keep the
+/// degree at most 100
+pub fn parse_transfer_function<T: Float>(
+    text: &str,
+    sample_time: T,
+) -> Result<ParsedTransferFunction<T>, ControlError> {
+    let (variable, numerator, denominator) = parse_rational(text, None)?;
+    match variable {
+        Some(Variable::Z) => {
+            DiscreteTransferFunction::from_numden(numerator, denominator, sample_time)
+                .map(ParsedTransferFunction::Discrete)
+        }
+        _ => ContinousTransferFunction::from_numden(&numerator, &denominator)
+            .map(ParsedTransferFunction::Continuous),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Variable {
+    S,
+    Z,
+}
+
+type ParsedRational<T> = (Option<Variable>, Vec<T>, Vec<T>);
+
+// Highest degree a power may produce, far beyond any useful model but small enough that a
+// typo such as s^1000000 cannot stall the expansion
+const MAX_DEGREE: usize = 100;
+
+/// Numerator and denominator coefficients in positive powers of the variable. Expressions with
+/// negative powers of z have their common factors of z cancelled, so that z^-1 forms come out as
+/// ordinary polynomials in z. Anything else is kept as typed, including cancelling poles and
+/// zeroes at the origin
+pub(crate) fn parse_rational<T: Float>(
+    text: &str,
+    expected: Option<Variable>,
+) -> Result<ParsedRational<T>, ControlError> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        index: 0,
+        expected,
+        variable: None,
+        negative_powers: false,
+    };
+    let rational: Rational<T> = parser.expression()?;
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected character"));
+    }
+
+    let mut numerator = rational.numerator.coeffs().to_vec();
+    let mut denominator = rational.denominator.coeffs().to_vec();
+    while parser.negative_powers
+        && numerator.len() > 1
+        && denominator.len() > 1
+        && numerator.last().unwrap().is_zero()
+        && denominator.last().unwrap().is_zero()
+    {
+        numerator.pop();
+        denominator.pop();
+    }
+    Ok((parser.variable, numerator, denominator))
+}
+
+struct Parser {
+    // Whitespace only separates tokens, so positions are indices into the original text
+    chars: Vec<char>,
+    index: usize,
+    expected: Option<Variable>,
+    variable: Option<Variable>,
+    // Set by a negative power of z, whose expansion adds factors of z that were not typed
+    negative_powers: bool,
+}
+
+impl Parser {
+    // Start of the next token
+    fn position(&self) -> usize {
+        self.chars[self.index..]
+            .iter()
+            .position(|c| !c.is_whitespace())
+            .map_or(self.chars.len(), |offset| self.index + offset)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position()).copied()
+    }
+
+    // Character at the cursor without skipping whitespace, used inside a token
+    fn current(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn error(&self, reason: &'static str) -> ControlError {
+        ControlError::InvalidExpression {
+            position: self.position(),
+            reason,
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.index = self.position() + 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // expression := term (('+' | '-') term)*
+    fn expression<T: Float>(&mut self) -> Result<Rational<T>, ControlError> {
+        let mut result = self.term()?;
+        loop {
+            if self.eat('+') {
+                result = result.add(&self.term()?);
+            } else if self.eat('-') {
+                result = result.add(&self.term()?.neg());
+            } else {
+                return Ok(result);
+            }
+        }
+    }
+
+    // term := unary (('*' | '/' | implicit) unary)*
+    fn term<T: Float>(&mut self) -> Result<Rational<T>, ControlError> {
+        let mut result = self.unary()?;
+        loop {
+            if self.eat('*') {
+                result = result.mul(&self.unary()?);
+            } else if self.eat('/') {
+                result = result.mul(&self.unary()?.recip());
+            } else if self
+                .peek()
+                .is_some_and(|c| c.is_ascii_digit() || matches!(c, '.' | 's' | 'z' | '('))
+            {
+                result = result.mul(&self.unary()?);
+            } else {
+                return Ok(result);
+            }
+        }
+    }
+
+    // unary := ('+' | '-') unary | power
+    fn unary<T: Float>(&mut self) -> Result<Rational<T>, ControlError> {
+        if self.eat('+') {
+            self.unary()
+        } else if self.eat('-') {
+            Ok(self.unary()?.neg())
+        } else {
+            self.power()
+        }
+    }
+
+    // power := primary ('^' exponent)?
+    fn power<T: Float>(&mut self) -> Result<Rational<T>, ControlError> {
+        let base = self.primary()?;
+        if !self.eat('^') {
+            return Ok(base);
+        }
+        let parenthesized = self.eat('(');
+        let negative = if self.eat('-') {
+            true
+        } else {
+            self.eat('+');
+            false
+        };
+        let position = self.position();
+        let exponent = self.number()?;
+        if exponent.fract() != 0.0 {
+            return Err(ControlError::InvalidExpression {
+                position,
+                reason: "powers must be integers",
+            });
+        }
+        // Constants count as degree one so that their powers are bounded as well
+        if base.degree().max(1) as f64 * exponent > MAX_DEGREE as f64 {
+            return Err(ControlError::InvalidExpression {
+                position,
+                reason: "powers may not raise the degree above 100",
+            });
+        }
+        if parenthesized && !self.eat(')') {
+            return Err(self.error("expected ')'"));
+        }
+        let exponent = exponent as i32;
+        if negative && self.variable == Some(Variable::Z) {
+            self.negative_powers = true;
+        }
+        Ok(base.powi(if negative { -exponent } else { exponent }))
+    }
+
+    // primary := number | 's' | 'z' | '(' expression ')'
+    fn primary<T: Float>(&mut self) -> Result<Rational<T>, ControlError> {
+        match self.peek() {
+            Some('(') => {
+                self.eat('(');
+                let inner = self.expression()?;
+                if !self.eat(')') {
+                    return Err(self.error("expected ')'"));
+                }
+                Ok(inner)
+            }
+            Some('s') => self.variable(Variable::S),
+            Some('z') => self.variable(Variable::Z),
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let value = self.number()?;
+                Ok(Rational::constant(T::from(value).unwrap()))
+            }
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of expression")),
+        }
+    }
+
+    fn variable<T: Float>(&mut self, variable: Variable) -> Result<Rational<T>, ControlError> {
+        if let Some(expected) = self.expected
+            && expected != variable
+        {
+            return Err(self.error(match expected {
+                Variable::S => "expected a transfer function in s",
+                Variable::Z => "expected a transfer function in z",
+            }));
+        }
+        if self.variable.is_some_and(|v| v != variable) {
+            return Err(self.error("cannot mix s and z"));
+        }
+        self.variable = Some(variable);
+        self.index = self.position() + 1;
+        Ok(Rational::variable())
+    }
+
+    // Decimal number with an optional exponent such as 1.5e-3, which may not be followed
+    // directly by another number: "2 3" is an error rather than 23 or 2*3
+    fn number(&mut self) -> Result<f64, ControlError> {
+        self.index = self.position();
+        let start = self.index;
+        let mut text = String::new();
+        while let Some(c) = self.current().filter(|c| c.is_ascii_digit() || *c == '.') {
+            text.push(c);
+            self.index += 1;
+        }
+        if matches!(self.current(), Some('e' | 'E')) {
+            let signed = matches!(self.chars.get(self.index + 1), Some('+' | '-'));
+            let digit = self.chars.get(self.index + 1 + signed as usize);
+            if digit.is_some_and(|c| c.is_ascii_digit()) {
+                for _ in 0..=signed as usize {
+                    text.push(self.current().unwrap());
+                    self.index += 1;
+                }
+                while let Some(c) = self.current().filter(|c| c.is_ascii_digit()) {
+                    text.push(c);
+                    self.index += 1;
+                }
+            }
+        }
+        let value = text.parse().map_err(|_| {
+            self.index = start;
+            if text.is_empty() {
+                self.error("expected a number")
+            } else {
+                self.error("invalid number")
+            }
+        })?;
+        if self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            return Err(self.error("expected an operator between numbers"));
+        }
+        Ok(value)
+    }
+}
+
+// Rational function in the single variable of the expression
+struct Rational<T: Float> {
+    numerator: Polynomial<T>,
+    denominator: Polynomial<T>,
+}
+
+impl<T: Float> Rational<T> {
+    fn constant(value: T) -> Self {
+        Self {
+            numerator: Polynomial::constant(value),
+            denominator: Polynomial::one(),
+        }
+    }
+
+    fn variable() -> Self {
+        Self {
+            numerator: Polynomial::new(&[T::one(), T::zero()]),
+            denominator: Polynomial::one(),
+        }
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        // Shared denominators are kept as they are, 1/(s+1) + s/(s+1) stays first order
+        if self.denominator == other.denominator {
+            return Self {
+                numerator: &self.numerator + &other.numerator,
+                denominator: self.denominator.clone(),
+            };
+        }
+        Self {
+            numerator: &self.numerator * &other.denominator + &other.numerator * &self.denominator,
+            denominator: &self.denominator * &other.denominator,
+        }
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        Self {
+            numerator: &self.numerator * &other.numerator,
+            denominator: &self.denominator * &other.denominator,
+        }
+    }
+
+    fn neg(&self) -> Self {
+        Self {
+            numerator: -&self.numerator,
+            denominator: self.denominator.clone(),
+        }
+    }
+
+    fn recip(&self) -> Self {
+        Self {
+            numerator: self.denominator.clone(),
+            denominator: self.numerator.clone(),
+        }
+    }
+
+    fn degree(&self) -> usize {
+        self.numerator.degree().max(self.denominator.degree())
+    }
+
+    fn powi(&self, exponent: i32) -> Self {
+        let base = if exponent < 0 {
+            self.recip()
+        } else {
+            Self {
+                numerator: self.numerator.clone(),
+                denominator: self.denominator.clone(),
+            }
+        };
+        (0..exponent.unsigned_abs()).fold(Self::constant(T::one()), |acc, _| acc.mul(&base))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::tf::TransferFunction;
+
+    fn parse(text: &str) -> ParsedRational<f64> {
+        parse_rational(text, None).unwrap()
+    }
+
+    fn error_at(text: &str) -> usize {
+        match parse_rational::<f64>(text, None) {
+            Err(ControlError::InvalidExpression { position, .. }) => position,
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_continuous() {
+        // Then
+        assert_eq!(
+            (Some(Variable::S), vec![1.0, 1.0], vec![1.0, 2.0, 5.0]),
+            parse("(s+1)/(s^2+2*s+5)")
+        );
+        assert_eq!(
+            (Some(Variable::S), vec![10.0, 20.0], vec![1.0, 10.0, 0.0]),
+            parse("10*(s+2)/(s*(s+10))")
+        );
+        assert_eq!(
+            (Some(Variable::S), vec![-2.0, 0.0, 4.0], vec![1.0]),
+            parse(" -2s^2 + 4 ")
+        );
+        assert_eq!((None, vec![1.5e-3], vec![1.0]), parse("1.5e-3"));
+    }
+
+    #[test]
+    fn test_parse_implicit_multiplication_and_powers() {
+        // Then
+        assert_eq!(parse("2*s*(s+1)^2"), parse("2s(s+1)^2"));
+        assert_eq!(parse("1/(s*s)"), parse("s^-2"));
+        assert_eq!(parse("1/(s*s)"), parse("s^(-2)"));
+        assert_eq!(101, parse("s^100").1.len());
+        assert_eq!(
+            (Some(Variable::S), vec![1.0, 2.0], vec![1.0, 1.0]),
+            parse("1/(s+1) + (s+1)/(s+1)")
+        );
+    }
+
+    #[test]
+    fn test_parse_keeps_typed_cancellations() {
+        // Then
+        // The pole-zero pair at the origin is left for minreal to report
+        assert_eq!(
+            (Some(Variable::S), vec![1.0, 0.0], vec![1.0, 1.0, 0.0]),
+            parse("s/(s*(s+1))")
+        );
+        assert_eq!(
+            (Some(Variable::Z), vec![1.0, 0.0], vec![1.0, -0.5, 0.0]),
+            parse("z/(z^2-0.5z)")
+        );
+    }
+
+    #[test]
+    fn test_parse_negative_powers_of_z() {
+        // Given
+        let text = "(1 - 0.5z^-1)/(1 - 0.9z^-1)";
+
+        // When
+        let parsed = parse_transfer_function(text, 0.1).unwrap();
+
+        // Then
+        // Common powers of z cancel, leaving (z - 0.5)/(z - 0.9)
+        let ParsedTransferFunction::Discrete(dtf) = parsed else {
+            panic!("expected a discrete transfer function");
+        };
+        assert_eq!(vec![1.0, -0.5], dtf.numerator());
+        assert_eq!(vec![1.0, -0.9], dtf.denominator());
+        assert_relative_eq!(0.1, dtf.sample_time());
+        assert!(matches!(
+            parse_transfer_function("s/(s+1)", 0.1),
+            Ok(ParsedTransferFunction::Continuous(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_errors_report_position() {
+        // Then
+        assert_eq!(6, error_at("(s+1)/"));
+        assert_eq!(7, error_at("(s+1 /s"));
+        assert_eq!(3, error_at("s+1)"));
+        assert_eq!(2, error_at("s^1.5"));
+        assert_eq!(10, error_at("(s^2+1)^(-51)"));
+        assert_eq!(2, error_at("2^2147483647"));
+        assert_eq!(4, error_at("s + z"));
+        assert_eq!(2, error_at("s+x"));
+        assert_eq!(2, error_at("2 3"));
+        assert_eq!(2, error_at("1 0s"));
+        assert_eq!(8, error_at("s^2 / 3 4"));
+        assert_eq!(
+            Err(ControlError::InvalidExpression {
+                position: 0,
+                reason: "expected a transfer function in s"
+            }),
+            parse_rational::<f64>("z/(z-1)", Some(Variable::S))
+        );
+        assert_eq!(
+            Err(ControlError::ZeroDenominator),
+            parse_transfer_function::<f64>("1/(s-s)", 0.1).map(|_| ())
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use num::Float;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ControlError;
+
+use super::{
+    TimeDomain, TransferFunction, ctf::ContinousTransferFunction, dtf::DiscreteTransferFunction,
+};
+
+pub use super::SCHEMA_VERSION;
+
+/// Stable serialized form of a single-input single-output transfer function, in JSON
+/// `{"version":1,"time_domain":{"type":"discrete","sample_time":0.1},"numerator":[1.0,-0.5],"denominator":[1.0,-0.9]}`
+/// Coefficients are highest power first, the transport delay in seconds is only written when
+/// non-zero. Poles and zeroes are recomputed on loading
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransferFunctionRecord<T: Float> {
+    pub version: u32,
+    pub time_domain: TimeDomain<T>,
+    pub numerator: Vec<T>,
+    pub denominator: Vec<T>,
+    #[serde(
+        default = "num::Zero::zero",
+        skip_serializing_if = "num::Zero::is_zero"
+    )]
+    pub delay: T,
+}
+
+impl<T: Float> TransferFunctionRecord<T> {
+    pub fn from_tf(tf: &dyn TransferFunction<T>) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            time_domain: tf.time_domain(),
+            numerator: tf.numerator().to_vec(),
+            denominator: tf.denominator().to_vec(),
+            delay: tf.delay(),
+        }
+    }
+
+    fn check_version(&self) -> Result<(), ControlError> {
+        if (1..=SCHEMA_VERSION).contains(&self.version) {
+            Ok(())
+        } else {
+            Err(ControlError::UnsupportedSchemaVersion {
+                version: self.version,
+            })
+        }
+    }
+}
+
+impl<T: Float> From<ContinousTransferFunction<T>> for TransferFunctionRecord<T> {
+    fn from(tf: ContinousTransferFunction<T>) -> Self {
+        Self::from_tf(&tf)
+    }
+}
+
+impl<T: Float> From<DiscreteTransferFunction<T>> for TransferFunctionRecord<T> {
+    fn from(tf: DiscreteTransferFunction<T>) -> Self {
+        Self::from_tf(&tf)
+    }
+}
+
+impl<T: Float> TryFrom<TransferFunctionRecord<T>> for ContinousTransferFunction<T> {
+    type Error = ControlError;
+
+    fn try_from(record: TransferFunctionRecord<T>) -> Result<Self, Self::Error> {
+        record.check_version()?;
+        if record.time_domain != TimeDomain::Continous {
+            return Err(ControlError::TimeDomainMismatch);
+        }
+        Self::from_numden(&record.numerator, &record.denominator)?.with_delay(record.delay)
+    }
+}
+
+impl<T: Float> TryFrom<TransferFunctionRecord<T>> for DiscreteTransferFunction<T> {
+    type Error = ControlError;
+
+    fn try_from(record: TransferFunctionRecord<T>) -> Result<Self, Self::Error> {
+        record.check_version()?;
+        let TimeDomain::Discrete { sample_time } = record.time_domain else {
+            return Err(ControlError::TimeDomainMismatch);
+        };
+        if !record.delay.is_zero() {
+            return Err(ControlError::DelayNotSupported);
+        }
+        Self::from_numden(record.numerator, record.denominator, sample_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        analysis::{frequency::bode_data, time::ResponseType},
+        tf::discretization::DiscretizationMethod,
+    };
+
+    #[test]
+    fn test_discrete_json_layout() {
+        // Given
+        let dtf =
+            DiscreteTransferFunction::from_numden(vec![1.0, -0.5], vec![1.0, -0.9], 0.1).unwrap();
+
+        // When
+        let json = serde_json::to_string(&dtf).unwrap();
+
+        // Then
+        assert_eq!(
+            r#"{"version":1,"time_domain":{"type":"discrete","sample_time":0.1},"numerator":[1.0,-0.5],"denominator":[1.0,-0.9]}"#,
+            json
+        );
+    }
+
+    #[test]
+    fn test_continuous_round_trip() {
+        // Given
+        let ctf = ContinousTransferFunction::from_numden(&[2.0, 1.0], &[1.0, 2.0, 5.0])
+            .unwrap()
+            .with_delay(0.25)
+            .unwrap();
+
+        // When
+        let json = serde_json::to_string(&ctf).unwrap();
+        let loaded: ContinousTransferFunction<f64> = serde_json::from_str(&json).unwrap();
+
+        // Then
+        assert_eq!(
+            r#"{"version":1,"time_domain":{"type":"continuous"},"numerator":[2.0,1.0],"denominator":[1.0,2.0,5.0],"delay":0.25}"#,
+            json
+        );
+        assert_eq!(ctf.numerator(), loaded.numerator());
+        assert_eq!(ctf.denominator(), loaded.denominator());
+        assert_eq!(ctf.poles(), loaded.poles());
+        assert_eq!(0.25, loaded.delay());
+    }
+
+    #[test]
+    fn test_invalid_records_rejected() {
+        // Given
+        let discrete = r#"{"version":1,"time_domain":{"type":"discrete","sample_time":0.1},"numerator":[1.0],"denominator":[1.0,-0.9]}"#;
+        let future = r#"{"version":2,"time_domain":{"type":"continuous"},"numerator":[1.0],"denominator":[1.0,1.0]}"#;
+        let zero_sample_time = r#"{"version":1,"time_domain":{"type":"discrete","sample_time":0.0},"numerator":[1.0],"denominator":[1.0,-0.9]}"#;
+
+        // Then
+        let error = serde_json::from_str::<ContinousTransferFunction<f64>>(discrete).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains(&ControlError::TimeDomainMismatch.to_string())
+        );
+        let error = serde_json::from_str::<ContinousTransferFunction<f64>>(future).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains(&ControlError::UnsupportedSchemaVersion { version: 2 }.to_string())
+        );
+        assert!(serde_json::from_str::<DiscreteTransferFunction<f64>>(zero_sample_time).is_err());
+        assert!(serde_json::from_str::<DiscreteTransferFunction<f64>>(discrete).is_ok());
+    }
+
+    #[test]
+    fn test_design_types() {
+        // Given
+        let ctf = ContinousTransferFunction::from_numden(&[1.0], &[1.0, 1.0]).unwrap();
+        let bode = bode_data(&ctf, &[0.0]);
+        let method = DiscretizationMethod::TustinPrewarped { omega_p: 2.0 };
+
+        // Then
+        assert_eq!(
+            r#"[{"omega":0.0,"mag_db":0.0,"phase_rad":0.0}]"#,
+            serde_json::to_string(&bode).unwrap()
+        );
+        assert_eq!(
+            r#"{"method":"tustin_prewarped","omega_p":2.0}"#,
+            serde_json::to_string(&method).unwrap()
+        );
+        assert_eq!(
+            r#""step""#,
+            serde_json::to_string(&ResponseType::Step).unwrap()
+        );
+        assert_eq!(
+            method,
+            serde_json::from_str(r#"{"method":"tustin_prewarped","omega_p":2.0}"#).unwrap()
+        );
+    }
+}
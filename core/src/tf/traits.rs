@@ -1,13 +1,26 @@
-use ndarray::Array2;
-use ndarray_linalg::Eig;
 use num::{Complex, Float};
 
-use crate::util::poly::convolve;
+use crate::{error::ControlError, util::poly::horner};
 
-#[derive(Debug, PartialEq)]
+pub use crate::util::{
+    poly::coeff_from_pz,
+    roots::{eigenvalues, roots},
+};
+
+use super::zpk::ZeroPoleGain;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
 pub enum TimeDomain<T: Float> {
+    #[cfg_attr(feature = "serde", serde(rename = "continuous"))]
     Continous,
-    Discrete { sample_time: T },
+    Discrete {
+        sample_time: T,
+    },
 }
 
 pub trait TransferFunction<T: Float> {
@@ -22,79 +35,101 @@ pub trait TransferFunction<T: Float> {
     fn frequency_response(&self, omega: &[T]) -> Vec<Complex<T>>;
 
     fn evaluate(&self, eval_point: Complex<T>) -> Complex<T> {
-        let horner = |coeffs: &[T]| {
-            coeffs
-                .iter()
-                .fold(Complex::<T>::new(T::zero(), T::zero()), |acc, coeff| {
-                    acc * eval_point + coeff
-                })
-        };
-        horner(self.numerator()) / horner(self.denominator())
+        horner(self.numerator(), eval_point) / horner(self.denominator(), eval_point)
+    }
+
+    /// Transport delay in seconds that is not part of the numerator and denominator
+    fn delay(&self) -> T {
+        T::zero()
     }
 
     fn order(&self) -> usize {
         self.denominator().len().saturating_sub(1)
     }
 
+    fn zpk(&self) -> ZeroPoleGain<T>
+    where
+        Self: Sized,
+    {
+        ZeroPoleGain::from_tf(self)
+    }
+
     fn normalize_at_w(&mut self, w: T);
 }
 
-// Based on MATLAB roots algorithm (see https://www.mathworks.com/help/matlab/ref/roots.html)
-// Kept as f64 to avoid propagating Lapack trait requirements
-// TODO: Should this be in TF trait file?
-fn roots_f64(coeffs: &[f64]) -> Vec<Complex<f64>> {
-    if coeffs.len() <= 1 {
-        return Vec::new();
+/// Rejects coefficient lists that do not describe a transfer function
+pub fn check_numden<T: Float>(numerator: &[T], denominator: &[T]) -> Result<(), ControlError> {
+    if numerator.is_empty() {
+        return Err(ControlError::EmptyPolynomial {
+            polynomial: "numerator",
+        });
     }
-
-    // Make coeffs monic (normalize to highest-order coefficient)
-    let monic_coeffs: Vec<f64> = coeffs.iter().map(|coeff| coeff / coeffs[0]).collect();
-
-    // Construct companion matrix
-    let n = coeffs.len() - 1;
-    let mut companion_matrix = Array2::<f64>::zeros((n, n));
-    for i in 0..n {
-        // First row: negative coefficients from n-1 to 0
-        companion_matrix[(0, i)] = -monic_coeffs[i + 1];
-        // Remaining rows: subdiagonal matrix
-        if i < n - 1 {
-            companion_matrix[(i + 1, i)] = 1.0;
-        }
+    if denominator.is_empty() {
+        return Err(ControlError::EmptyPolynomial {
+            polynomial: "denominator",
+        });
     }
-
-    // Eigenvalues of companion matrix == roots
-    match companion_matrix.eig() {
-        Ok((eigenvalues, _)) => eigenvalues.to_vec(),
-        Err(_) => vec![],
+    if numerator.iter().chain(denominator).any(|c| !c.is_finite()) {
+        return Err(ControlError::NonFiniteCoefficient);
     }
+    if denominator.iter().all(|c| c.is_zero()) {
+        return Err(ControlError::ZeroDenominator);
+    }
+    Ok(())
 }
 
-pub fn roots<T: Float>(coeffs: &[T]) -> Vec<Complex<T>> {
-    let coeffs_f64: Vec<f64> = coeffs.iter().map(|&x| x.to_f64().unwrap()).collect();
-    roots_f64(&coeffs_f64)
-        .into_iter()
-        .map(|c| Complex::new(T::from(c.re).unwrap(), T::from(c.im).unwrap()))
-        .collect()
-}
-
-pub fn coeff_from_pz<T: Float>(p_or_z: &[Complex<T>]) -> Vec<Complex<T>> {
-    p_or_z
-        .iter()
-        .fold(vec![Complex::<T>::new(T::one(), T::zero())], |acc, &pz| {
-            let poly = vec![Complex::<T>::new(T::one(), T::zero()), -pz];
-            convolve(&acc, &poly)
+pub fn check_sample_time<T: Float>(sample_time: T) -> Result<(), ControlError> {
+    if sample_time > T::zero() && sample_time.is_finite() {
+        Ok(())
+    } else {
+        Err(ControlError::InvalidSampleTime {
+            sample_time: sample_time.to_f64().unwrap(),
         })
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::coeff_from_pz;
+    use super::*;
     use num::complex::Complex64;
 
     #[test]
-    fn test_coeff_from_pz() {
-        let z1 = Complex64::new(-1.0, 2.0);
-        let z2 = Complex64::new(-1.0, -2.0);
-        println!("{:?}", coeff_from_pz(&vec![z1, z2]));
+    fn test_roots_skip_leading_zeroes() {
+        // Given
+        // 0s^2 + s + 2 has a single root at -2
+        let coeffs = [0.0, 1.0, 2.0];
+
+        // When
+        let result = roots(&coeffs).unwrap();
+
+        // Then
+        assert_eq!(vec![Complex64::new(-2.0, 0.0)], result);
+    }
+
+    #[test]
+    fn test_roots_non_finite() {
+        assert_eq!(
+            Err(ControlError::NonFiniteCoefficient),
+            roots(&[1.0, f64::NAN])
+        );
+    }
+
+    #[test]
+    fn test_check_numden() {
+        assert_eq!(
+            Err(ControlError::EmptyPolynomial {
+                polynomial: "denominator"
+            }),
+            check_numden::<f64>(&[1.0], &[])
+        );
+        assert_eq!(
+            Err(ControlError::ZeroDenominator),
+            check_numden(&[1.0], &[0.0, 0.0])
+        );
+        assert_eq!(
+            Err(ControlError::NonFiniteCoefficient),
+            check_numden(&[f64::INFINITY], &[1.0])
+        );
+        assert_eq!(Ok(()), check_numden(&[0.0], &[0.0, 1.0]));
     }
 }
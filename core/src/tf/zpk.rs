@@ -0,0 +1,191 @@
+use num::{Float, complex::Complex};
+
+use crate::{error::ControlError, util::poly::reduce_to_real};
+
+use super::{
+    TransferFunction,
+    traits::{check_numden, coeff_from_pz, roots},
+};
+
+/// Factored form H = k * prod(x - z_i) / prod(x - p_i)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZeroPoleGain<T: Float> {
+    zeroes: Vec<Complex<T>>,
+    poles: Vec<Complex<T>>,
+    gain: T,
+}
+
+impl<T: Float> ZeroPoleGain<T> {
+    pub fn new(zeroes: &[Complex<T>], poles: &[Complex<T>], gain: T) -> Result<Self, ControlError> {
+        check_conjugate_pairs(zeroes)?;
+        check_conjugate_pairs(poles)?;
+        Ok(Self {
+            zeroes: zeroes.to_vec(),
+            poles: poles.to_vec(),
+            gain,
+        })
+    }
+
+    pub fn from_numden(numerator: &[T], denominator: &[T]) -> Result<Self, ControlError> {
+        check_numden(numerator, denominator)?;
+        Ok(Self {
+            zeroes: roots(numerator)?,
+            poles: roots(denominator)?,
+            gain: leading_coefficient(numerator) / leading_coefficient(denominator),
+        })
+    }
+
+    pub fn from_tf(tf: &dyn TransferFunction<T>) -> Self {
+        Self {
+            zeroes: tf.zeroes().to_vec(),
+            poles: tf.poles().to_vec(),
+            gain: leading_coefficient(tf.numerator()) / leading_coefficient(tf.denominator()),
+        }
+    }
+
+    pub fn to_numden(&self) -> (Vec<T>, Vec<T>) {
+        let numerator = reduce_to_real(&coeff_from_pz(&self.zeroes))
+            .iter()
+            .map(|&c| c * self.gain)
+            .collect();
+        let denominator = reduce_to_real(&coeff_from_pz(&self.poles));
+        (numerator, denominator)
+    }
+
+    /// Evaluates the factored form, which stays accurate for high orders and clustered roots
+    pub fn evaluate(&self, x: Complex<T>) -> Complex<T> {
+        let numerator = self
+            .zeroes
+            .iter()
+            .fold(Complex::from(self.gain), |acc, &z| acc * (x - z));
+        let denominator = self
+            .poles
+            .iter()
+            .fold(Complex::from(T::one()), |acc, &p| acc * (x - p));
+        numerator / denominator
+    }
+
+    pub fn zeroes(&self) -> &[Complex<T>] {
+        &self.zeroes
+    }
+
+    pub fn poles(&self) -> &[Complex<T>] {
+        &self.poles
+    }
+
+    pub fn gain(&self) -> T {
+        self.gain
+    }
+}
+
+// Leading zeroes only lower the degree, so the gain comes from the first nonzero coefficient
+fn leading_coefficient<T: Float>(coeffs: &[T]) -> T {
+    coeffs
+        .iter()
+        .copied()
+        .find(|c| !c.is_zero())
+        .unwrap_or(T::zero())
+}
+
+// Every root with a non-negligible imaginary part needs a matching conjugate,
+// otherwise the expanded polynomial has complex coefficients
+pub(crate) fn check_conjugate_pairs<T: Float>(roots: &[Complex<T>]) -> Result<(), ControlError> {
+    let tol = |r: &Complex<T>| T::epsilon().sqrt() * r.norm().max(T::one());
+    let mut paired = vec![false; roots.len()];
+    for (i, root) in roots.iter().enumerate() {
+        if paired[i] || root.im.abs() <= tol(root) {
+            continue;
+        }
+        let conjugate = (i + 1..roots.len())
+            .find(|&j| !paired[j] && (roots[j] - root.conj()).norm() <= tol(root));
+        match conjugate {
+            Some(j) => {
+                paired[i] = true;
+                paired[j] = true;
+            }
+            None => {
+                return Err(ControlError::UnpairedComplexRoot {
+                    re: root.re.to_f64().unwrap(),
+                    im: root.im.to_f64().unwrap(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use num::complex::Complex64;
+
+    use super::*;
+
+    #[test]
+    fn test_to_numden_keeps_gain() {
+        // Given
+        // H(s) = 5(s + 1) / (s + 2)
+        let zpk = ZeroPoleGain::new(
+            &[Complex64::new(-1.0, 0.0)],
+            &[Complex64::new(-2.0, 0.0)],
+            5.0,
+        )
+        .unwrap();
+
+        // When
+        let (num, den) = zpk.to_numden();
+
+        // Then
+        assert_eq!(vec![5.0, 5.0], num);
+        assert_eq!(vec![1.0, 2.0], den);
+    }
+
+    #[test]
+    fn test_conjugate_pair() {
+        // Given
+        // H(s) = 2 / (s^2 + 2s + 5), poles at -1 +- 2j
+        let poles = [Complex64::new(-1.0, 2.0), Complex64::new(-1.0, -2.0)];
+
+        // When
+        let (num, den) = ZeroPoleGain::new(&[], &poles, 2.0).unwrap().to_numden();
+
+        // Then
+        assert_eq!(vec![2.0], num);
+        assert_eq!(vec![1.0, 2.0, 5.0], den);
+    }
+
+    #[test]
+    fn test_unpaired_complex_root() {
+        // Given
+        let poles = [Complex64::new(-1.0, 2.0), Complex64::new(-1.0, 2.0)];
+
+        // When
+        let result = ZeroPoleGain::new(&[], &poles, 1.0);
+
+        // Then
+        assert_eq!(
+            Err(ControlError::UnpairedComplexRoot { re: -1.0, im: 2.0 }),
+            result
+        );
+    }
+
+    #[test]
+    fn test_from_numden_round_trip() {
+        // Given
+        // H(s) = (3s + 6) / (2s^2 + 4s + 10)
+        let num = [3.0, 6.0];
+        let den = [2.0, 4.0, 10.0];
+
+        // When
+        let zpk = ZeroPoleGain::from_numden(&num, &den).unwrap();
+        let (num_result, den_result) = zpk.to_numden();
+
+        // Then
+        assert_relative_eq!(1.5, zpk.gain());
+        assert_relative_eq!(1.5, num_result[0], epsilon = 1e-12);
+        assert_relative_eq!(3.0, num_result[1], epsilon = 1e-12);
+        assert_relative_eq!(1.0, den_result[0], epsilon = 1e-12);
+        assert_relative_eq!(2.0, den_result[1], epsilon = 1e-12);
+        assert_relative_eq!(5.0, den_result[2], epsilon = 1e-12);
+    }
+}
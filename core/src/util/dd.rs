@@ -0,0 +1,734 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    num::FpCategory,
+    ops::{Add, Div, Mul, Neg, Rem, Sub},
+};
+
+use num::{Float, Num, NumCast, One, ToPrimitive, Zero};
+
+/// Double-double number, an unevaluated sum hi + lo with |lo| <= ulp(hi) / 2
+/// Gives about 32 significant digits for the arithmetic, square roots, exp, ln, powf and the
+/// trigonometric functions sin, cos, tan, atan and atan2. The remaining transcendental functions
+/// (inverse sine and cosine, hyperbolic functions, exp_m1, ln_1p, cbrt) are evaluated in f64
+// Algorithms from Hida, Li, Bailey - Library for Double-Double and Quad-Double Arithmetic (2007)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DoubleDouble {
+    hi: f64,
+    lo: f64,
+}
+
+impl DoubleDouble {
+    pub fn new(hi: f64, lo: f64) -> Self {
+        let (hi, lo) = quick_two_sum(hi, lo);
+        Self { hi, lo }
+    }
+
+    pub fn from_f64(value: f64) -> Self {
+        Self { hi: value, lo: 0.0 }
+    }
+
+    pub fn hi(self) -> f64 {
+        self.hi
+    }
+
+    pub fn lo(self) -> f64 {
+        self.lo
+    }
+
+    // Results that overflow or are NaN carry no meaningful low part
+    fn checked(hi: f64, lo: f64) -> Self {
+        if hi.is_finite() {
+            Self { hi, lo }
+        } else {
+            Self { hi, lo: 0.0 }
+        }
+    }
+
+    fn via_f64(self, f: impl Fn(f64) -> f64) -> Self {
+        Self::from_f64(f(self.hi + self.lo))
+    }
+
+    // Exact for powers of two as long as the result stays normal
+    fn mul_pow2(self, exponent: i32) -> Self {
+        let factor = 2f64.powi(exponent);
+        Self::checked(self.hi * factor, self.lo * factor)
+    }
+
+    // x = k ln 2 + r, then e^r from its Taylor series on r / 2^8 and eight squarings. The
+    // series sums e^r - 1 so that the squarings do not lose the small part against the one
+    fn exp_dd(self) -> Self {
+        const HALVINGS: i32 = 8;
+        if !self.hi.is_finite() || self.hi.abs() > 700.0 {
+            return self.via_f64(f64::exp);
+        }
+        let k = (self.hi / LN_2.hi).round();
+        let r = (self - LN_2 * Self::from_f64(k)).mul_pow2(-HALVINGS);
+        let (mut sum, mut term, mut n) = (Self::zero(), Self::one(), 1.0);
+        loop {
+            term = term * r / Self::from_f64(n);
+            sum = sum + term;
+            if term.hi.abs() <= Self::epsilon().hi * sum.hi.abs() {
+                break;
+            }
+            n += 1.0;
+        }
+        for _ in 0..HALVINGS {
+            // (1 + s)^2 = 1 + (2s + s^2)
+            sum = sum.mul_pow2(1) + sum * sum;
+        }
+        (sum + Self::one()).mul_pow2(k as i32)
+    }
+
+    // One Newton step y + x e^-y - 1 on the f64 logarithm doubles the number of correct digits
+    fn ln_dd(self) -> Self {
+        if self.hi <= 0.0 || !self.hi.is_finite() {
+            return self.via_f64(f64::ln);
+        }
+        let y = Self::from_f64(self.hi.ln());
+        y + self * (-y).exp_dd() - Self::one()
+    }
+
+    // x = k pi/2 + r with |r| <= pi/4, Taylor series of sin r and cos r, then the quadrant k
+    // picks the signs. Very large arguments lose the reduction accuracy and fall back to f64
+    fn sin_cos_dd(self) -> (Self, Self) {
+        if !self.hi.is_finite() || self.hi.abs() > 1e15 {
+            let x = self.hi + self.lo;
+            return (Self::from_f64(x.sin()), Self::from_f64(x.cos()));
+        }
+        let k = (self.hi / FRAC_PI_2.hi).round();
+        let r = self - FRAC_PI_2 * Self::from_f64(k);
+        let r_squared = r * r;
+        let (mut sin, mut cos) = (r, Self::one());
+        let (mut sin_term, mut cos_term, mut n) = (r, Self::one(), 1.0);
+        loop {
+            sin_term = -sin_term * r_squared / Self::from_f64((2.0 * n) * (2.0 * n + 1.0));
+            cos_term = -cos_term * r_squared / Self::from_f64((2.0 * n - 1.0) * (2.0 * n));
+            sin = sin + sin_term;
+            cos = cos + cos_term;
+            let eps = Self::epsilon().hi;
+            if sin_term.hi.abs() <= eps * sin.hi.abs() && cos_term.hi.abs() <= eps {
+                break;
+            }
+            n += 1.0;
+        }
+        match (k as i64).rem_euclid(4) {
+            0 => (sin, cos),
+            1 => (cos, -sin),
+            2 => (-sin, -cos),
+            _ => (-cos, sin),
+        }
+    }
+}
+
+// ln 2, pi / 2 and ln 10 rounded to double-double
+const LN_2: DoubleDouble = DoubleDouble {
+    hi: std::f64::consts::LN_2,
+    lo: 2.3190468138462996e-17,
+};
+const FRAC_PI_2: DoubleDouble = DoubleDouble {
+    hi: std::f64::consts::FRAC_PI_2,
+    lo: 6.123233995736766e-17,
+};
+const LN_10: DoubleDouble = DoubleDouble {
+    hi: std::f64::consts::LN_10,
+    lo: -2.1707562233822494e-16,
+};
+
+// a + b = s + e exactly, needs |a| >= |b|
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    (s, b - (s - a))
+}
+
+// a + b = s + e exactly
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let bb = s - a;
+    (s, (a - (s - bb)) + (b - bb))
+}
+
+// a * b = p + e exactly
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    let p = a * b;
+    (p, a.mul_add(b, -p))
+}
+
+impl From<f64> for DoubleDouble {
+    fn from(value: f64) -> Self {
+        Self::from_f64(value)
+    }
+}
+
+impl fmt::Display for DoubleDouble {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&(self.hi + self.lo), f)
+    }
+}
+
+impl PartialOrd for DoubleDouble {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match self.hi.partial_cmp(&other.hi)? {
+            Ordering::Equal => self.lo.partial_cmp(&other.lo),
+            ordering => Some(ordering),
+        }
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let (s, e) = two_sum(self.hi, rhs.hi);
+        let (t, f) = two_sum(self.lo, rhs.lo);
+        let (s, e) = quick_two_sum(s, e + t);
+        let (hi, lo) = quick_two_sum(s, e + f);
+        Self::checked(hi, lo)
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let (p, e) = two_prod(self.hi, rhs.hi);
+        let (hi, lo) = quick_two_sum(p, e + (self.hi * rhs.lo + self.lo * rhs.hi));
+        Self::checked(hi, lo)
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = Self;
+
+    // Long division, each quotient digit corrects the remainder of the previous one
+    fn div(self, rhs: Self) -> Self {
+        let q1 = self.hi / rhs.hi;
+        if !q1.is_finite() {
+            return Self::from_f64(q1);
+        }
+        let r = self - rhs * Self::from_f64(q1);
+        let q2 = r.hi / rhs.hi;
+        let r = r - rhs * Self::from_f64(q2);
+        let q3 = r.hi / rhs.hi;
+        let (hi, lo) = quick_two_sum(q1, q2);
+        Self { hi, lo } + Self::from_f64(q3)
+    }
+}
+
+impl Rem for DoubleDouble {
+    type Output = Self;
+
+    fn rem(self, rhs: Self) -> Self {
+        self - (self / rhs).trunc() * rhs
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Zero for DoubleDouble {
+    fn zero() -> Self {
+        Self::from_f64(0.0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.hi == 0.0
+    }
+}
+
+impl One for DoubleDouble {
+    fn one() -> Self {
+        Self::from_f64(1.0)
+    }
+}
+
+impl Num for DoubleDouble {
+    type FromStrRadixErr = <f64 as Num>::FromStrRadixErr;
+
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        f64::from_str_radix(s, radix).map(Self::from_f64)
+    }
+}
+
+impl ToPrimitive for DoubleDouble {
+    fn to_i64(&self) -> Option<i64> {
+        let value = self.trunc();
+        Some(value.hi.to_i64()? + value.lo.to_i64()?)
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        let value = self.trunc();
+        value.hi.to_u64()?.checked_add_signed(value.lo.to_i64()?)
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(self.hi + self.lo)
+    }
+}
+
+impl NumCast for DoubleDouble {
+    fn from<N: ToPrimitive>(n: N) -> Option<Self> {
+        // Integers beyond 2^53 need the low part to stay exact, fractions would be truncated
+        if let Some(i) = n.to_i64()
+            && n.to_f64().is_none_or(|value| value.fract() == 0.0)
+        {
+            let hi = i as f64;
+            let lo = (i - hi as i64) as f64;
+            return Some(Self::new(hi, lo));
+        }
+        n.to_f64().map(Self::from_f64)
+    }
+}
+
+impl Float for DoubleDouble {
+    fn nan() -> Self {
+        Self::from_f64(f64::NAN)
+    }
+
+    fn infinity() -> Self {
+        Self::from_f64(f64::INFINITY)
+    }
+
+    fn neg_infinity() -> Self {
+        Self::from_f64(f64::NEG_INFINITY)
+    }
+
+    fn neg_zero() -> Self {
+        Self::from_f64(-0.0)
+    }
+
+    fn min_value() -> Self {
+        Self::from_f64(f64::MIN)
+    }
+
+    fn min_positive_value() -> Self {
+        Self::from_f64(f64::MIN_POSITIVE)
+    }
+
+    fn max_value() -> Self {
+        Self::from_f64(f64::MAX)
+    }
+
+    // 2^-104
+    fn epsilon() -> Self {
+        Self::from_f64(4.930380657631324e-32)
+    }
+
+    fn is_nan(self) -> bool {
+        self.hi.is_nan()
+    }
+
+    fn is_infinite(self) -> bool {
+        self.hi.is_infinite()
+    }
+
+    fn is_finite(self) -> bool {
+        self.hi.is_finite()
+    }
+
+    fn is_normal(self) -> bool {
+        self.hi.is_normal()
+    }
+
+    fn classify(self) -> FpCategory {
+        self.hi.classify()
+    }
+
+    fn floor(self) -> Self {
+        let hi = self.hi.floor();
+        if hi == self.hi {
+            Self::new(hi, self.lo.floor())
+        } else {
+            Self::from_f64(hi)
+        }
+    }
+
+    fn ceil(self) -> Self {
+        let hi = self.hi.ceil();
+        if hi == self.hi {
+            Self::new(hi, self.lo.ceil())
+        } else {
+            Self::from_f64(hi)
+        }
+    }
+
+    fn round(self) -> Self {
+        if self.is_sign_negative() {
+            -(-self).round()
+        } else {
+            (self + Self::from_f64(0.5)).floor()
+        }
+    }
+
+    fn trunc(self) -> Self {
+        if self.is_sign_negative() {
+            self.ceil()
+        } else {
+            self.floor()
+        }
+    }
+
+    fn fract(self) -> Self {
+        self - self.trunc()
+    }
+
+    fn abs(self) -> Self {
+        if self.is_sign_negative() { -self } else { self }
+    }
+
+    fn signum(self) -> Self {
+        Self::from_f64(self.hi.signum())
+    }
+
+    fn is_sign_positive(self) -> bool {
+        self.hi.is_sign_positive()
+    }
+
+    fn is_sign_negative(self) -> bool {
+        self.hi.is_sign_negative()
+    }
+
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self * a + b
+    }
+
+    fn recip(self) -> Self {
+        Self::one() / self
+    }
+
+    fn powi(self, n: i32) -> Self {
+        let mut base = self;
+        let mut exponent = n.unsigned_abs();
+        let mut result = Self::one();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            exponent >>= 1;
+        }
+        if n < 0 { result.recip() } else { result }
+    }
+
+    // Negative bases only have real powers for integer exponents, those stay in f64
+    fn powf(self, n: Self) -> Self {
+        if self.hi > 0.0 {
+            (n * self.ln_dd()).exp_dd()
+        } else {
+            self.via_f64(|x| x.powf(n.hi + n.lo))
+        }
+    }
+
+    // One Newton step on the f64 square root doubles the number of correct digits
+    fn sqrt(self) -> Self {
+        if self.hi <= 0.0 || !self.hi.is_finite() {
+            return Self::from_f64(self.hi.sqrt());
+        }
+        let x = Self::from_f64(self.hi.sqrt());
+        x + (self - x * x) / (x * Self::from_f64(2.0))
+    }
+
+    fn exp(self) -> Self {
+        self.exp_dd()
+    }
+
+    fn exp2(self) -> Self {
+        (self * LN_2).exp_dd()
+    }
+
+    fn ln(self) -> Self {
+        self.ln_dd()
+    }
+
+    fn log(self, base: Self) -> Self {
+        self.ln_dd() / base.ln_dd()
+    }
+
+    fn log2(self) -> Self {
+        self.ln_dd() / LN_2
+    }
+
+    fn log10(self) -> Self {
+        self.ln_dd() / LN_10
+    }
+
+    fn max(self, other: Self) -> Self {
+        if self.is_nan() || other > self {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn min(self, other: Self) -> Self {
+        if self.is_nan() || other < self {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn abs_sub(self, other: Self) -> Self {
+        if self <= other {
+            Self::zero()
+        } else {
+            self - other
+        }
+    }
+
+    fn cbrt(self) -> Self {
+        self.via_f64(f64::cbrt)
+    }
+
+    fn hypot(self, other: Self) -> Self {
+        let (a, b) = (self.abs(), other.abs());
+        let (big, small) = if a > b { (a, b) } else { (b, a) };
+        if big.is_zero() || big.is_infinite() {
+            return big;
+        }
+        let ratio = small / big;
+        big * (Self::one() + ratio * ratio).sqrt()
+    }
+
+    fn sin(self) -> Self {
+        self.sin_cos_dd().0
+    }
+
+    fn cos(self) -> Self {
+        self.sin_cos_dd().1
+    }
+
+    fn tan(self) -> Self {
+        let (sin, cos) = self.sin_cos_dd();
+        sin / cos
+    }
+
+    fn asin(self) -> Self {
+        self.via_f64(f64::asin)
+    }
+
+    fn acos(self) -> Self {
+        self.via_f64(f64::acos)
+    }
+
+    fn atan(self) -> Self {
+        self.atan2(Self::one())
+    }
+
+    // One Newton step on the f64 angle, the correction is tan of the remaining angle error
+    fn atan2(self, other: Self) -> Self {
+        let theta = Self::from_f64((self.hi + self.lo).atan2(other.hi + other.lo));
+        if !theta.hi.is_finite() || (self.hi == 0.0 && other.hi == 0.0) {
+            return theta;
+        }
+        let (sin, cos) = theta.sin_cos_dd();
+        theta + (self * cos - other * sin) / (other * cos + self * sin)
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        self.sin_cos_dd()
+    }
+
+    fn exp_m1(self) -> Self {
+        self.via_f64(f64::exp_m1)
+    }
+
+    fn ln_1p(self) -> Self {
+        self.via_f64(f64::ln_1p)
+    }
+
+    fn sinh(self) -> Self {
+        self.via_f64(f64::sinh)
+    }
+
+    fn cosh(self) -> Self {
+        self.via_f64(f64::cosh)
+    }
+
+    fn tanh(self) -> Self {
+        self.via_f64(f64::tanh)
+    }
+
+    fn asinh(self) -> Self {
+        self.via_f64(f64::asinh)
+    }
+
+    fn acosh(self) -> Self {
+        self.via_f64(f64::acosh)
+    }
+
+    fn atanh(self) -> Self {
+        self.via_f64(f64::atanh)
+    }
+
+    fn integer_decode(self) -> (u64, i16, i8) {
+        self.hi.integer_decode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use num::complex::Complex64;
+
+    use super::*;
+    use crate::util::{poly::Polynomial, roots::roots};
+
+    fn dd(value: f64) -> DoubleDouble {
+        DoubleDouble::from_f64(value)
+    }
+
+    #[test]
+    fn test_arithmetic_keeps_low_part() {
+        // Given
+        // 1 + 2^-80 is not representable in f64
+        let tiny = dd(2f64.powi(-80));
+
+        // When
+        let sum = dd(1.0) + tiny;
+
+        // Then
+        assert_eq!(1.0, sum.hi());
+        assert_eq!(tiny.hi(), sum.lo());
+        assert_eq!(tiny, sum - dd(1.0));
+        assert_eq!(dd(1.0), (dd(1.0) / dd(3.0)) * dd(3.0));
+        let third = dd(1.0) / dd(3.0);
+        assert!((third * dd(3.0) - dd(1.0)).abs() < DoubleDouble::epsilon());
+    }
+
+    #[test]
+    fn test_cast_keeps_fractions_and_large_integers() {
+        // Given
+        let large = (1i64 << 60) + 1;
+
+        // When
+        let fraction: DoubleDouble = NumCast::from(0.75).unwrap();
+        let negative: DoubleDouble = NumCast::from(-1e-9).unwrap();
+        let integer: DoubleDouble = NumCast::from(large).unwrap();
+
+        // Then
+        assert_eq!(dd(0.75), fraction);
+        assert_eq!(dd(-1e-9), negative);
+        assert_eq!(Some(large), integer.to_i64());
+    }
+
+    #[test]
+    fn test_sqrt() {
+        // Given
+        let two = dd(2.0);
+
+        // When
+        let root = two.sqrt();
+
+        // Then
+        assert!((root * root - two).abs() < dd(4.0) * DoubleDouble::epsilon());
+        assert_relative_eq!(std::f64::consts::SQRT_2, root.hi());
+    }
+
+    #[test]
+    fn test_exp_ln() {
+        // Given
+        let e = DoubleDouble::new(std::f64::consts::E, 1.4456468917292502e-16);
+        let ln_3 = DoubleDouble::new(1.0986122886681098, -9.07129723500153e-17);
+        let exp_minus_20 = DoubleDouble::new(2.061153622438558e-09, -4.19755767595054e-26);
+        let tol = dd(4.0) * DoubleDouble::epsilon();
+
+        // When
+        let exp_one = dd(1.0).exp();
+        let ln_three = dd(3.0).ln();
+        let exp_small = dd(-20.0).exp();
+
+        // Then
+        assert!((exp_one - e).abs() < tol * e);
+        assert!((ln_three - ln_3).abs() < tol * ln_3);
+        assert!((exp_small - exp_minus_20).abs() < tol * exp_minus_20);
+        assert!((dd(2.0).powf(dd(0.5)) - dd(2.0).sqrt()).abs() < tol);
+    }
+
+    #[test]
+    fn test_trigonometric() {
+        // Given
+        let sin_1 = DoubleDouble::new(0.8414709848078965, 1.776845092935536e-18);
+        let cos_1 = DoubleDouble::new(0.5403023058681398, -4.760954612604417e-17);
+        let sin_10 = DoubleDouble::new(-0.5440211108893698, -3.8949898668223557e-17);
+        let pi = DoubleDouble::new(std::f64::consts::PI, 1.2246467991473532e-16);
+        let tol = dd(4.0) * DoubleDouble::epsilon();
+
+        // When
+        let (sin, cos) = dd(1.0).sin_cos();
+        let angle = dd(1.0).atan2(dd(1.0));
+
+        // Then
+        assert!((sin - sin_1).abs() < tol);
+        assert!((cos - cos_1).abs() < tol);
+        assert!((dd(10.0).sin() - sin_10).abs() < tol);
+        assert!((angle * dd(4.0) - pi).abs() < tol * pi);
+        assert!((dd(-1.0).atan2(dd(-1.0)) + dd(0.75) * pi).abs() < tol * pi);
+    }
+
+    #[test]
+    fn test_expm_precision() {
+        // Given
+        // exp([[0, 1], [-1, 0]]) = [[cos 1, sin 1], [-sin 1, cos 1]]
+        let a = ndarray::array![[dd(0.0), dd(1.0)], [dd(-1.0), dd(0.0)]];
+        let sin_1 = DoubleDouble::new(0.8414709848078965, 1.776845092935536e-18);
+        let cos_1 = DoubleDouble::new(0.5403023058681398, -4.760954612604417e-17);
+        let tol = dd(1e-30);
+
+        // When
+        let result = crate::util::linalg::expm(&a);
+
+        // Then
+        assert!((result[(0, 0)] - cos_1).abs() < tol);
+        assert!((result[(0, 1)] - sin_1).abs() < tol);
+        assert!((result[(1, 0)] + sin_1).abs() < tol);
+        assert!((result[(1, 1)] - cos_1).abs() < tol);
+    }
+
+    #[test]
+    fn test_multiple_root_accuracy() {
+        // Given
+        // (x - 1)^4 (x + 2): a fourfold root is only accurate to about eps^(1/4)
+        let factors = [
+            Complex64::new(1.0, 0.0),
+            Complex64::new(1.0, 0.0),
+            Complex64::new(1.0, 0.0),
+            Complex64::new(1.0, 0.0),
+            Complex64::new(-2.0, 0.0),
+        ];
+        let coeffs = Polynomial::from_roots(&factors).coeffs().to_vec();
+        let coeffs_dd: Vec<DoubleDouble> = coeffs.iter().map(|&c| dd(c)).collect();
+
+        // When
+        let result_f64 = roots(&coeffs).unwrap();
+        let result_dd = roots(&coeffs_dd).unwrap();
+
+        // Then
+        let error_f64 = result_f64[1..]
+            .iter()
+            .fold(0.0, |acc: f64, r| acc.max((r - 1.0).norm()));
+        let error_dd = result_dd[1..].iter().fold(0.0, |acc: f64, r| {
+            let re = (r.re - dd(1.0)).to_f64().unwrap();
+            acc.max(re.hypot(r.im.to_f64().unwrap()))
+        });
+        assert_relative_eq!(-2.0, result_dd[0].re.hi(), epsilon = 1e-15);
+        assert!(error_dd < 1e-7, "double-double error {}", error_dd);
+        assert!(error_dd < error_f64 * 1e-3);
+    }
+}
@@ -0,0 +1,597 @@
+use ndarray::Array2;
+use num::{Float, complex::Complex};
+
+pub fn matmul<T: Float>(a: &Array2<T>, b: &Array2<T>) -> Array2<T> {
+    Array2::from_shape_fn((a.nrows(), b.ncols()), |(i, j)| {
+        (0..a.ncols()).fold(T::zero(), |acc, k| acc + a[(i, k)] * b[(k, j)])
+    })
+}
+
+pub fn trace<T: Float>(a: &Array2<T>) -> T {
+    a.diag().iter().fold(T::zero(), |acc, &x| acc + x)
+}
+
+fn norm_1<T: Float>(a: &Array2<T>) -> T {
+    a.columns()
+        .into_iter()
+        .map(|col| col.iter().fold(T::zero(), |acc, x| acc + x.abs()))
+        .fold(T::zero(), T::max)
+}
+
+// Scaling and squaring with a truncated Taylor series
+// The matrix is scaled until its 1-norm is below 0.5 and the series runs until the terms drop
+// below the precision of T, about 16 terms for f64 and 30 for double-double
+const MAX_TAYLOR_TERMS: usize = 64;
+
+pub fn expm<T: Float>(a: &Array2<T>) -> Array2<T> {
+    let norm = norm_1(a);
+    let half = T::from(0.5).unwrap();
+    let squarings = if norm > half {
+        (norm / half).log2().ceil().to_i32().unwrap()
+    } else {
+        0
+    };
+    let scale = T::from(2.0).unwrap().powi(squarings);
+    let scaled = a.mapv(|x| x / scale);
+
+    let mut result = Array2::<T>::eye(a.nrows());
+    let mut term = Array2::<T>::eye(a.nrows());
+    for k in 1..=MAX_TAYLOR_TERMS {
+        let k = T::from(k).unwrap();
+        term = matmul(&term, &scaled).mapv(|x| x / k);
+        result = result + &term;
+        if norm_1(&term) <= T::epsilon() * norm_1(&result) {
+            break;
+        }
+    }
+
+    for _ in 0..squarings {
+        result = matmul(&result, &result);
+    }
+    result
+}
+
+// Gauss-Jordan elimination with partial pivoting, None if A is singular
+pub fn inv<T: Float>(a: &Array2<T>) -> Option<Array2<T>> {
+    let n = a.nrows();
+    let mut lhs = a.clone();
+    let mut rhs = Array2::<T>::eye(n);
+    let tol = norm_1(a) * T::epsilon();
+
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&i, &j| {
+                lhs[(i, col)]
+                    .abs()
+                    .partial_cmp(&lhs[(j, col)].abs())
+                    .unwrap()
+            })
+            .unwrap();
+        if lhs[(pivot, col)].abs() <= tol {
+            return None;
+        }
+        for k in 0..n {
+            lhs.swap((col, k), (pivot, k));
+            rhs.swap((col, k), (pivot, k));
+        }
+        let p = lhs[(col, col)];
+        for k in 0..n {
+            lhs[(col, k)] = lhs[(col, k)] / p;
+            rhs[(col, k)] = rhs[(col, k)] / p;
+        }
+        for row in (0..n).filter(|&row| row != col) {
+            let factor = lhs[(row, col)];
+            if factor != T::zero() {
+                for k in 0..n {
+                    lhs[(row, k)] = lhs[(row, k)] - factor * lhs[(col, k)];
+                    rhs[(row, k)] = rhs[(row, k)] - factor * rhs[(col, k)];
+                }
+            }
+        }
+    }
+    Some(rhs)
+}
+
+// Principal square root by Denman-Beavers iteration, None if the iteration breaks down
+pub fn sqrtm<T: Float>(a: &Array2<T>) -> Option<Array2<T>> {
+    let half = T::from(0.5).unwrap();
+    let tol = T::epsilon() * T::from(a.nrows().max(1) * 10).unwrap();
+    let mut y = a.clone();
+    let mut z = Array2::<T>::eye(a.nrows());
+    for _ in 0..100 {
+        let y_next = (&y + &inv(&z)?).mapv(|x| x * half);
+        let z_next = (&z + &inv(&y)?).mapv(|x| x * half);
+        let step = norm_1(&(&y_next - &y));
+        y = y_next;
+        z = z_next;
+        if step <= tol * norm_1(&y) {
+            return Some(y);
+        }
+    }
+    None
+}
+
+// Inverse scaling and squaring: square roots bring A close to I, where
+// log(A) = 2 * artanh((A - I)(A + I)^-1) converges quickly
+// None if A has no real logarithm (e.g. eigenvalues on the negative real axis) or is singular
+pub fn logm<T: Float>(a: &Array2<T>) -> Option<Array2<T>> {
+    let n = a.nrows();
+    let identity = Array2::<T>::eye(n);
+    let quarter = T::from(0.25).unwrap();
+
+    let mut x = a.clone();
+    let mut square_roots = 0;
+    while norm_1(&(&x - &identity)) > quarter {
+        x = sqrtm(&x)?;
+        square_roots += 1;
+        if square_roots > 64 {
+            return None;
+        }
+    }
+
+    let y = matmul(&(&x - &identity), &inv(&(&x + &identity))?);
+    let y_squared = matmul(&y, &y);
+    let mut term = y.clone();
+    let mut result = y;
+    for k in (3..=31).step_by(2) {
+        term = matmul(&term, &y_squared);
+        let k = T::from(k).unwrap();
+        result = result + &term.mapv(|x| x / k);
+    }
+
+    let scale = T::from(2.0).unwrap().powi(square_roots + 1);
+    let result = result.mapv(|x| x * scale);
+    result.iter().all(|x| x.is_finite()).then_some(result)
+}
+
+// Faddeev-LeVerrier algorithm (see https://en.wikipedia.org/wiki/Faddeev%E2%80%93LeVerrier_algorithm)
+// Returns the monic characteristic polynomial of A (highest power first) and the matrices
+// M_1..M_n such that adj(zI - A) = sum(M_k * z^(n-k))
+pub fn faddeev_leverrier<T: Float>(a: &Array2<T>) -> (Vec<T>, Vec<Array2<T>>) {
+    let n = a.nrows();
+    let identity = Array2::<T>::eye(n);
+    let mut char_poly = vec![T::one()];
+    let mut adjugate_coeffs = Vec::with_capacity(n);
+
+    let mut m = identity.clone();
+    for k in 1..=n {
+        if k > 1 {
+            let c_prev = char_poly[k - 1];
+            m = matmul(a, &m) + &identity.mapv(|x| x * c_prev);
+        }
+        let c_k = -trace(&matmul(a, &m)) / T::from(k).unwrap();
+        adjugate_coeffs.push(m.clone());
+        char_poly.push(c_k);
+    }
+    (char_poly, adjugate_coeffs)
+}
+
+// One-sided Jacobi (Hestenes) SVD: plane rotations orthogonalize the columns,
+// whose norms are then the singular values. Returned in descending order
+pub fn singular_values<T: Float>(a: &Array2<T>) -> Vec<T> {
+    // Work on the taller orientation, the singular values are the same
+    let mut u = if a.nrows() >= a.ncols() {
+        a.clone()
+    } else {
+        a.t().to_owned()
+    };
+    let n = u.ncols();
+    let two = T::from(2.0).unwrap();
+
+    for _ in 0..64 {
+        let mut rotated = false;
+        for p in 0..n {
+            for q in p + 1..n {
+                let (alpha, beta, gamma) = u.rows().into_iter().fold(
+                    (T::zero(), T::zero(), T::zero()),
+                    |(alpha, beta, gamma), row| {
+                        (
+                            alpha + row[p] * row[p],
+                            beta + row[q] * row[q],
+                            gamma + row[p] * row[q],
+                        )
+                    },
+                );
+                if gamma.abs() <= T::epsilon() * (alpha * beta).sqrt() {
+                    continue;
+                }
+                rotated = true;
+                let zeta = (beta - alpha) / (two * gamma);
+                let t = zeta.signum() / (zeta.abs() + (T::one() + zeta * zeta).sqrt());
+                let c = T::one() / (T::one() + t * t).sqrt();
+                let s = c * t;
+                for mut row in u.rows_mut() {
+                    let (up, uq) = (row[p], row[q]);
+                    row[p] = c * up - s * uq;
+                    row[q] = s * up + c * uq;
+                }
+            }
+        }
+        if !rotated {
+            break;
+        }
+    }
+
+    let mut sigma: Vec<T> = u
+        .columns()
+        .into_iter()
+        .map(|col| col.iter().fold(T::zero(), |acc, &x| acc + x * x).sqrt())
+        .collect();
+    sigma.sort_by(|a, b| b.partial_cmp(a).unwrap());
+    sigma
+}
+
+// The real matrix [[Re, -Im], [Im, Re]] has every singular value of the complex matrix twice
+pub fn complex_singular_values<T: Float>(a: &Array2<Complex<T>>) -> Vec<T> {
+    let (rows, cols) = a.dim();
+    let embedded = Array2::from_shape_fn((2 * rows, 2 * cols), |(i, j)| {
+        let z = a[(i % rows, j % cols)];
+        match (i < rows, j < cols) {
+            (true, true) | (false, false) => z.re,
+            (true, false) => -z.im,
+            (false, true) => z.im,
+        }
+    });
+    singular_values(&embedded)
+        .into_iter()
+        .step_by(2)
+        .take(rows.min(cols))
+        .collect()
+}
+
+// Eigenvalues of a general real matrix: diagonal balancing, orthogonal reduction to upper
+// Hessenberg form with Householder reflectors, then implicit Francis double-shift QR steps
+// (see Golub & Van Loan - Matrix Computations, algorithms 7.4.2 and 7.5.1, and the LAPACK
+// routines dgebal and dlahqr). None if the iteration does not converge
+pub fn eigenvalues_qr<T: Float>(a: &Array2<T>) -> Option<Vec<Complex<T>>> {
+    let mut h = a.clone();
+    balance(&mut h);
+    hessenberg(&mut h);
+    hessenberg_qr(h)
+}
+
+// Scales row and column i by 1/f and f, a power of two so no rounding is introduced, until
+// the off-diagonal row and column norms are within a factor of two of each other. The
+// eigenvalues are unchanged but much less sensitive for badly scaled companion matrices
+fn balance<T: Float>(a: &mut Array2<T>) {
+    const MAX_SWEEPS: usize = 100;
+    let n = a.nrows();
+    let two = T::from(2.0).unwrap();
+    for _ in 0..MAX_SWEEPS {
+        let mut converged = true;
+        for i in 0..n {
+            let (col, row) = (0..n)
+                .filter(|&j| j != i)
+                .fold((T::zero(), T::zero()), |(col, row), j| {
+                    (col + a[(j, i)].abs(), row + a[(i, j)].abs())
+                });
+            if col.is_zero() || row.is_zero() {
+                continue;
+            }
+            // col * f and row / f are closest for f = sqrt(row / col)
+            let exponent = (0.5 * (row / col).to_f64().unwrap().log2()).round() as i32;
+            let f = two.powi(exponent);
+            if exponent == 0 || col * f + row / f >= T::from(0.95).unwrap() * (col + row) {
+                continue;
+            }
+            converged = false;
+            for j in 0..n {
+                a[(i, j)] = a[(i, j)] / f;
+                a[(j, i)] = a[(j, i)] * f;
+            }
+        }
+        if converged {
+            break;
+        }
+    }
+}
+
+// Reflector I - beta v v^T with v[0] = 1 that maps x onto a multiple of the first unit vector,
+// None when x is already zero
+fn householder<T: Float>(x: &[T]) -> Option<(Vec<T>, T)> {
+    let norm = x.iter().fold(T::zero(), |acc, &v| acc.hypot(v));
+    if norm.is_zero() {
+        return None;
+    }
+    // Adding the norm with the sign of x[0] avoids cancellation in v[0]
+    let alpha = if x[0] < T::zero() { -norm } else { norm };
+    let head = x[0] + alpha;
+    let mut v: Vec<T> = x.iter().map(|&value| value / head).collect();
+    v[0] = T::one();
+    let beta = head / alpha;
+    Some((v, beta))
+}
+
+// P a for the rows `first_row..` that v spans, restricted to the given columns
+fn reflect_rows<T: Float>(
+    a: &mut Array2<T>,
+    (v, beta): &(Vec<T>, T),
+    first_row: usize,
+    columns: std::ops::RangeInclusive<usize>,
+) {
+    for j in columns {
+        let dot = v
+            .iter()
+            .enumerate()
+            .fold(T::zero(), |acc, (k, &vk)| acc + vk * a[(first_row + k, j)]);
+        for (k, &vk) in v.iter().enumerate() {
+            a[(first_row + k, j)] = a[(first_row + k, j)] - *beta * vk * dot;
+        }
+    }
+}
+
+// a P for the columns `first_column..` that v spans, restricted to the given rows
+fn reflect_columns<T: Float>(
+    a: &mut Array2<T>,
+    (v, beta): &(Vec<T>, T),
+    first_column: usize,
+    rows: std::ops::RangeInclusive<usize>,
+) {
+    for i in rows {
+        let dot = v.iter().enumerate().fold(T::zero(), |acc, (k, &vk)| {
+            acc + vk * a[(i, first_column + k)]
+        });
+        for (k, &vk) in v.iter().enumerate() {
+            a[(i, first_column + k)] = a[(i, first_column + k)] - *beta * vk * dot;
+        }
+    }
+}
+
+// Zeroes column k below the subdiagonal with one reflector per column, applied on both sides
+fn hessenberg<T: Float>(a: &mut Array2<T>) {
+    let n = a.nrows();
+    for k in 0..n.saturating_sub(2) {
+        let x: Vec<T> = (k + 1..n).map(|i| a[(i, k)]).collect();
+        if let Some(reflector) = householder(&x) {
+            reflect_rows(a, &reflector, k + 1, k..=n - 1);
+            reflect_columns(a, &reflector, k + 1, 0..=n - 1);
+            for i in k + 2..n {
+                a[(i, k)] = T::zero();
+            }
+        }
+    }
+}
+
+// Eigenvalues [lambda_1, lambda_2] of [[a, b], [c, d]], conjugate pairs come out exact
+fn eigenvalues_2x2<T: Float>(a: T, b: T, c: T, d: T) -> [Complex<T>; 2] {
+    let half = T::from(0.5).unwrap();
+    let p = half * (a - d);
+    let discriminant = p * p + b * c;
+    if discriminant >= T::zero() {
+        // d + p +- r, with the smaller root from the product to avoid cancellation
+        let r = discriminant.sqrt();
+        let s = if p < T::zero() { p - r } else { p + r };
+        let lower = if s.is_zero() { d } else { d - b * c / s };
+        [Complex::from(d + s), Complex::from(lower)]
+    } else {
+        let im = (-discriminant).sqrt();
+        [Complex::new(d + p, im), Complex::new(d + p, -im)]
+    }
+}
+
+// Francis double-shift QR on an upper Hessenberg matrix. The active block h[low..=high]
+// shrinks whenever a subdiagonal entry becomes negligible, splitting off one real eigenvalue
+// or the pair of a trailing 2x2 block. Only the active block is updated, which is enough for
+// the eigenvalues
+fn hessenberg_qr<T: Float>(mut h: Array2<T>) -> Option<Vec<Complex<T>>> {
+    const MAX_ITERATIONS: usize = 60;
+    let n = h.nrows();
+    let mut eigenvalues = Vec::with_capacity(n);
+    let norm = h.iter().fold(T::zero(), |acc, x| acc + x.abs());
+    // Relative tests instead of x + s == s, which never holds for types such as double-double
+    // that can carry a tiny low part next to a large value
+    let eps = T::epsilon();
+
+    let mut high = n;
+    let mut iterations = 0;
+    while high > 0 {
+        let last = high - 1;
+        let mut low = last;
+        while low > 0 {
+            let mut scale = h[(low - 1, low - 1)].abs() + h[(low, low)].abs();
+            if scale.is_zero() {
+                scale = norm;
+            }
+            if h[(low, low - 1)].abs() <= eps * scale {
+                h[(low, low - 1)] = T::zero();
+                break;
+            }
+            low -= 1;
+        }
+
+        if low == last {
+            eigenvalues.push(Complex::from(h[(last, last)]));
+            high -= 1;
+            iterations = 0;
+            continue;
+        }
+        if low + 1 == last {
+            eigenvalues.extend(eigenvalues_2x2(
+                h[(low, low)],
+                h[(low, last)],
+                h[(last, low)],
+                h[(last, last)],
+            ));
+            high -= 2;
+            iterations = 0;
+            continue;
+        }
+        if iterations == MAX_ITERATIONS {
+            return None;
+        }
+        iterations += 1;
+
+        // Shifts are the eigenvalues of the trailing 2x2 block, entered through their sum and
+        // product. Every tenth step uses an ad hoc shift to break cycles, as LAPACK does
+        let (sum, product) = if iterations % 10 == 0 {
+            let s = h[(last, last - 1)].abs() + h[(last - 1, last - 2)].abs();
+            let diagonal = h[(last, last)] + T::from(0.75).unwrap() * s;
+            (
+                diagonal + diagonal,
+                diagonal * diagonal + T::from(0.4375).unwrap() * s * s,
+            )
+        } else {
+            let (a, b) = (h[(last - 1, last - 1)], h[(last - 1, last)]);
+            let (c, d) = (h[(last, last - 1)], h[(last, last)]);
+            (a + d, a * d - b * c)
+        };
+
+        // First column of (H - s1 I)(H - s2 I), then chase the bulge down the block
+        let mut x = h[(low, low)] * h[(low, low)] + h[(low, low + 1)] * h[(low + 1, low)]
+            - sum * h[(low, low)]
+            + product;
+        let mut y = h[(low + 1, low)] * (h[(low, low)] + h[(low + 1, low + 1)] - sum);
+        let mut z = h[(low + 2, low + 1)] * h[(low + 1, low)];
+        for k in low..last - 1 {
+            if let Some(reflector) = householder(&[x, y, z]) {
+                reflect_rows(&mut h, &reflector, k, k.max(low + 1) - 1..=last);
+                reflect_columns(&mut h, &reflector, k, low..=last.min(k + 3));
+            }
+            if k > low {
+                h[(k + 1, k - 1)] = T::zero();
+                h[(k + 2, k - 1)] = T::zero();
+            }
+            x = h[(k + 1, k)];
+            y = h[(k + 2, k)];
+            if k + 3 <= last {
+                z = h[(k + 3, k)];
+            }
+        }
+        if let Some(reflector) = householder(&[x, y]) {
+            reflect_rows(&mut h, &reflector, last - 1, last - 2..=last);
+            reflect_columns(&mut h, &reflector, last - 1, low..=last);
+        }
+        h[(last, last - 2)] = T::zero();
+    }
+    Some(eigenvalues)
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use ndarray::array;
+
+    use super::*;
+
+    #[test]
+    fn test_expm_diagonal() {
+        // Given
+        let a = array![[-1.0, 0.0], [0.0, 2.0]];
+
+        // When
+        let result = expm(&a);
+
+        // Then
+        assert_relative_eq!((-1.0f64).exp(), result[(0, 0)], epsilon = 1e-12);
+        assert_relative_eq!(0.0, result[(0, 1)], epsilon = 1e-12);
+        assert_relative_eq!(0.0, result[(1, 0)], epsilon = 1e-12);
+        assert_relative_eq!(2.0f64.exp(), result[(1, 1)], epsilon = 1e-10);
+    }
+
+    #[test]
+    fn test_expm_rotation() {
+        // Given
+        // exp([[0, -t], [t, 0]]) = [[cos t, -sin t], [sin t, cos t]]
+        let t = 3.0f64;
+        let a = array![[0.0, -t], [t, 0.0]];
+
+        // When
+        let result = expm(&a);
+
+        // Then
+        assert_relative_eq!(t.cos(), result[(0, 0)], epsilon = 1e-12);
+        assert_relative_eq!(-t.sin(), result[(0, 1)], epsilon = 1e-12);
+        assert_relative_eq!(t.sin(), result[(1, 0)], epsilon = 1e-12);
+        assert_relative_eq!(t.cos(), result[(1, 1)], epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_inv() {
+        // Given
+        let a = array![[0.0, 2.0], [1.0, 3.0]];
+
+        // When
+        let result = inv(&a).unwrap();
+
+        // Then
+        let identity = matmul(&a, &result);
+        assert_relative_eq!(1.0, identity[(0, 0)], epsilon = 1e-12);
+        assert_relative_eq!(0.0, identity[(0, 1)], epsilon = 1e-12);
+        assert_relative_eq!(0.0, identity[(1, 0)], epsilon = 1e-12);
+        assert_relative_eq!(1.0, identity[(1, 1)], epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_inv_singular() {
+        let a = array![[1.0, 2.0], [2.0, 4.0]];
+        assert!(inv(&a).is_none());
+    }
+
+    #[test]
+    fn test_logm_inverts_expm() {
+        // Given
+        let a = array![[-3.0, -2.0, 0.5], [1.0, 0.0, 0.0], [0.0, 1.0, -0.1]];
+
+        // When
+        let result = logm(&expm(&a)).unwrap();
+
+        // Then
+        for (expected, actual) in a.iter().zip(result.iter()) {
+            assert_relative_eq!(expected, actual, epsilon = 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_logm_negative_eigenvalue() {
+        let a = array![[-1.0, 0.0], [0.0, 2.0]];
+        assert!(logm(&a).is_none());
+    }
+
+    #[test]
+    fn test_faddeev_leverrier() {
+        // Given
+        // Companion matrix of s^3 + 6s^2 + 11s + 6
+        let a = array![[-6.0, -11.0, -6.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+
+        // When
+        let (char_poly, adjugate_coeffs) = faddeev_leverrier(&a);
+
+        // Then
+        assert_eq!(vec![1.0, 6.0, 11.0, 6.0], char_poly);
+        assert_eq!(3, adjugate_coeffs.len());
+        assert_eq!(Array2::<f64>::eye(3), adjugate_coeffs[0]);
+    }
+
+    #[test]
+    fn test_singular_values() {
+        // Given
+        // [[3, 0], [4, 5]] has singular values 3 * sqrt(5) and sqrt(5)
+        let a = array![[3.0, 0.0], [4.0, 5.0]];
+
+        // When
+        let result = singular_values(&a);
+
+        // Then
+        assert_relative_eq!(3.0 * 5.0f64.sqrt(), result[0], epsilon = 1e-12);
+        assert_relative_eq!(5.0f64.sqrt(), result[1], epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_complex_singular_values() {
+        // Given
+        // Rank one 2x3 matrix u * v^H with |u| = sqrt(2), |v| = sqrt(3)
+        let j = Complex::new(0.0, 1.0);
+        let one = Complex::new(1.0, 0.0);
+        let u = [one, j];
+        let v = [one, -j, one];
+        let a = Array2::from_shape_fn((2, 3), |(r, c)| u[r] * v[c].conj());
+
+        // When
+        let result = complex_singular_values(&a);
+
+        // Then
+        assert_eq!(2, result.len());
+        assert_relative_eq!(6.0f64.sqrt(), result[0], epsilon = 1e-12);
+        assert_relative_eq!(0.0, result[1], epsilon = 1e-12);
+    }
+}
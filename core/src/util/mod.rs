@@ -1 +1,5 @@
-pub mod poly;
\ No newline at end of file
+#[cfg(feature = "double-double")]
+pub mod dd;
+pub mod linalg;
+pub mod poly;
+pub mod roots;
@@ -1,17 +1,13 @@
-use num::{Float, Zero, complex::Complex};
-use std::ops::{Add, AddAssign, Mul};
+use num::{Float, One, Zero, complex::Complex};
+use std::ops::{Add, Mul, Neg, Sub};
 
-pub fn binomial_expansion(pow: usize, negative: bool) -> Vec<f64> {
-    if negative {
-        NEG_PASCAL[pow][0..=pow].iter().map(|&x| x as f64).collect()
-    } else {
-        PASCAL[pow][0..=pow].iter().map(|&x| x as f64).collect()
-    }
-}
+use crate::error::ControlError;
+
+use super::roots::roots;
 
 pub fn poly_add<T>(a: &[T], b: &[T]) -> Vec<T>
 where
-    T: Copy + AddAssign + Zero,
+    T: Copy + Add<Output = T> + Zero,
 {
     let len = a.len().max(b.len());
     let mut result = vec![T::zero(); len];
@@ -19,10 +15,10 @@ where
     let offset_b = len - b.len();
 
     for i in 0..a.len() {
-        result[offset_a + i] += a[i];
+        result[offset_a + i] = result[offset_a + i] + a[i];
     }
     for i in 0..b.len() {
-        result[offset_b + i] += b[i];
+        result[offset_b + i] = result[offset_b + i] + b[i];
     }
     result
 }
@@ -41,73 +37,493 @@ where
     result
 }
 
+// Coefficients of the monic polynomial with the given roots, highest power first
+pub fn coeff_from_pz<T: Float>(p_or_z: &[Complex<T>]) -> Vec<Complex<T>> {
+    p_or_z
+        .iter()
+        .fold(vec![Complex::<T>::new(T::one(), T::zero())], |acc, &pz| {
+            let poly = vec![Complex::<T>::new(T::one(), T::zero()), -pz];
+            convolve(&acc, &poly)
+        })
+}
+
+// Evaluates a real polynomial (highest power first) at a complex point
+pub fn horner<T: Float>(coeffs: &[T], x: Complex<T>) -> Complex<T> {
+    coeffs
+        .iter()
+        .fold(Complex::new(T::zero(), T::zero()), |acc, &coeff| {
+            acc * x + coeff
+        })
+}
+
+pub fn poly_pow<T>(base: &[T], pow: usize) -> Vec<T>
+where
+    T: Copy + Add<Output = T> + Mul<Output = T> + Zero + One,
+{
+    (0..pow).fold(vec![T::one()], |acc, _| convolve(&acc, base))
+}
+
+// Substitutes x = (a*y + b) / (c*y + d) into a polynomial P(x) and clears the denominator,
+// returning the coefficients of P((a*y + b) / (c*y + d)) * (c*y + d)^order
+// `order` must be at least the degree of P so that the result stays polynomial
+pub fn mobius_substitution<T: Float>(
+    coeffs: &[T],
+    order: usize,
+    (a, b, c, d): (T, T, T, T),
+) -> Vec<T> {
+    let degree = coeffs.len().saturating_sub(1);
+    coeffs
+        .iter()
+        .enumerate()
+        .fold(vec![T::zero(); order + 1], |acc, (k, &coeff)| {
+            let pow = degree - k;
+            let term: Vec<T> = convolve(&poly_pow(&[a, b], pow), &poly_pow(&[c, d], order - pow))
+                .iter()
+                .map(|&x| x * coeff)
+                .collect();
+            poly_add(&acc, &term)
+        })
+}
+
+// Coefficients of `Polynomial::new(coeffs)`, for code that works on bare coefficient slices
+pub fn trim_leading_zeros<T: Float>(coeffs: &[T]) -> Vec<T> {
+    Polynomial::new(coeffs).coeffs
+}
+
+fn max_abs<T: Float>(coeffs: &[T]) -> T {
+    coeffs.iter().fold(T::zero(), |acc, c| acc.max(c.abs()))
+}
+
+// The one trimming rule of the crate: leading coefficients at or below `tol` times the largest
+// magnitude are dropped, the constant term is always kept
+fn trim_relative<T: Float>(coeffs: &[T], tol: T) -> Vec<T> {
+    let threshold = tol * max_abs(coeffs);
+    let first_nonzero = coeffs
+        .iter()
+        .position(|c| c.abs() > threshold)
+        .unwrap_or(coeffs.len().saturating_sub(1));
+    coeffs[first_nonzero..].to_vec()
+}
+
 pub fn reduce_to_real<T: Copy>(coeffs: &[Complex<T>]) -> Vec<T> {
     coeffs.iter().map(|&c| c.re).collect()
 }
 
-const fn build_pascal_triangle(max_pow: usize) -> [[isize; MAX_ORDER + 1]; MAX_ORDER + 1] {
-    // Represent triangle as square array... ( ͠° ͟ʖ ͡°)
-    let mut triangle = [[0; MAX_ORDER + 1]; MAX_ORDER + 1];
-    triangle[0][0] = 1;
-    let mut i = 1;
-    // No for loops because const fn
-    while i <= max_pow {
-        triangle[i][0] = 1;
-        let mut j = 1;
-        while j <= i {
-            triangle[i][j] = triangle[i - 1][j - 1] + triangle[i - 1][j];
-            j += 1;
+/// Real polynomial with coefficients stored highest power first, as everywhere else in the crate
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial<T: Float> {
+    coeffs: Vec<T>,
+}
+
+impl<T: Float> Polynomial<T> {
+    /// Leading coefficients that vanish at the precision of `T` relative to the largest one are
+    /// dropped, see `trim`. An empty slice gives the zero polynomial
+    pub fn new(coeffs: &[T]) -> Self {
+        if coeffs.is_empty() {
+            return Self::zero();
+        }
+        let tol = T::epsilon() * T::from(coeffs.len()).unwrap();
+        Self {
+            coeffs: trim_relative(coeffs, tol),
         }
-        i += 1;
     }
-    triangle
-}
 
-const fn build_neg_pascal_triangle(max_pow: usize) -> [[isize; MAX_ORDER + 1]; MAX_ORDER + 1] {
-    let mut triangle = build_pascal_triangle(max_pow);
-    let mut i = 1;
-    while i <= max_pow {
-        if i % 2 != 0 {
-            let mut j = 0;
-            while j <= max_pow {
-                triangle[j][i] = -1 * triangle[j][i];
-                j += 1
+    pub fn zero() -> Self {
+        Self::constant(T::zero())
+    }
+
+    pub fn one() -> Self {
+        Self::constant(T::one())
+    }
+
+    pub fn constant(value: T) -> Self {
+        Self {
+            coeffs: vec![value],
+        }
+    }
+
+    /// Monic polynomial with the given roots, complex roots need their conjugates
+    pub fn from_roots(roots: &[Complex<T>]) -> Self {
+        Self::new(&reduce_to_real(&coeff_from_pz(roots)))
+    }
+
+    pub fn coeffs(&self) -> &[T] {
+        &self.coeffs
+    }
+
+    pub fn degree(&self) -> usize {
+        self.coeffs.len() - 1
+    }
+
+    pub fn leading_coefficient(&self) -> T {
+        self.coeffs[0]
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coeffs.iter().all(|c| c.is_zero())
+    }
+
+    /// Drops leading coefficients at or below `tol` times the largest one in magnitude,
+    /// keeping at least the constant term
+    pub fn trim(&self, tol: T) -> Self {
+        Self {
+            coeffs: trim_relative(&self.coeffs, tol),
+        }
+    }
+
+    pub fn eval(&self, x: T) -> T {
+        self.coeffs.iter().fold(T::zero(), |acc, &c| acc * x + c)
+    }
+
+    pub fn eval_complex(&self, x: Complex<T>) -> Complex<T> {
+        horner(&self.coeffs, x)
+    }
+
+    pub fn scale(&self, factor: T) -> Self {
+        Self::new(&self.coeffs.iter().map(|&c| c * factor).collect::<Vec<_>>())
+    }
+
+    /// Scaled to a leading coefficient of one, `None` for the zero polynomial
+    pub fn monic(&self) -> Option<Self> {
+        (!self.is_zero()).then(|| self.scale(self.leading_coefficient().recip()))
+    }
+
+    pub fn derivative(&self) -> Self {
+        let degree = self.degree();
+        let coeffs: Vec<T> = self.coeffs[..degree]
+            .iter()
+            .enumerate()
+            .map(|(k, &c)| c * T::from(degree - k).unwrap())
+            .collect();
+        Self::new(&coeffs)
+    }
+
+    /// Antiderivative with a zero constant of integration
+    pub fn integral(&self) -> Self {
+        let degree = self.degree();
+        let mut coeffs: Vec<T> = self
+            .coeffs
+            .iter()
+            .enumerate()
+            .map(|(k, &c)| c / T::from(degree - k + 1).unwrap())
+            .collect();
+        coeffs.push(T::zero());
+        Self::new(&coeffs)
+    }
+
+    /// Long division, self = quotient * divisor + remainder with deg(remainder) < deg(divisor)
+    pub fn div_rem(&self, divisor: &Self) -> Result<(Self, Self), ControlError> {
+        if divisor.is_zero() {
+            return Err(ControlError::ZeroDenominator);
+        }
+        if self.degree() < divisor.degree() {
+            return Ok((Self::zero(), self.clone()));
+        }
+        let mut remainder = self.coeffs.clone();
+        let mut quotient = vec![T::zero(); self.degree() - divisor.degree() + 1];
+        for i in 0..quotient.len() {
+            let factor = remainder[i] / divisor.leading_coefficient();
+            quotient[i] = factor;
+            for (j, &d) in divisor.coeffs.iter().enumerate() {
+                remainder[i + j] = remainder[i + j] - factor * d;
             }
         }
-        i += 1;
+        Ok((
+            Self::new(&quotient),
+            Self::new(&remainder[quotient.len()..]),
+        ))
+    }
+
+    /// Monic greatest common divisor by the Euclidean algorithm
+    /// Remainders with coefficients below `tol` relative to the dividend count as zero
+    pub fn gcd(&self, other: &Self, tol: T) -> Self {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        if a.degree() < b.degree() {
+            std::mem::swap(&mut a, &mut b);
+        }
+        while !b.is_zero() {
+            let scale = max_abs(&a.coeffs);
+            // b is nonzero, so the division cannot fail
+            let (_, remainder) = a.div_rem(&b).unwrap();
+            let remainder_scale = max_abs(&remainder.coeffs);
+            a = b;
+            b = if remainder_scale <= tol * scale {
+                Self::zero()
+            } else {
+                remainder.trim(tol * scale / remainder_scale)
+            };
+        }
+        a.monic().unwrap_or(a)
+    }
+
+    /// p(q(x)), evaluated with Horner's scheme on polynomials
+    pub fn compose(&self, inner: &Self) -> Self {
+        self.coeffs
+            .iter()
+            .fold(Self::zero(), |acc, &c| &(&acc * inner) + &Self::constant(c))
+    }
+
+    pub fn roots(&self) -> Result<Vec<Complex<T>>, ControlError> {
+        roots(&self.coeffs)
+    }
+}
+
+impl<T: Float> From<Vec<T>> for Polynomial<T> {
+    fn from(coeffs: Vec<T>) -> Self {
+        Self::new(&coeffs)
+    }
+}
+
+impl<T: Float> Add for &Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Polynomial::new(&poly_add(&self.coeffs, &rhs.coeffs))
     }
-    triangle
 }
 
-const MAX_ORDER: usize = 20;
-pub const PASCAL: [[isize; MAX_ORDER + 1]; MAX_ORDER + 1] = build_pascal_triangle(MAX_ORDER);
-pub const NEG_PASCAL: [[isize; MAX_ORDER + 1]; MAX_ORDER + 1] =
-    build_neg_pascal_triangle(MAX_ORDER);
+impl<T: Float> Sub for &Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + &(-rhs)
+    }
+}
+
+impl<T: Float> Mul for &Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Polynomial::new(&convolve(&self.coeffs, &rhs.coeffs))
+    }
+}
+
+impl<T: Float> Neg for &Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn neg(self) -> Self::Output {
+        self.scale(-T::one())
+    }
+}
+
+impl<T: Float> Add for Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        &self + &rhs
+    }
+}
+
+impl<T: Float> Sub for Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        &self - &rhs
+    }
+}
+
+impl<T: Float> Mul for Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        &self * &rhs
+    }
+}
+
+impl<T: Float> Neg for Polynomial<T> {
+    type Output = Polynomial<T>;
+
+    fn neg(self) -> Self::Output {
+        -&self
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    use crate::util::poly::{NEG_PASCAL, PASCAL};
+    use approx::assert_relative_eq;
+    use num::complex::Complex64;
+
+    use crate::error::ControlError;
+    use crate::util::poly::{
+        Polynomial, coeff_from_pz, mobius_substitution, poly_pow, trim_leading_zeros,
+    };
 
     #[test]
-    fn test_pascal_triangle() {
-        assert_eq!(PASCAL[0][0], 1);
-        assert_eq!(PASCAL[1][0], 1);
-        assert_eq!(PASCAL[1][1], 1);
-        assert_eq!(PASCAL[2][1], 2);
-        assert_eq!(PASCAL[4][2], 6);
-        assert_eq!(PASCAL[5][3], 10);
-        assert_eq!(PASCAL[6][3], 20);
+    fn test_binomial_coefficients() {
+        // Rows of Pascal's triangle, well past the old 20th-order tables
+        assert_eq!(vec![1.0, 4.0, 6.0, 4.0, 1.0], poly_pow(&[1.0, 1.0], 4));
+        assert_eq!(
+            vec![1.0, -5.0, 10.0, -10.0, 5.0, -1.0],
+            poly_pow(&[1.0, -1.0], 5)
+        );
+        let row = poly_pow(&[1.0, 1.0], 30);
+        assert_eq!(31, row.len());
+        assert_eq!(155117520.0, row[15]);
     }
 
     #[test]
-    fn test_neg_pascal_triangle() {
-        assert_eq!(NEG_PASCAL[0][0], 1);
-        assert_eq!(NEG_PASCAL[1][0], 1);
-        assert_eq!(NEG_PASCAL[1][1], -1);
-        assert_eq!(NEG_PASCAL[2][1], -2);
-        assert_eq!(NEG_PASCAL[4][2], 6);
-        assert_eq!(NEG_PASCAL[5][3], -10);
-        assert_eq!(NEG_PASCAL[6][3], -20);
+    fn test_poly_pow() {
+        // (x + 1)^3 = x^3 + 3x^2 + 3x + 1
+        assert_eq!(vec![1.0, 3.0, 3.0, 1.0], poly_pow(&[1.0, 1.0], 3));
+        assert_eq!(vec![1.0], poly_pow(&[1.0, 1.0], 0));
+    }
+
+    #[test]
+    fn test_mobius_substitution() {
+        // Given
+        // P(x) = x + 2, x = (y - 1) / (y + 1)
+        let coeffs = vec![1.0, 2.0];
+
+        // When
+        let result = mobius_substitution(&coeffs, 2, (1.0, -1.0, 1.0, 1.0));
+
+        // Then
+        // P(x) * (y + 1)^2 = (y - 1)(y + 1) + 2(y + 1)^2 = 3y^2 + 4y + 1
+        assert_eq!(vec![3.0, 4.0, 1.0], result);
+    }
+
+    #[test]
+    fn test_trim_leading_zeros() {
+        assert_eq!(vec![1.0, 2.0], trim_leading_zeros(&[0.0, 1e-20, 1.0, 2.0]));
+        assert_eq!(vec![0.0], trim_leading_zeros(&[0.0, 0.0]));
+    }
+
+    #[test]
+    fn test_polynomial_arithmetic() {
+        // Given
+        let p = Polynomial::new(&[1.0, 2.0]);
+        let q = Polynomial::new(&[1.0, -2.0]);
+
+        // Then
+        assert_eq!(vec![2.0, 0.0], (&p + &q).coeffs());
+        assert_eq!(vec![4.0], (&p - &q).coeffs());
+        assert_eq!(vec![1.0, 0.0, -4.0], (&p * &q).coeffs());
+        assert_eq!(vec![-1.0, -2.0], (-p).coeffs());
+        // x^2 + 1 - x^2 drops the cancelled leading term
+        let r = Polynomial::new(&[1.0, 0.0, 1.0]) - Polynomial::new(&[1.0, 0.0, 0.0]);
+        assert_eq!(0, r.degree());
+    }
+
+    #[test]
+    fn test_polynomial_calculus() {
+        // Given
+        // p(x) = 3x^2 + 2x + 1
+        let p = Polynomial::new(&[3.0, 2.0, 1.0]);
+
+        // Then
+        assert_eq!(17.0, p.eval(2.0));
+        assert_eq!(vec![6.0, 2.0], p.derivative().coeffs());
+        assert_eq!(vec![1.0, 1.0, 1.0, 0.0], p.integral().coeffs());
+        assert_eq!(p, p.integral().derivative());
+        assert_eq!(vec![0.0], Polynomial::constant(5.0).derivative().coeffs());
+        let value = p.eval_complex(Complex64::new(0.0, 1.0));
+        assert_relative_eq!(-2.0, value.re);
+        assert_relative_eq!(2.0, value.im);
+    }
+
+    #[test]
+    fn test_polynomial_monic() {
+        // Then
+        assert_eq!(
+            Some(Polynomial::new(&[1.0, 0.5])),
+            Polynomial::new(&[2.0, 1.0]).monic()
+        );
+        assert_eq!(None, Polynomial::<f64>::zero().monic());
+    }
+
+    #[test]
+    fn test_coeff_from_pz() {
+        // Given
+        let z1 = Complex64::new(-1.0, 2.0);
+        let z2 = Complex64::new(-1.0, -2.0);
+
+        // When
+        let coeffs = coeff_from_pz(&[z1, z2]);
+
+        // Then
+        // (s + 1 - 2j)(s + 1 + 2j) = s^2 + 2s + 5
+        assert_eq!(
+            vec![
+                Complex64::new(1.0, 0.0),
+                Complex64::new(2.0, 0.0),
+                Complex64::new(5.0, 0.0)
+            ],
+            coeffs
+        );
+    }
+
+    #[test]
+    fn test_polynomial_div_rem() {
+        // Given
+        // x^3 - 2x^2 - 4 = (x - 3)(x^2 + x + 3) + 5
+        let p = Polynomial::new(&[1.0, -2.0, 0.0, -4.0]);
+        let d = Polynomial::new(&[1.0, -3.0]);
+
+        // When
+        let (quotient, remainder) = p.div_rem(&d).unwrap();
+
+        // Then
+        assert_eq!(vec![1.0, 1.0, 3.0], quotient.coeffs());
+        assert_eq!(vec![5.0], remainder.coeffs());
+        assert_eq!(
+            Err(ControlError::ZeroDenominator),
+            p.div_rem(&Polynomial::zero())
+        );
+    }
+
+    #[test]
+    fn test_polynomial_gcd() {
+        // Given
+        // (x + 1)(x + 2) and (x + 1)(x - 3)
+        let a = Polynomial::new(&[2.0, 6.0, 4.0]);
+        let b = Polynomial::new(&[1.0, -2.0, -3.0]);
+
+        // When
+        let gcd = a.gcd(&b, 1e-10);
+
+        // Then
+        assert_eq!(1, gcd.degree());
+        assert_relative_eq!(1.0, gcd.coeffs()[0]);
+        assert_relative_eq!(1.0, gcd.coeffs()[1], epsilon = 1e-12);
+        assert_eq!(0, a.gcd(&Polynomial::new(&[1.0, 5.0]), 1e-10).degree());
     }
-}
 
+    #[test]
+    fn test_polynomial_compose() {
+        // Given
+        // p(x) = x^2 + 1, q(x) = x - 1
+        let p = Polynomial::new(&[1.0, 0.0, 1.0]);
+        let q = Polynomial::new(&[1.0, -1.0]);
+
+        // Then
+        // p(q(x)) = x^2 - 2x + 2
+        assert_eq!(vec![1.0, -2.0, 2.0], p.compose(&q).coeffs());
+    }
+
+    #[test]
+    fn test_polynomial_roots_and_trim() {
+        // Given
+        let p = Polynomial::from_roots(&[Complex64::new(-1.0, 0.0), Complex64::new(-2.0, 0.0)]);
+
+        // When
+        let mut roots: Vec<f64> = p.roots().unwrap().iter().map(|r| r.re).collect();
+        roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        // Then
+        assert_eq!(vec![1.0, 3.0, 2.0], p.coeffs());
+        assert_relative_eq!(-2.0, roots[0], epsilon = 1e-12);
+        assert_relative_eq!(-1.0, roots[1], epsilon = 1e-12);
+        assert_eq!(
+            vec![1.0, 2.0],
+            Polynomial::new(&[1e-14, 1.0, 2.0]).trim(1e-12).coeffs()
+        );
+        // The tolerance is relative, so uniformly small coefficients are kept
+        assert_eq!(
+            vec![1e-14, 1e-15],
+            Polynomial::new(&[1e-14, 1e-15]).trim(1e-12).coeffs()
+        );
+        assert_eq!(vec![1.0, 2.0], Polynomial::new(&[1e-20, 1.0, 2.0]).coeffs());
+        assert_eq!(vec![0.0], Polynomial::new(&[0.0, 0.0]).trim(1e-12).coeffs());
+        assert_eq!(vec![0.0], Polynomial::<f64>::new(&[]).coeffs());
+    }
+}
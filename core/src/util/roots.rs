@@ -0,0 +1,300 @@
+use std::cmp::Ordering;
+
+use ndarray::Array2;
+use num::{Float, complex::Complex};
+
+use crate::{error::ControlError, util::linalg::eigenvalues_qr};
+
+type Solver<T> = fn(&Array2<T>) -> Result<Vec<Complex<T>>, ControlError>;
+
+/// Roots of a polynomial given highest power first
+// Based on MATLAB roots algorithm (see https://www.mathworks.com/help/matlab/ref/roots.html)
+pub fn roots<T: Float>(coeffs: &[T]) -> Result<Vec<Complex<T>>, ControlError> {
+    roots_with(coeffs, solve)
+}
+
+/// Eigenvalues in ascending order of real part, complex pairs together with the positive
+/// imaginary part first
+pub fn eigenvalues<T: Float>(matrix: &Array2<T>) -> Result<Vec<Complex<T>>, ControlError> {
+    eigenvalues_with(matrix, solve)
+}
+
+fn roots_with<T: Float>(coeffs: &[T], solver: Solver<T>) -> Result<Vec<Complex<T>>, ControlError> {
+    // Leading zeroes only lower the degree
+    let first_nonzero = coeffs
+        .iter()
+        .position(|c| !c.is_zero())
+        .unwrap_or(coeffs.len());
+    let coeffs = &coeffs[first_nonzero..];
+    if coeffs.len() <= 1 {
+        return Ok(Vec::new());
+    }
+    // Trailing zeroes are exact roots at the origin, the eigenvalue solver would smear
+    // a multiple root there into a small circle
+    let last_nonzero = coeffs.iter().rposition(|c| !c.is_zero()).unwrap();
+    let origin = vec![Complex::new(T::zero(), T::zero()); coeffs.len() - 1 - last_nonzero];
+    let coeffs = &coeffs[..=last_nonzero];
+    if coeffs.len() <= 1 {
+        return Ok(origin);
+    }
+
+    // Eigenvalues of companion matrix == roots
+    let mut values = eigenvalues_with(&companion_matrix(coeffs), solver)?;
+    values.extend(origin);
+    sort_roots(&mut values);
+    Ok(values)
+}
+
+fn eigenvalues_with<T: Float>(
+    matrix: &Array2<T>,
+    solver: Solver<T>,
+) -> Result<Vec<Complex<T>>, ControlError> {
+    if matrix.is_empty() {
+        return Ok(Vec::new());
+    }
+    if matrix.iter().any(|x| !x.is_finite()) {
+        return Err(ControlError::NonFiniteCoefficient);
+    }
+    let mut values = solver(matrix)?;
+    sort_roots(&mut values);
+    Ok(values)
+}
+
+// First row holds the negated monic coefficients, ones on the subdiagonal
+fn companion_matrix<T: Float>(coeffs: &[T]) -> Array2<T> {
+    let n = coeffs.len() - 1;
+    let mut companion = Array2::<T>::zeros((n, n));
+    for i in 0..n {
+        companion[(0, i)] = -coeffs[i + 1] / coeffs[0];
+        if i < n - 1 {
+            companion[(i + 1, i)] = T::one();
+        }
+    }
+    companion
+}
+
+// Real parts are compared on a grid relative to the largest root, so solver noise cannot
+// reorder roots that share a real part. Sorting by |im| before the sign of im then keeps
+// each conjugate pair together, positive imaginary part first
+fn sort_roots<T: Float>(values: &mut [Complex<T>]) {
+    let scale = values.iter().fold(T::one(), |acc, v| acc.max(v.norm()));
+    let grid = scale * T::from(1e-9).unwrap();
+    let compare = |a: T, b: T| a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+    values.sort_by(|a, b| {
+        compare((a.re / grid).round(), (b.re / grid).round())
+            .then(compare(a.im.abs(), b.im.abs()))
+            .then(compare(b.im, a.im))
+    });
+}
+
+// LAPACK only works in f64, any other precision runs the QR iteration in T so that
+// f32 stays cheap and extended types such as DoubleDouble keep their extra digits
+fn solve<T: Float>(matrix: &Array2<T>) -> Result<Vec<Complex<T>>, ControlError> {
+    #[cfg(feature = "lapack")]
+    if T::epsilon().to_f64() == Some(f64::EPSILON) {
+        return lapack_eigenvalues(matrix);
+    }
+    qr_eigenvalues(matrix)
+}
+
+fn qr_eigenvalues<T: Float>(matrix: &Array2<T>) -> Result<Vec<Complex<T>>, ControlError> {
+    eigenvalues_qr(matrix).ok_or(ControlError::RootFindingFailed)
+}
+
+// Only called for f64 precision, the casts keep Lapack trait requirements out of the API
+#[cfg(feature = "lapack")]
+fn lapack_eigenvalues<T: Float>(matrix: &Array2<T>) -> Result<Vec<Complex<T>>, ControlError> {
+    use ndarray_linalg::Eig;
+
+    let matrix_f64 = matrix.mapv(|x| x.to_f64().unwrap());
+    match matrix_f64.eig() {
+        Ok((values, _)) => Ok(values
+            .iter()
+            .map(|c| Complex::new(T::from(c.re).unwrap(), T::from(c.im).unwrap()))
+            .collect()),
+        Err(_) => Err(ControlError::RootFindingFailed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::relative_eq;
+    use ndarray::array;
+    use num::complex::Complex64;
+
+    use super::*;
+
+    // Every backend compiled into this build runs the same cases
+    fn solvers() -> Vec<(&'static str, Solver<f64>)> {
+        vec![
+            ("qr", qr_eigenvalues),
+            #[cfg(feature = "lapack")]
+            ("lapack", lapack_eigenvalues),
+        ]
+    }
+
+    fn assert_roots(name: &str, expected: &[Complex64], actual: &[Complex64], tol: f64) {
+        assert_eq!(expected.len(), actual.len(), "{}: {:?}", name, actual);
+        for (e, a) in expected.iter().zip(actual) {
+            assert!(
+                relative_eq!(e.re, a.re, epsilon = tol) && relative_eq!(e.im, a.im, epsilon = tol),
+                "{}: expected {} in {:?}",
+                name,
+                e,
+                actual
+            );
+        }
+    }
+
+    fn c(re: f64, im: f64) -> Complex64 {
+        Complex64::new(re, im)
+    }
+
+    #[test]
+    fn test_real_roots() {
+        // (s + 1)(s + 2)(s + 3)
+        for (name, solver) in solvers() {
+            let result = roots_with(&[1.0, 6.0, 11.0, 6.0], solver).unwrap();
+            assert_roots(
+                name,
+                &[c(-3.0, 0.0), c(-2.0, 0.0), c(-1.0, 0.0)],
+                &result,
+                1e-10,
+            );
+        }
+    }
+
+    #[test]
+    fn test_conjugate_pairs_ordered() {
+        // (s^2 + 2s + 5)(s^2 + 2s + 10)(s + 1), two pairs sharing the real part -1
+        for (name, solver) in solvers() {
+            let result = roots_with(&[1.0, 5.0, 23.0, 49.0, 80.0, 50.0], solver).unwrap();
+            assert_roots(
+                name,
+                &[
+                    c(-1.0, 0.0),
+                    c(-1.0, 2.0),
+                    c(-1.0, -2.0),
+                    c(-1.0, 3.0),
+                    c(-1.0, -3.0),
+                ],
+                &result,
+                1e-9,
+            );
+            assert_eq!(result[1], result[2].conj(), "{}", name);
+            assert_eq!(result[3], result[4].conj(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_badly_scaled_polynomial() {
+        // (s + 1e-3)(s + 1)(s + 1e3)
+        for (name, solver) in solvers() {
+            let result = roots_with(&[1.0, 1001.001, 1001.001, 1.0], solver).unwrap();
+            for (expected, root) in [-1e3, -1.0, -1e-3].iter().zip(&result) {
+                assert!(
+                    relative_eq!(*expected, root.re, max_relative = 1e-10),
+                    "{}: {:?}",
+                    name,
+                    result
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_roots_on_unit_circle() {
+        // z^8 + z^7 + ... + 1 = (z^9 - 1)/(z - 1), the ninth roots of unity other than 1
+        let coeffs = vec![1.0; 9];
+        for (name, solver) in solvers() {
+            let result = roots_with(&coeffs, solver).unwrap();
+            assert_eq!(8, result.len(), "{}", name);
+            for root in &result {
+                assert!(
+                    relative_eq!(1.0, root.norm(), epsilon = 1e-12)
+                        && relative_eq!(0.0, (root.powi(9) - 1.0).norm(), epsilon = 1e-12),
+                    "{}: {}",
+                    name,
+                    root
+                );
+            }
+            for pair in result.chunks(2) {
+                assert_eq!(pair[0], pair[1].conj(), "{}", name);
+                assert!(pair[0].im > 0.0, "{}: {:?}", name, pair);
+            }
+        }
+    }
+
+    #[test]
+    fn test_backends_agree_on_random_polynomials() {
+        // Deterministic pseudo-random coefficients, degrees 1 to 12
+        let mut state = 12345u64;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (state >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+        };
+        for degree in 1..=12 {
+            let coeffs: Vec<f64> = (0..=degree).map(|_| next()).collect();
+            let reference = roots_with(&coeffs, qr_eigenvalues).unwrap();
+            assert_eq!(degree, reference.len());
+            for root in &reference {
+                let residual = coeffs.iter().fold(c(0.0, 0.0), |acc, &k| acc * root + k);
+                assert!(residual.norm() < 1e-9, "degree {}: {}", degree, residual);
+            }
+            for (name, solver) in solvers() {
+                let result = roots_with(&coeffs, solver).unwrap();
+                assert_roots(name, &reference, &result, 1e-8);
+            }
+        }
+    }
+
+    #[test]
+    fn test_roots_at_origin() {
+        // z^3 (z - 0.5), the triple root stays exactly at zero
+        for (name, solver) in solvers() {
+            let result = roots_with(&[1.0, -0.5, 0.0, 0.0, 0.0], solver).unwrap();
+            assert_eq!(vec![c(0.0, 0.0); 3], result[..3], "{}", name);
+            assert!(
+                relative_eq!(0.5, result[3].re, epsilon = 1e-12),
+                "{}: {:?}",
+                name,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn test_eigenvalues() {
+        // Upper triangular with a rotation block, eigenvalues 2 and -1 +- 1j
+        let matrix = array![[2.0, 1.0, 0.0], [0.0, -1.0, 1.0], [0.0, -1.0, -1.0]];
+        for (name, solver) in solvers() {
+            let result = eigenvalues_with(&matrix, solver).unwrap();
+            assert_roots(
+                name,
+                &[c(-1.0, 1.0), c(-1.0, -1.0), c(2.0, 0.0)],
+                &result,
+                1e-12,
+            );
+        }
+    }
+
+    #[test]
+    fn test_f32_matches_f64() {
+        // Given
+        // (s + 0.5)(s + 2)(s^2 + 2s + 5)(s^2 + 0.4s + 4), well separated roots
+        let coeffs = [1.0, 4.9, 18.8, 43.9, 74.3, 72.8, 40.0];
+        let coeffs_f32: Vec<f32> = coeffs.iter().map(|&c| c as f32).collect();
+
+        // When
+        let result_f64 = roots(&coeffs).unwrap();
+        let result_f32 = roots(&coeffs_f32).unwrap();
+
+        // Then
+        // Simple roots are perturbed by about the condition number times the f32 epsilon
+        assert_eq!(result_f64.len(), result_f32.len());
+        for (a, b) in result_f64.iter().zip(&result_f32) {
+            let b = c(b.re as f64, b.im as f64);
+            assert!((a - b).norm() < 1e-4 * a.norm(), "{} vs {}", a, b);
+        }
+    }
+}
@@ -1,78 +1,299 @@
-use eframe::egui::{self, ComboBox};
+use std::path::PathBuf;
+
+use eframe::egui::{self, Color32, ComboBox, RichText};
+use serde::{Deserialize, Serialize};
 
 use ctrlsys_toolbox_core::{
-    analysis::time::{LTIResponse, OpenLoopResponse, ResponseType},
+    analysis::time::{OpenLoopResponse, ResponseType},
+    error::ControlError,
     filter::sallenkey::butterworth_poles,
     tf::{
-        TimeDomain, TransferFunction, ctf::ContinousTransferFunction,
-        dtf::DiscreteTransferFunction, traits::coeff_from_pz,
+        TimeDomain, TransferFunction,
+        ctf::ContinousTransferFunction,
+        discretization::DiscretizationMethod,
+        dtf::DiscreteTransferFunction,
+        format::{FormatOptions, Style, format_numden, format_tf},
+        mimo::TransferFunctionMatrix,
+        minreal::find_cancellations,
+        traits::coeff_from_pz,
     },
-    util::poly::reduce_to_real,
+    util::poly::{Polynomial, reduce_to_real},
 };
 
+use super::project::{
+    FilterSettings, PROJECT_VERSION, Project, ResponseSettings, read_project, write_project,
+};
 use crate::plot::{
-    bode::{bode_mag_plot, bode_phase_plot},
+    bode::{
+        BodeSettings, FrequencyUnit, bode_mag_plot, bode_phase_plot, margins_summary,
+        system_margins,
+    },
+    nichols::nichols_plot,
+    nyquist::nyquist_plot,
     pz::pzplot,
     response::response_plot,
-    text::{print_coeffs, tf_text},
+    sigma::sigma_plot,
+    text::print_coeffs,
 };
 
 pub struct MainApp {
     ctf: ContinousTransferFunction<f64>,
     dtf: DiscreteTransferFunction<f64>,
     selected_time_domain: TimeDomain<f64>,
+    analysis_view: AnalysisView,
+    bode_settings: BodeSettings,
+    pz_show_cancellations: bool,
+    pz_cancellation_tol: f64,
     tf_input: TfInput,
     ctf_input_order: usize,
     ctf_input_num: Vec<f64>,
     ctf_input_den: Vec<f64>,
+    ctf_input_delay: f64,
+    ctf_input_expression: String,
+    ctf_input_expression_error: Option<ControlError>,
+    equation_format: FormatOptions,
     dtf_input_t_sample: f64,
+    dtf_input_method: DiscretizationMethod<f64>,
     filter_input_type: FilterType,
     filter_input_order: usize,
     filter_input_cutoff: f64,
     filter_input_normalize: bool,
+    filter_input_prewarp: bool,
     response_type: ResponseType,
     response_length: f64,
+    input_error: Option<String>,
+    project_path: Option<PathBuf>,
+    project_error: Option<String>,
 }
 
 impl Default for MainApp {
     fn default() -> Self {
         let ctf_input_num = vec![0.0, 0.0, 0.0, 1.0];
         let ctf_input_den = vec![1.0, 2.0, 2.0, 1.0];
-        let ctf = ContinousTransferFunction::<f64>::from_numden(&ctf_input_num, &ctf_input_den);
+        let ctf = ContinousTransferFunction::<f64>::from_numden(&ctf_input_num, &ctf_input_den)
+            .expect("default transfer function is valid");
         Self {
             ctf_input_order: ctf_input_num.len() - 1,
             dtf_input_t_sample: 0.1,
-            dtf: DiscreteTransferFunction::from_ctf(&ctf, 0.1),
+            dtf_input_method: DiscretizationMethod::Tustin,
+            dtf: DiscreteTransferFunction::from_ctf(&ctf, 0.1)
+                .expect("default transfer function is valid"),
             selected_time_domain: TimeDomain::Continous,
+            analysis_view: AnalysisView::PoleZero,
+            bode_settings: BodeSettings::default(),
+            pz_show_cancellations: true,
+            pz_cancellation_tol: 1e-6,
             tf_input: TfInput::Continous,
             ctf_input_num,
             ctf_input_den,
+            ctf_input_delay: 0.0,
+            ctf_input_expression: "1/(s^3 + 2s^2 + 2s + 1)".to_string(),
+            ctf_input_expression_error: None,
+            equation_format: FormatOptions {
+                style: Style::Unicode,
+                ..Default::default()
+            },
             ctf,
             filter_input_type: FilterType::Butterworth,
             filter_input_order: 3,
             filter_input_cutoff: 1.0,
             filter_input_normalize: true,
+            filter_input_prewarp: false,
             response_type: ResponseType::Step,
             response_length: 20.0,
+            input_error: None,
+            project_path: None,
+            project_error: None,
         }
     }
 }
 
 impl MainApp {
+    /// Restores the previous session when eframe has stored one
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        Default::default()
+        let mut app = Self::default();
+        if let Some(storage) = cc.storage {
+            if let Some(project) = eframe::get_value::<Project>(storage, eframe::APP_KEY) {
+                app.load_project(project);
+            }
+            app.project_path = eframe::get_value(storage, PROJECT_PATH_KEY).flatten();
+        }
+        app
+    }
+
+    fn project(&self) -> Project {
+        Project {
+            version: PROJECT_VERSION,
+            ctf: self.ctf.clone(),
+            sample_time: self.dtf_input_t_sample,
+            discretization: self.dtf_input_method,
+            filter: FilterSettings {
+                filter_type: self.filter_input_type,
+                order: self.filter_input_order,
+                cutoff: self.filter_input_cutoff,
+                normalize: self.filter_input_normalize,
+                prewarp: self.filter_input_prewarp,
+            },
+            response: ResponseSettings {
+                response_type: self.response_type,
+                length: self.response_length,
+            },
+        }
+    }
+
+    // The coefficient inputs take the saved model, the discrete system is rebuilt from it
+    fn load_project(&mut self, project: Project) {
+        let ctf = project.ctf;
+        self.ctf_input_order = ctf.numerator().len().max(ctf.denominator().len()) - 1;
+        self.ctf_input_num = pad_to_order(ctf.numerator(), self.ctf_input_order);
+        self.ctf_input_den = pad_to_order(ctf.denominator(), self.ctf_input_order);
+        self.ctf_input_delay = ctf.delay();
+        self.ctf = ctf;
+        self.dtf_input_t_sample = project.sample_time;
+        self.dtf_input_method = project.discretization;
+        self.filter_input_type = project.filter.filter_type;
+        self.filter_input_order = project.filter.order;
+        self.filter_input_cutoff = project.filter.cutoff;
+        self.filter_input_normalize = project.filter.normalize;
+        self.filter_input_prewarp = project.filter.prewarp;
+        self.response_type = project.response.response_type;
+        self.response_length = project.response.length;
+        self.sync_ctf_expression();
+        self.handle_dtf_input();
+    }
+
+    fn new_project(&mut self) {
+        *self = Self::default();
+    }
+
+    fn open_project(&mut self) {
+        let Some(path) = project_dialog().pick_file() else {
+            return;
+        };
+        match read_project(&path) {
+            Ok(project) => {
+                self.load_project(project);
+                self.project_path = Some(path);
+                self.project_error = None;
+            }
+            Err(error) => self.project_error = Some(error.to_string()),
+        }
+    }
+
+    fn save_project(&mut self) {
+        match self.project_path.clone() {
+            Some(path) => self.write_project(path),
+            None => self.save_project_as(),
+        }
+    }
+
+    fn save_project_as(&mut self) {
+        if let Some(path) = project_dialog().save_file() {
+            self.write_project(path);
+        }
+    }
+
+    fn write_project(&mut self, path: PathBuf) {
+        match write_project(&path, &self.project()) {
+            Ok(()) => {
+                self.project_path = Some(path);
+                self.project_error = None;
+            }
+            Err(error) => self.project_error = Some(error.to_string()),
+        }
+    }
+
+    // Invalid input keeps the previous model and shows the error next to the input
+    fn apply<M>(&mut self, result: Result<M, ControlError>, update: impl FnOnce(&mut Self, M)) {
+        match result {
+            Ok(model) => {
+                update(self, model);
+                self.input_error = None;
+            }
+            Err(error) => self.input_error = Some(error.to_string()),
+        }
     }
 
     pub fn handle_ctf_input(&mut self) {
-        self.ctf = ContinousTransferFunction::<f64>::from_numden(
-            &trim_coeffs(&self.ctf_input_num),
-            &trim_coeffs(&self.ctf_input_den),
+        let result = ContinousTransferFunction::<f64>::from_numden(
+            Polynomial::new(&self.ctf_input_num)
+                .trim(COEFF_TOL)
+                .coeffs(),
+            Polynomial::new(&self.ctf_input_den)
+                .trim(COEFF_TOL)
+                .coeffs(),
+        )
+        .and_then(|ctf| ctf.with_delay(self.ctf_input_delay))
+        .and_then(|ctf| {
+            let dtf = DiscreteTransferFunction::from_ctf_with_method(
+                &ctf,
+                self.dtf_input_t_sample,
+                self.dtf_input_method,
+            )?;
+            Ok((ctf, dtf))
+        });
+        self.apply(result, |app, (ctf, dtf)| {
+            app.ctf = ctf;
+            app.dtf = dtf;
+        });
+    }
+
+    // Parse errors stay next to the expression field, the coefficients keep their last valid value
+    fn handle_ctf_expression_input(&mut self) {
+        match ContinousTransferFunction::<f64>::parse(&self.ctf_input_expression) {
+            Ok(ctf) => {
+                self.ctf_input_expression_error = None;
+                self.ctf_input_order = ctf.numerator().len().max(ctf.denominator().len()) - 1;
+                self.ctf_input_num = pad_to_order(ctf.numerator(), self.ctf_input_order);
+                self.ctf_input_den = pad_to_order(ctf.denominator(), self.ctf_input_order);
+                self.handle_ctf_input();
+            }
+            Err(error) => self.ctf_input_expression_error = Some(error),
+        }
+    }
+
+    // Keeps the expression field in step with edits to the coefficients or filter synthesis
+    fn sync_ctf_expression(&mut self) {
+        self.ctf_input_expression = format_numden(
+            self.ctf.numerator(),
+            self.ctf.denominator(),
+            TimeDomain::Continous,
+            &FormatOptions {
+                significant_digits: 6,
+                ..Default::default()
+            },
         );
-        self.dtf = DiscreteTransferFunction::from_ctf(&self.ctf, self.dtf_input_t_sample);
+        self.ctf_input_expression_error = None;
     }
 
+    // Discretizes the continuous model, a rejected sample time is reset to the one of `dtf`
     pub fn handle_dtf_input(&mut self) {
-        self.dtf = DiscreteTransferFunction::from_ctf(&self.ctf, self.dtf_input_t_sample);
+        let result = DiscreteTransferFunction::from_ctf_with_method(
+            &self.ctf,
+            self.dtf_input_t_sample,
+            self.dtf_input_method,
+        );
+        self.apply(result, |app, dtf| app.dtf = dtf);
+        self.dtf_input_t_sample = self.dtf.sample_time();
+    }
+
+    // Moves the discrete model to the new sample time through its continuous equivalent
+    // First-order hold and impulse invariance have no inverse, for them the continuous model is
+    // discretized again instead
+    pub fn handle_sample_time_input(&mut self) {
+        let result = match self
+            .dtf
+            .resample(self.dtf_input_t_sample, self.dtf_input_method)
+        {
+            Err(ControlError::NoInverse { .. }) => DiscreteTransferFunction::from_ctf_with_method(
+                &self.ctf,
+                self.dtf_input_t_sample,
+                self.dtf_input_method,
+            ),
+            result => result,
+        };
+        self.apply(result, |app, dtf| app.dtf = dtf);
+        self.dtf_input_t_sample = self.dtf.sample_time();
     }
 
     fn handle_filter_input(&mut self) {
@@ -88,46 +309,98 @@ impl MainApp {
         num.push(1.0);
         self.ctf_input_num = num;
         self.ctf_input_den = den;
-        self.ctf = ContinousTransferFunction::<f64>::from_numden(
-            &trim_coeffs(&self.ctf_input_num),
-            &trim_coeffs(&self.ctf_input_den),
+        self.ctf_input_delay = 0.0;
+        let result = ContinousTransferFunction::<f64>::from_numden(
+            Polynomial::new(&self.ctf_input_num)
+                .trim(COEFF_TOL)
+                .coeffs(),
+            Polynomial::new(&self.ctf_input_den)
+                .trim(COEFF_TOL)
+                .coeffs(),
         );
+        self.apply(result, |app, ctf| app.ctf = ctf);
         if self.filter_input_normalize {
             self.ctf.normalize_at_w(0.0); // TODO: w = 0 is good for LPF, but should be different for HPF/BPF
         }
-        self.dtf = DiscreteTransferFunction::from_ctf(&self.ctf, 1.0);
+        if self.filter_input_prewarp {
+            self.dtf_input_method = DiscretizationMethod::TustinPrewarped {
+                omega_p: self.filter_input_cutoff,
+            };
+        } else if let DiscretizationMethod::TustinPrewarped { .. } = self.dtf_input_method {
+            self.dtf_input_method = DiscretizationMethod::Tustin;
+        }
+        self.sync_ctf_expression();
+        self.handle_dtf_input();
     }
 }
 
-// TODO: consider returning slice
-fn trim_coeffs(coeffs: &[f64]) -> Vec<f64> {
-    let first_nonzero = coeffs
-        .iter()
-        .position(|&c| c.abs() >= 1e-12)
-        .unwrap_or(coeffs.len().saturating_sub(1));
+// Storage key for the open project file, the project itself is stored under eframe::APP_KEY
+const PROJECT_PATH_KEY: &str = "project_path";
+
+fn project_dialog() -> rfd::FileDialog {
+    rfd::FileDialog::new()
+        .add_filter("Control system project", &["json"])
+        .set_file_name("project.json")
+}
+
+// Leading coefficients typed in below this fraction of the largest one are treated as zero
+const COEFF_TOL: f64 = 1e-12;
 
-    coeffs[first_nonzero..].to_vec()
+// Leading zeroes up to order + 1 coefficients, one per DragValue
+fn pad_to_order(coeffs: &[f64], order: usize) -> Vec<f64> {
+    let mut padded = vec![0.0; (order + 1).saturating_sub(coeffs.len())];
+    padded.extend_from_slice(coeffs);
+    padded
 }
 
 impl eframe::App for MainApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, &self.project());
+        eframe::set_value(storage, PROJECT_PATH_KEY, &self.project_path);
+    }
+
     // TODO: destroy giant evil function asap
     // TODO: massive runtime optimisations possible
-    fn update(&mut self, ctx: &eframe::egui::Context, frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
         let screen_height = ctx.screen_rect().height();
         let row_height = screen_height / 3.0;
 
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
+                    if ui.button("New").clicked() {
+                        self.new_project();
+                        ui.close_menu();
+                    }
+                    if ui.button("Open...").clicked() {
+                        self.open_project();
+                        ui.close_menu();
+                    }
+                    if ui.button("Save").clicked() {
+                        self.save_project();
+                        ui.close_menu();
+                    }
+                    if ui.button("Save As...").clicked() {
+                        self.save_project_as();
+                        ui.close_menu();
+                    }
+                    ui.separator();
                     if ui.button("Quit").clicked() {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                     }
-                })
+                });
+                if let Some(path) = &self.project_path {
+                    ui.label(path.display().to_string());
+                }
+                if let Some(error) = &self.project_error {
+                    ui.colored_label(Color32::RED, error);
+                }
             });
 
             egui::widgets::global_theme_preference_buttons(ui);
         });
 
+        let margins = system_margins(&[&self.ctf, &self.dtf]);
         egui::CentralPanel::default().show(ctx, |ui| {
             egui_extras::TableBuilder::new(ui)
                 .columns(egui_extras::Column::remainder(), 2)
@@ -137,18 +410,73 @@ impl eframe::App for MainApp {
                         row.col(|ui| {
                             ui.group(|ui| {
                                 ui.heading("Bode Plot: Magnitude");
-                                bode_mag_plot(ui, &self.ctf, 0.0, 10.0, 1000);
+                                frequency_axis_settings(ui, &mut self.bode_settings);
+                                bode_mag_plot(
+                                    ui,
+                                    &[&self.ctf, &self.dtf],
+                                    &margins,
+                                    &self.bode_settings,
+                                );
                             });
                         });
                         row.col(|ui| {
                             ui.group(|ui| {
-                                ui.heading("Pole-Zero Plot");
+                                ui.horizontal(|ui| {
+                                    ui.selectable_value(
+                                        &mut self.analysis_view,
+                                        AnalysisView::PoleZero,
+                                        RichText::new("Pole-Zero Plot").heading(),
+                                    );
+                                    ui.selectable_value(
+                                        &mut self.analysis_view,
+                                        AnalysisView::Nyquist,
+                                        RichText::new("Nyquist Plot").heading(),
+                                    );
+                                    ui.selectable_value(
+                                        &mut self.analysis_view,
+                                        AnalysisView::Nichols,
+                                        RichText::new("Nichols Chart").heading(),
+                                    );
+                                    ui.selectable_value(
+                                        &mut self.analysis_view,
+                                        AnalysisView::Sigma,
+                                        RichText::new("σ Plot").heading(),
+                                    );
+                                });
                                 domain_switch(ui, &mut self.selected_time_domain);
-                                match self.selected_time_domain {
-                                    TimeDomain::Continous => pzplot(ui, &self.ctf),
-                                    TimeDomain::Discrete { sample_time: _ } => {
-                                        pzplot(ui, &self.dtf)
+                                let tf: &dyn TransferFunction<f64> = match self.selected_time_domain
+                                {
+                                    TimeDomain::Continous => &self.ctf,
+                                    TimeDomain::Discrete { sample_time: _ } => &self.dtf,
+                                };
+                                match self.analysis_view {
+                                    AnalysisView::PoleZero => {
+                                        cancellation_switch(
+                                            ui,
+                                            &mut self.pz_show_cancellations,
+                                            &mut self.pz_cancellation_tol,
+                                        );
+                                        let cancelled = if self.pz_show_cancellations {
+                                            find_cancellations(tf, self.pz_cancellation_tol)
+                                        } else {
+                                            Vec::new()
+                                        };
+                                        pzplot(ui, tf, &cancelled);
+                                    }
+                                    AnalysisView::Nyquist => {
+                                        nyquist_plot(ui, tf, &self.bode_settings.omega_range());
                                     }
+                                    AnalysisView::Nichols => {
+                                        nichols_plot(ui, tf, &self.bode_settings.omega_range());
+                                    }
+                                    AnalysisView::Sigma => match self.selected_time_domain {
+                                        TimeDomain::Continous => {
+                                            siso_sigma_plot(ui, &self.ctf, &self.bode_settings)
+                                        }
+                                        TimeDomain::Discrete { sample_time: _ } => {
+                                            siso_sigma_plot(ui, &self.dtf, &self.bode_settings)
+                                        }
+                                    },
                                 }
                             });
                         });
@@ -157,7 +485,18 @@ impl eframe::App for MainApp {
                         row.col(|ui| {
                             ui.group(|ui| {
                                 ui.heading("Bode Plot: Phase");
-                                bode_phase_plot(ui, &self.ctf, 0.0, 10.0, 1000);
+                                margins_summary(
+                                    ui,
+                                    &[&self.ctf, &self.dtf],
+                                    &margins,
+                                    &self.bode_settings,
+                                );
+                                bode_phase_plot(
+                                    ui,
+                                    &[&self.ctf, &self.dtf],
+                                    &margins,
+                                    &self.bode_settings,
+                                );
                             });
                         });
                         row.col(|ui| {
@@ -236,6 +575,62 @@ fn domain_switch(ui: &mut egui::Ui, selected: &mut TimeDomain<f64>) {
     });
 }
 
+fn frequency_axis_settings(ui: &mut egui::Ui, settings: &mut BodeSettings) {
+    ui.horizontal(|ui| {
+        ui.label("Decades");
+        ui.add(
+            egui::DragValue::new(&mut settings.start_decade).range(-6..=settings.stop_decade - 1),
+        );
+        ui.label("to");
+        ui.add(
+            egui::DragValue::new(&mut settings.stop_decade).range(settings.start_decade + 1..=9),
+        );
+        egui::ComboBox::from_id_salt("bode_unit_switch")
+            .selected_text(settings.unit.label())
+            .show_ui(ui, |ui| {
+                for unit in [FrequencyUnit::RadPerSecond, FrequencyUnit::Hertz] {
+                    ui.selectable_value(&mut settings.unit, unit, unit.label());
+                }
+            });
+    });
+}
+
+fn cancellation_switch(ui: &mut egui::Ui, show: &mut bool, tol: &mut f64) {
+    ui.horizontal(|ui| {
+        ui.checkbox(show, "Highlight cancelling pole-zero pairs");
+        ui.add_enabled(
+            *show,
+            egui::DragValue::new(tol)
+                .speed(1e-7)
+                .range(1e-12..=1e-1)
+                .prefix("tol: "),
+        );
+    });
+}
+
+#[derive(Debug, PartialEq)]
+enum AnalysisView {
+    PoleZero,
+    Nyquist,
+    Nichols,
+    Sigma,
+}
+
+// The models are single-input single-output, so the matrix has a single entry and its only
+// singular value is |H|
+fn siso_sigma_plot<S: TransferFunction<f64> + Clone>(
+    ui: &mut egui::Ui,
+    tf: &S,
+    settings: &BodeSettings,
+) {
+    match TransferFunctionMatrix::new(vec![vec![tf.clone()]]) {
+        Ok(tfm) => sigma_plot(ui, &tfm, settings),
+        Err(error) => {
+            ui.colored_label(Color32::RED, error.to_string());
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum TfInput {
     Continous,
@@ -258,6 +653,10 @@ fn tf_input(ui: &mut egui::Ui, app: &mut MainApp) {
         ui.selectable_value(&mut app.tf_input, TfInput::Filter, "Filter synthesis");
     });
 
+    if let Some(error) = &app.input_error {
+        ui.colored_label(Color32::RED, error);
+    }
+
     ui.separator();
 
     match app.tf_input {
@@ -271,6 +670,10 @@ fn tf_input(ui: &mut egui::Ui, app: &mut MainApp) {
             ui.separator();
             discrete_tf_input(ui, app);
             ui.separator();
+            ui.label("Transfer function equation:");
+            equation_options(ui, &mut app.equation_format, true);
+            ui.monospace(format_tf(&app.dtf, &app.equation_format));
+            ui.separator();
             ui.label("Numerator coefficients");
             ui.code(print_coeffs(app.dtf.numerator()));
             ui.label("Denominator coefficients");
@@ -285,13 +688,42 @@ fn tf_input(ui: &mut egui::Ui, app: &mut MainApp) {
 }
 
 fn continuous_tf_input(ui: &mut egui::Ui, app: &mut MainApp) {
+    ui.label("Transfer function expression:");
+    if ui
+        .add(
+            egui::TextEdit::singleline(&mut app.ctf_input_expression)
+                .font(egui::TextStyle::Monospace)
+                .hint_text("(s+1)/(s^2+2*s+5)")
+                .desired_width(f32::INFINITY),
+        )
+        .changed()
+    {
+        app.handle_ctf_expression_input();
+    }
+    if let Some(error) = &app.ctf_input_expression_error {
+        // Echo the expression with a marker under the offending character
+        if let ControlError::InvalidExpression { position, .. } = error {
+            ui.label(
+                RichText::new(format!(
+                    "{}\n{}^",
+                    app.ctf_input_expression,
+                    " ".repeat(*position)
+                ))
+                .monospace()
+                .color(Color32::RED),
+            );
+        }
+        ui.colored_label(Color32::RED, error.to_string());
+    }
+
+    ui.separator();
     ui.label("Transfer function order");
     ui.horizontal(|ui| {
         // --- Order Setter ---
         let mut order = app.ctf_input_order as u32;
         if ui
             .add(
-                egui::Slider::new(&mut order, 0..=20)
+                egui::Slider::new(&mut order, 0..=40)
                     .show_value(true)
                     .step_by(1.0),
             )
@@ -317,6 +749,7 @@ fn continuous_tf_input(ui: &mut egui::Ui, app: &mut MainApp) {
                     .changed()
                 {
                     app.handle_ctf_input();
+                    app.sync_ctf_expression();
                 };
             });
         }
@@ -335,15 +768,42 @@ fn continuous_tf_input(ui: &mut egui::Ui, app: &mut MainApp) {
                     .changed()
                 {
                     app.handle_ctf_input();
+                    app.sync_ctf_expression();
                 };
             });
         }
     });
 
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.label("Transport delay (s):");
+        if ui
+            .add(
+                egui::DragValue::new(&mut app.ctf_input_delay)
+                    .speed(1e-2)
+                    .range(0.0..=f64::MAX),
+            )
+            .changed()
+        {
+            app.handle_ctf_input();
+        }
+    });
+
     ui.separator();
     ui.label("Transfer function equation:");
-    let ctf_text = tf_text(app.ctf.numerator(), app.ctf.denominator());
-    ui.monospace(ctf_text);
+    equation_options(ui, &mut app.equation_format, false);
+    ui.monospace(format_tf(&app.ctf, &app.equation_format));
+}
+
+fn equation_options(ui: &mut egui::Ui, options: &mut FormatOptions, discrete: bool) {
+    ui.horizontal(|ui| {
+        ui.label("Significant digits");
+        ui.add(egui::DragValue::new(&mut options.significant_digits).range(1..=12));
+        ui.checkbox(&mut options.factored, "Pole-zero form");
+        if discrete {
+            ui.checkbox(&mut options.negative_powers, "Powers of z⁻¹");
+        }
+    });
 }
 
 fn discrete_tf_input(ui: &mut egui::Ui, app: &mut MainApp) {
@@ -358,12 +818,69 @@ fn discrete_tf_input(ui: &mut egui::Ui, app: &mut MainApp) {
         )
         .changed()
     {
-        app.handle_dtf_input();
+        app.handle_sample_time_input();
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Discretization method");
+        let previous = app.dtf_input_method;
+        ComboBox::from_id_salt("dtf_input_method_select")
+            .selected_text(discretization_method_name(app.dtf_input_method))
+            .show_ui(ui, |ui| {
+                for method in [
+                    DiscretizationMethod::Tustin,
+                    DiscretizationMethod::TustinPrewarped {
+                        omega_p: app.filter_input_cutoff,
+                    },
+                    DiscretizationMethod::ZeroOrderHold,
+                    DiscretizationMethod::FirstOrderHold,
+                    DiscretizationMethod::ImpulseInvariant,
+                    DiscretizationMethod::MatchedPoleZero,
+                    DiscretizationMethod::ForwardEuler,
+                    DiscretizationMethod::BackwardEuler,
+                ] {
+                    let selected = std::mem::discriminant(&app.dtf_input_method)
+                        == std::mem::discriminant(&method);
+                    if ui
+                        .selectable_label(selected, discretization_method_name(method))
+                        .clicked()
+                        && !selected
+                    {
+                        app.dtf_input_method = method;
+                    }
+                }
+            });
+        if let DiscretizationMethod::TustinPrewarped { omega_p } = &mut app.dtf_input_method {
+            ui.add(
+                egui::DragValue::new(omega_p)
+                    .speed(0.01)
+                    .range(1e-3..=1e6)
+                    .prefix("ω = ")
+                    .suffix(" rad/s"),
+            );
+        }
+        if app.dtf_input_method != previous {
+            app.handle_dtf_input();
+        }
+    });
+}
+
+fn discretization_method_name(method: DiscretizationMethod<f64>) -> &'static str {
+    match method {
+        DiscretizationMethod::Tustin => "Tustin",
+        DiscretizationMethod::TustinPrewarped { .. } => "Tustin (pre-warped)",
+        DiscretizationMethod::ZeroOrderHold => "Zero-order hold",
+        DiscretizationMethod::FirstOrderHold => "First-order hold",
+        DiscretizationMethod::ImpulseInvariant => "Impulse invariant",
+        DiscretizationMethod::MatchedPoleZero => "Matched pole-zero",
+        DiscretizationMethod::ForwardEuler => "Forward Euler",
+        DiscretizationMethod::BackwardEuler => "Backward Euler",
     }
 }
 
-#[derive(Debug, PartialEq)]
-enum FilterType {
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterType {
     Butterworth,
     Chebyshev,
     Elliptic,
@@ -396,7 +913,7 @@ fn filter_input(ui: &mut egui::Ui, app: &mut MainApp) {
     ui.horizontal(|ui| {
         ui.label("Order:");
         if ui
-            .add(egui::DragValue::new(&mut app.filter_input_order).range(1..=20))
+            .add(egui::DragValue::new(&mut app.filter_input_order).range(1..=40))
             .changed()
         {
             app.handle_filter_input();
@@ -423,4 +940,11 @@ fn filter_input(ui: &mut egui::Ui, app: &mut MainApp) {
             app.handle_filter_input();
         }
     });
+
+    ui.horizontal(|ui| {
+        ui.label("Pre-warp discretization at cutoff");
+        if ui.checkbox(&mut app.filter_input_prewarp, "").changed() {
+            app.handle_filter_input();
+        }
+    });
 }
@@ -1 +1,2 @@
 pub mod main_app;
+pub mod project;
@@ -0,0 +1,164 @@
+use std::{fmt, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use ctrlsys_toolbox_core::{
+    analysis::time::ResponseType,
+    tf::{ctf::ContinousTransferFunction, discretization::DiscretizationMethod},
+};
+
+use super::main_app::FilterType;
+
+/// Bumped whenever the project layout changes incompatibly
+pub const PROJECT_VERSION: u32 = 1;
+
+/// Everything needed to rebuild a design, saved as JSON
+/// The transfer function uses the core transfer function record, the discrete system is
+/// recomputed from it with the stored sample time and method
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub version: u32,
+    pub ctf: ContinousTransferFunction<f64>,
+    pub sample_time: f64,
+    pub discretization: DiscretizationMethod<f64>,
+    pub filter: FilterSettings,
+    pub response: ResponseSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterSettings {
+    pub filter_type: FilterType,
+    pub order: usize,
+    pub cutoff: f64,
+    pub normalize: bool,
+    pub prewarp: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ResponseSettings {
+    pub response_type: ResponseType,
+    pub length: f64,
+}
+
+#[derive(Debug)]
+pub enum ProjectError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectError::Io(error) => write!(f, "cannot access project file: {}", error),
+            ProjectError::Format(error) => write!(f, "invalid project file: {}", error),
+            ProjectError::UnsupportedVersion(version) => write!(
+                f,
+                "project version {} is not supported, this build reads versions 1 to {}",
+                version, PROJECT_VERSION
+            ),
+        }
+    }
+}
+
+// Only the version, read before the rest so that files from newer builds report their version
+// instead of whatever field they changed
+#[derive(Deserialize)]
+struct ProjectHeader {
+    version: u32,
+}
+
+pub fn read_project(path: &Path) -> Result<Project, ProjectError> {
+    let text = fs::read_to_string(path).map_err(ProjectError::Io)?;
+    let header: ProjectHeader = serde_json::from_str(&text).map_err(ProjectError::Format)?;
+    if !(1..=PROJECT_VERSION).contains(&header.version) {
+        return Err(ProjectError::UnsupportedVersion(header.version));
+    }
+    serde_json::from_str(&text).map_err(ProjectError::Format)
+}
+
+pub fn write_project(path: &Path, project: &Project) -> Result<(), ProjectError> {
+    let text = serde_json::to_string_pretty(project).map_err(ProjectError::Format)?;
+    fs::write(path, text).map_err(ProjectError::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ctrlsys_toolbox_core::tf::TransferFunction;
+
+    use super::*;
+
+    // Unique per test so that tests running in parallel do not share files
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ctrlsys_{}_{}.json", name, std::process::id()))
+    }
+
+    fn project() -> Project {
+        Project {
+            version: PROJECT_VERSION,
+            ctf: ContinousTransferFunction::from_numden(&[1.0, 2.0], &[1.0, 3.0, 5.0])
+                .unwrap()
+                .with_delay(0.25)
+                .unwrap(),
+            sample_time: 0.1,
+            discretization: DiscretizationMethod::Tustin,
+            filter: FilterSettings {
+                filter_type: FilterType::Butterworth,
+                order: 3,
+                cutoff: 2.0,
+                normalize: true,
+                prewarp: false,
+            },
+            response: ResponseSettings {
+                response_type: ResponseType::Step,
+                length: 10.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // Given
+        let path = temp_path("round_trip");
+        let project = project();
+
+        // When
+        write_project(&path, &project).unwrap();
+        let read = read_project(&path);
+        fs::remove_file(&path).unwrap();
+
+        // Then
+        let read = read.unwrap();
+        assert_eq!(PROJECT_VERSION, read.version);
+        assert_eq!(project.ctf.numerator(), read.ctf.numerator());
+        assert_eq!(project.ctf.denominator(), read.ctf.denominator());
+        assert_eq!(project.ctf.delay(), read.ctf.delay());
+        assert_eq!(project.sample_time, read.sample_time);
+        assert_eq!(project.discretization, read.discretization);
+        assert_eq!(project.filter.filter_type, read.filter.filter_type);
+        assert_eq!(project.filter.order, read.filter.order);
+        assert!(matches!(read.response.response_type, ResponseType::Step));
+        assert_eq!(project.response.length, read.response.length);
+    }
+
+    #[test]
+    fn test_newer_version_rejected() {
+        // Given
+        // A future layout that this build could not parse beyond its version
+        let path = temp_path("newer_version");
+        let text = format!(r#"{{"version": {}, "systems": []}}"#, PROJECT_VERSION + 1);
+        fs::write(&path, text).unwrap();
+
+        // When
+        let result = read_project(&path);
+        fs::remove_file(&path).unwrap();
+
+        // Then
+        assert!(matches!(
+            result,
+            Err(ProjectError::UnsupportedVersion(version)) if version == PROJECT_VERSION + 1
+        ));
+    }
+}
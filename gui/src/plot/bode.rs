@@ -1,48 +1,302 @@
-use eframe::egui;
+use std::f64::consts::PI;
+
+use eframe::egui::{self, Color32};
+use egui_plot::{GridInput, GridMark, LineStyle, PlotUi};
 
 use ctrlsys_toolbox_core::{
-    analysis::{frequency::bode_data, linspace},
-    tf::TransferFunction,
+    analysis::{
+        frequency::{Margins, margins, unwrapped_bode_data, unwrapped_phase},
+        logspace,
+    },
+    error::ControlError,
+    tf::{TimeDomain, TransferFunction},
 };
 
+pub type MarginsResult = Result<Margins<f64>, ControlError>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrequencyUnit {
+    RadPerSecond,
+    Hertz,
+}
+
+impl FrequencyUnit {
+    pub fn label(self) -> &'static str {
+        match self {
+            FrequencyUnit::RadPerSecond => "rad/s",
+            FrequencyUnit::Hertz => "Hz",
+        }
+    }
+
+    fn omega(self, frequency: f64) -> f64 {
+        match self {
+            FrequencyUnit::RadPerSecond => frequency,
+            FrequencyUnit::Hertz => 2.0 * PI * frequency,
+        }
+    }
+
+    pub(crate) fn frequency(self, omega: f64) -> f64 {
+        match self {
+            FrequencyUnit::RadPerSecond => omega,
+            FrequencyUnit::Hertz => omega / (2.0 * PI),
+        }
+    }
+}
+
+/// Frequency axis shared by the magnitude and phase plots, the range is given in decades of
+/// the selected unit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodeSettings {
+    pub start_decade: i32,
+    pub stop_decade: i32,
+    pub points: usize,
+    pub unit: FrequencyUnit,
+}
+
+impl Default for BodeSettings {
+    fn default() -> Self {
+        Self {
+            start_decade: -2,
+            stop_decade: 2,
+            points: 1000,
+            unit: FrequencyUnit::RadPerSecond,
+        }
+    }
+}
+
+impl BodeSettings {
+    /// Log-spaced sample frequencies in rad/s
+    pub fn omega_range(&self) -> Vec<f64> {
+        logspace(
+            self.start_decade as f64,
+            self.stop_decade as f64,
+            self.points,
+        )
+        .into_iter()
+        .map(|f| self.unit.omega(f))
+        .collect()
+    }
+}
+
+/// Margins of each system taken as an open loop, computed once per frame and shared by the
+/// summary and both Bode plots
+pub fn system_margins(tfs: &[&dyn TransferFunction<f64>]) -> Vec<MarginsResult> {
+    tfs.iter().map(|&tf| margins(tf)).collect()
+}
+
 pub fn bode_mag_plot(
     ui: &mut egui::Ui,
-    tf: &dyn TransferFunction<f64>,
-    w_start: f64,
-    w_stop: f64,
-    points: usize,
+    tfs: &[&dyn TransferFunction<f64>],
+    margins: &[MarginsResult],
+    settings: &BodeSettings,
 ) {
-    let freq_range: Vec<f64> = linspace(w_start, w_stop, points);
-    let bode_points = bode_data(tf, &freq_range);
-
-    let mag_points: egui_plot::PlotPoints = bode_points
-        .iter()
-        .map(|point| [point.omega, point.mag_db])
-        .collect();
-
     ui.label("Magnitude Response (dB)");
-    egui_plot::Plot::new("bode_mag").show(ui, |plot_ui| {
-        plot_ui.line(egui_plot::Line::new("H(s)", mag_points));
-    });
+    bode_plot(
+        ui,
+        "bode_mag",
+        &tfs.iter().copied().zip(margins).collect::<Vec<_>>(),
+        settings,
+        "dB",
+        |mag_db, _| mag_db,
+        |plot_ui, tf, margins| {
+            plot_ui.hline(egui_plot::HLine::new("0 dB", 0.0).color(Color32::GRAY));
+            if let (Some(w), Some(gm)) = (margins.phase_crossover, margins.gain_margin_db) {
+                let x = settings.unit.frequency(w).log10();
+                margin_marker(
+                    plot_ui,
+                    format!("GM {}", line_name(tf)),
+                    x,
+                    [-gm, 0.0],
+                    format!("GM {:.2} dB", gm),
+                );
+            }
+        },
+    );
 }
 
 pub fn bode_phase_plot(
     ui: &mut egui::Ui,
-    tf: &dyn TransferFunction<f64>,
-    w_start: f64,
-    w_stop: f64,
-    points: usize,
+    tfs: &[&dyn TransferFunction<f64>],
+    margins: &[MarginsResult],
+    settings: &BodeSettings,
 ) {
-    let freq_range: Vec<f64> = linspace(w_start, w_stop, points);
-    let bode_points = bode_data(tf, &freq_range);
-
-    let phase_points: egui_plot::PlotPoints = bode_points
-        .iter()
-        .map(|point| [point.omega, point.phase_rad])
-        .collect();
-
-    ui.label("Phase Response (rad)");
-    egui_plot::Plot::new("phase_mag").show(ui, |plot_ui| {
-        plot_ui.line(egui_plot::Line::new("H(s)", phase_points));
-    });
+    ui.label("Phase Response (deg)");
+    bode_plot(
+        ui,
+        "bode_phase",
+        &tfs.iter().copied().zip(margins).collect::<Vec<_>>(),
+        settings,
+        "°",
+        |_, phase_rad| phase_rad.to_degrees(),
+        |plot_ui, tf, margins| {
+            if let (Some(w), Some(pm)) = (margins.gain_crossover, margins.phase_margin_rad) {
+                // The unwrapped phase may sit a multiple of 360 degrees away from -180
+                let phase = unwrapped_phase(tf, &[w])[0].to_degrees();
+                let pm = pm.to_degrees();
+                plot_ui.hline(egui_plot::HLine::new("-180°", phase - pm).color(Color32::GRAY));
+                margin_marker(
+                    plot_ui,
+                    format!("PM {}", line_name(tf)),
+                    settings.unit.frequency(w).log10(),
+                    [phase - pm, phase],
+                    format!("PM {:.1}°", pm),
+                );
+            }
+        },
+    );
+}
+
+/// One line per system with the margins of `tfs` taken as open loops
+pub fn margins_summary(
+    ui: &mut egui::Ui,
+    tfs: &[&dyn TransferFunction<f64>],
+    margins: &[MarginsResult],
+    settings: &BodeSettings,
+) {
+    let unit = settings.unit;
+    let at = |w: Option<f64>| match w {
+        Some(w) => format!(" at {:.4} {}", unit.frequency(w), unit.label()),
+        None => String::new(),
+    };
+    for (&tf, margins) in tfs.iter().zip(margins) {
+        let text = match margins {
+            Ok(m) => format!(
+                "{}: GM {}{}, PM {}{}, DM {}, MM {:.3}",
+                line_name(tf),
+                m.gain_margin_db
+                    .map_or("inf".to_string(), |gm| format!("{:.2} dB", gm)),
+                at(m.phase_crossover),
+                m.phase_margin_rad
+                    .map_or("inf".to_string(), |pm| format!("{:.1}°", pm.to_degrees())),
+                at(m.gain_crossover),
+                m.delay_margin
+                    .map_or("inf".to_string(), |dm| format!("{:.4} s", dm)),
+                m.modulus_margin
+            ),
+            Err(error) => format!("{}: {}", line_name(tf), error),
+        };
+        ui.label(text);
+    }
+}
+
+// The x axis holds log10 of the frequency in the selected unit, egui_plot has no log axis
+fn bode_plot(
+    ui: &mut egui::Ui,
+    id: &str,
+    systems: &[(&dyn TransferFunction<f64>, &MarginsResult)],
+    settings: &BodeSettings,
+    y_unit: &'static str,
+    value: impl Fn(f64, f64) -> f64,
+    annotate: impl Fn(&mut PlotUi, &dyn TransferFunction<f64>, &Margins<f64>),
+) {
+    let unit = settings.unit;
+    let omega_range = settings.omega_range();
+
+    egui_plot::Plot::new(id)
+        .legend(egui_plot::Legend::default())
+        .link_axis("bode", [true, false])
+        .link_cursor("bode", [true, false])
+        .x_axis_label(format!("Frequency ({})", unit.label()))
+        .x_grid_spacer(decade_grid_spacer)
+        .x_axis_formatter(|mark, _| decade_label(mark.value))
+        .label_formatter(move |name, point| {
+            format!(
+                "{}\n{:.4} {}\n{:.2} {}",
+                name,
+                10f64.powf(point.x),
+                unit.label(),
+                point.y,
+                y_unit
+            )
+        })
+        .show(ui, |plot_ui| {
+            for &(tf, margins) in systems {
+                let nyquist = nyquist_omega(tf.time_domain());
+                let omega: Vec<f64> = omega_range
+                    .iter()
+                    .copied()
+                    .filter(|&w| nyquist.is_none_or(|nyquist| w <= nyquist))
+                    .collect();
+                let points: egui_plot::PlotPoints = unwrapped_bode_data(tf, &omega)
+                    .iter()
+                    .map(|point| {
+                        [
+                            unit.frequency(point.omega).log10(),
+                            value(point.mag_db, point.phase_rad),
+                        ]
+                    })
+                    .collect();
+                plot_ui.line(egui_plot::Line::new(line_name(tf), points));
+                if let Ok(margins) = margins {
+                    annotate(plot_ui, tf, margins);
+                }
+                if let Some(nyquist) = nyquist {
+                    plot_ui.vline(
+                        egui_plot::VLine::new("Nyquist", unit.frequency(nyquist).log10())
+                            .color(Color32::GRAY)
+                            .style(LineStyle::dashed_loose()),
+                    );
+                }
+            }
+        });
+}
+
+// Vertical segment between the curve and the critical level, labelled next to the curve
+fn margin_marker(plot_ui: &mut PlotUi, name: String, x: f64, y: [f64; 2], label: String) {
+    plot_ui.line(
+        egui_plot::Line::new(name, vec![[x, y[0]], [x, y[1]]])
+            .color(Color32::RED)
+            .width(2.0),
+    );
+    plot_ui.text(
+        egui_plot::Text::new("", egui_plot::PlotPoint::new(x, y[1]), label)
+            .anchor(egui::Align2::LEFT_BOTTOM),
+    );
+}
+
+/// Half the sampling rate in rad/s, `None` for continuous systems
+pub(crate) fn nyquist_omega(time_domain: TimeDomain<f64>) -> Option<f64> {
+    match time_domain {
+        TimeDomain::Continous => None,
+        TimeDomain::Discrete { sample_time } => Some(PI / sample_time),
+    }
+}
+
+// Major lines on every decade, minor lines at 2..9 times the decade
+pub(crate) fn decade_grid_spacer(input: GridInput) -> Vec<GridMark> {
+    let (min, max) = input.bounds;
+    let mut marks = Vec::new();
+    for decade in (min.floor() as i64)..=(max.ceil() as i64) {
+        let decade = decade as f64;
+        marks.push(GridMark {
+            value: decade,
+            step_size: 1.0,
+        });
+        if input.base_step_size < 0.05 {
+            marks.extend((2..10).map(|k| GridMark {
+                value: decade + (k as f64).log10(),
+                step_size: 0.1,
+            }));
+        }
+    }
+    marks.retain(|mark| (min..=max).contains(&mark.value));
+    marks
+}
+
+pub(crate) fn decade_label(log_frequency: f64) -> String {
+    let decade = log_frequency.round();
+    if (log_frequency - decade).abs() > 1e-9 {
+        return String::new();
+    }
+    match decade as i32 {
+        exponent @ -3..=3 => format!("{}", 10f64.powi(exponent)),
+        exponent => format!("1e{}", exponent),
+    }
+}
+
+fn line_name(tf: &dyn TransferFunction<f64>) -> &'static str {
+    match tf.time_domain() {
+        TimeDomain::Continous => "H(s)",
+        TimeDomain::Discrete { sample_time: _ } => "H(z)",
+    }
 }
@@ -1,4 +1,7 @@
 pub mod bode;
+pub mod nichols;
+pub mod nyquist;
 pub mod pz;
 pub mod text;
-pub mod response;
\ No newline at end of file
+pub mod response;
+pub mod sigma;
\ No newline at end of file
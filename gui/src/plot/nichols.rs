@@ -0,0 +1,149 @@
+use std::f64::consts::PI;
+
+use eframe::egui::{self, Color32};
+use egui_plot::{LineStyle, MarkerShape};
+
+use ctrlsys_toolbox_core::{
+    analysis::{frequency::nichols_data, logspace},
+    tf::{TimeDomain, TransferFunction},
+};
+
+// Closed-loop gain |L / (1 + L)| of the M contours
+const M_CONTOURS_DB: [f64; 8] = [-12.0, -6.0, -3.0, -1.0, 0.0, 1.0, 3.0, 6.0];
+// Closed-loop phase of the N contours, drawn together with their mirror images
+const N_CONTOURS_DEG: [f64; 7] = [-5.0, -15.0, -30.0, -60.0, -90.0, -120.0, -150.0];
+// Each copy of the grid is a few thousand points, zooming far out must not multiply them
+const MAX_GRID_COPIES: i32 = 8;
+
+pub fn nichols_plot(ui: &mut egui::Ui, tf: &dyn TransferFunction<f64>, omega: &[f64]) {
+    let omega: Vec<f64> = match tf.time_domain() {
+        TimeDomain::Continous => omega.to_vec(),
+        TimeDomain::Discrete { sample_time } => omega
+            .iter()
+            .copied()
+            .filter(|&w| w <= PI / sample_time)
+            .collect(),
+    };
+    let nichols_points = nichols_data(tf, &omega);
+    let (min_phase, max_phase) = nichols_points
+        .iter()
+        .fold((-360.0f64, 0.0f64), |(lo, hi), point| {
+            (lo.min(point.phase_deg), hi.max(point.phase_deg))
+        });
+
+    ui.label("Open-loop gain (dB) against phase (deg)");
+    egui_plot::Plot::new("nichols")
+        .legend(egui_plot::Legend::default())
+        .default_x_bounds(-360.0, 0.0)
+        .default_y_bounds(-40.0, 40.0)
+        .x_axis_label("Phase (deg)")
+        .y_axis_label("Magnitude (dB)")
+        .show(ui, |plot_ui| {
+            // The chart repeats every 360 degrees, draw the copies the curve spans as far as
+            // they are visible
+            let bounds = plot_ui.plot_bounds();
+            let first = (min_phase.max(bounds.min()[0]) / 360.0).floor() as i32;
+            let last = (max_phase.min(bounds.max()[0]) / 360.0).ceil() as i32;
+            for copy in first..last.min(first + MAX_GRID_COPIES) {
+                let shift = 360.0 * (copy + 1) as f64;
+                for m_db in M_CONTOURS_DB {
+                    let m = 10f64.powf(m_db / 20.0);
+                    let contour = (1..360).map(|k| closed_loop_to_open_loop(m, -(k as f64)));
+                    grid_line(
+                        plot_ui,
+                        "M contours",
+                        contour,
+                        shift,
+                        LineStyle::dashed_dense(),
+                    );
+                    let label = closed_loop_to_open_loop(m, -90.0);
+                    plot_ui.text(
+                        egui_plot::Text::new(
+                            "M contours",
+                            egui_plot::PlotPoint::new(label[0] + shift, label[1]),
+                            format!("{} dB", m_db),
+                        )
+                        .color(Color32::GRAY),
+                    );
+                }
+                for n_deg in N_CONTOURS_DEG {
+                    for theta in [n_deg, -360.0 - n_deg] {
+                        let contour = logspace(-2.0, 2.0, 400)
+                            .into_iter()
+                            .map(|m| closed_loop_to_open_loop(m, theta));
+                        grid_line(
+                            plot_ui,
+                            "N contours",
+                            contour,
+                            shift,
+                            LineStyle::dotted_dense(),
+                        );
+                    }
+                }
+                plot_ui.points(
+                    egui_plot::Points::new("-1", vec![[-180.0 + shift, 0.0]])
+                        .shape(MarkerShape::Plus)
+                        .radius(6.0)
+                        .color(Color32::RED),
+                );
+            }
+
+            let curve: egui_plot::PlotPoints = nichols_points
+                .iter()
+                .map(|point| [point.phase_deg, point.mag_db])
+                .collect();
+            plot_ui.line(egui_plot::Line::new(line_name(tf), curve).color(Color32::BLUE));
+        });
+}
+
+/// Open-loop [phase in (-360, 0] degrees, magnitude in dB] giving the closed loop
+/// T = m e^(j theta), from L = T / (1 - T)
+fn closed_loop_to_open_loop(m: f64, theta_deg: f64) -> [f64; 2] {
+    let (sin, cos) = theta_deg.to_radians().sin_cos();
+    let (re, im) = (m * cos, m * sin);
+    // Divides (re + j im) by (1 - re - j im)
+    let (den_re, den_im) = (1.0 - re, -im);
+    let den = den_re * den_re + den_im * den_im;
+    let l_re = (re * den_re + im * den_im) / den;
+    let l_im = (im * den_re - re * den_im) / den;
+    let mut phase = l_im.atan2(l_re).to_degrees();
+    if phase > 0.0 {
+        phase -= 360.0;
+    }
+    [phase, 10.0 * (l_re * l_re + l_im * l_im).log10()]
+}
+
+// Breaks the contour where its phase wraps between -360 and 0 degrees
+fn grid_line(
+    plot_ui: &mut egui_plot::PlotUi,
+    name: &str,
+    contour: impl Iterator<Item = [f64; 2]>,
+    shift: f64,
+    style: LineStyle,
+) {
+    let mut segment: Vec<[f64; 2]> = Vec::new();
+    let mut segments = Vec::new();
+    for point in contour.filter(|point| point.iter().all(|v| v.is_finite())) {
+        if let Some(last) = segment.last()
+            && (point[0] - last[0]).abs() > 180.0
+        {
+            segments.push(std::mem::take(&mut segment));
+        }
+        segment.push([point[0] + shift, point[1]]);
+    }
+    segments.push(segment);
+    for segment in segments {
+        plot_ui.line(
+            egui_plot::Line::new(name, segment)
+                .color(Color32::GRAY)
+                .style(style),
+        );
+    }
+}
+
+fn line_name(tf: &dyn TransferFunction<f64>) -> &'static str {
+    match tf.time_domain() {
+        TimeDomain::Continous => "L(s)",
+        TimeDomain::Discrete { sample_time: _ } => "L(z)",
+    }
+}
@@ -0,0 +1,83 @@
+use std::f64::consts::PI;
+
+use eframe::egui::{self, Color32};
+use egui_plot::{LineStyle, MarkerShape};
+
+use ctrlsys_toolbox_core::{
+    analysis::frequency::{NyquistPoint, nyquist_data},
+    tf::TransferFunction,
+};
+
+// Closed-loop gain |L / (1 + L)| of the M-circles
+const M_CIRCLES_DB: [f64; 6] = [-6.0, -3.0, -1.0, 1.0, 3.0, 6.0];
+
+pub fn nyquist_plot(ui: &mut egui::Ui, tf: &dyn TransferFunction<f64>, omega: &[f64]) {
+    let data = nyquist_data(tf, omega);
+    let (negative, positive): (Vec<&NyquistPoint<f64>>, Vec<_>) =
+        data.points.iter().partition(|point| point.omega < 0.0);
+
+    ui.label(format!(
+        "Encirclements of -1: {}, open-loop unstable poles: {}, closed-loop unstable poles: {}",
+        data.encirclements,
+        data.open_loop_unstable_poles,
+        data.closed_loop_unstable_poles()
+    ));
+    egui_plot::Plot::new("nyquist")
+        .legend(egui_plot::Legend::default())
+        .data_aspect(1.0)
+        .default_x_bounds(-3.0, 1.0)
+        .default_y_bounds(-2.0, 2.0)
+        .show(ui, |plot_ui| {
+            for m_db in M_CIRCLES_DB {
+                let (center, radius) = m_circle(m_db);
+                let circle: egui_plot::PlotPoints = (0..=180)
+                    .map(|k| {
+                        let phi = 2.0 * PI * k as f64 / 180.0;
+                        [center + radius * phi.cos(), radius * phi.sin()]
+                    })
+                    .collect();
+                plot_ui.line(
+                    egui_plot::Line::new("M-circles", circle)
+                        .color(Color32::GRAY)
+                        .style(LineStyle::dotted_dense()),
+                );
+                plot_ui.text(
+                    egui_plot::Text::new(
+                        "M-circles",
+                        egui_plot::PlotPoint::new(center, radius),
+                        format!("{} dB", m_db),
+                    )
+                    .color(Color32::GRAY),
+                );
+            }
+            // The 0 dB circle degenerates to the line Re(L) = -1/2
+            plot_ui.vline(
+                egui_plot::VLine::new("M-circles", -0.5)
+                    .color(Color32::GRAY)
+                    .style(LineStyle::dotted_dense()),
+            );
+
+            let positive: egui_plot::PlotPoints =
+                positive.iter().map(|point| [point.re, point.im]).collect();
+            let negative: egui_plot::PlotPoints =
+                negative.iter().map(|point| [point.re, point.im]).collect();
+            plot_ui.line(egui_plot::Line::new("L(jω), ω > 0", positive).color(Color32::BLUE));
+            plot_ui.line(
+                egui_plot::Line::new("L(jω), ω < 0", negative)
+                    .color(Color32::BLUE)
+                    .style(LineStyle::dashed_loose()),
+            );
+            plot_ui.points(
+                egui_plot::Points::new("-1", vec![[-1.0, 0.0]])
+                    .shape(MarkerShape::Plus)
+                    .radius(6.0)
+                    .color(Color32::RED),
+            );
+        });
+}
+
+/// Center on the real axis and radius of the contour |L / (1 + L)| = M
+fn m_circle(m_db: f64) -> (f64, f64) {
+    let m2 = 10f64.powf(m_db / 10.0);
+    (-m2 / (m2 - 1.0), (m2.sqrt() / (m2 - 1.0)).abs())
+}
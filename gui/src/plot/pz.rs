@@ -1,8 +1,8 @@
 use eframe::egui::{self, Color32};
 
-use ctrlsys_toolbox_core::tf::{TimeDomain, TransferFunction};
+use ctrlsys_toolbox_core::tf::{TimeDomain, TransferFunction, minreal::CancelledPair};
 
-pub fn pzplot(ui: &mut egui::Ui, tf: &dyn TransferFunction<f64>) {
+pub fn pzplot(ui: &mut egui::Ui, tf: &dyn TransferFunction<f64>, cancelled: &[CancelledPair<f64>]) {
     let poles = tf.poles();
     let zeroes = tf.zeroes();
 
@@ -20,16 +20,28 @@ pub fn pzplot(ui: &mut egui::Ui, tf: &dyn TransferFunction<f64>) {
         .radius(6.0)
         .shape(egui_plot::MarkerShape::Cross);
 
-    let zero_points = egui_plot::Points::new("poles", zero_coords)
+    let zero_points = egui_plot::Points::new("zeroes", zero_coords)
         .color(egui::Color32::CYAN)
         .radius(6.0)
         .shape(egui_plot::MarkerShape::Circle);
 
+    // Rings around both members of every pair that cancels
+    let cancelled_coords: Vec<[f64; 2]> = cancelled
+        .iter()
+        .flat_map(|pair| [[pair.pole.re, pair.pole.im], [pair.zero.re, pair.zero.im]])
+        .collect();
+    let cancelled_points = egui_plot::Points::new("cancelled", cancelled_coords)
+        .color(Color32::YELLOW)
+        .radius(11.0)
+        .filled(false)
+        .shape(egui_plot::MarkerShape::Circle);
+
     egui_plot::Plot::new("pz_plot")
         .data_aspect(1.0)
         .show(ui, |plot_ui| {
             plot_ui.points(pole_points);
             plot_ui.points(zero_points);
+            plot_ui.points(cancelled_points);
             if let TimeDomain::Discrete { sample_time: _ } = tf.time_domain() {
                 let num_points = 200;
                 let unit_circle_points: egui_plot::PlotPoints = (0..=num_points)
@@ -9,6 +9,7 @@ pub fn response_plot(ui: &mut egui::Ui, response: &mut dyn LTIResponse<f64>, t_e
     let response_points: Vec<[f64; 2]> = response
         .simulate(t_end)
         .iter()
+        .take(MAX_POINTS)
         .map(|point| [point.time, point.mag])
         .collect();
 
@@ -0,0 +1,58 @@
+use eframe::egui::{self, Color32};
+use egui_plot::LineStyle;
+
+use ctrlsys_toolbox_core::{
+    analysis::frequency::sigma_data,
+    tf::{TransferFunction, mimo::TransferFunctionMatrix},
+};
+
+use super::bode::{BodeSettings, decade_grid_spacer, decade_label, nyquist_omega};
+
+/// Singular values of the frequency response on the Bode frequency axis, one line per value
+pub fn sigma_plot<S: TransferFunction<f64>>(
+    ui: &mut egui::Ui,
+    tfm: &TransferFunctionMatrix<f64, S>,
+    settings: &BodeSettings,
+) {
+    let unit = settings.unit;
+    let nyquist = nyquist_omega(tfm.time_domain());
+    let omega: Vec<f64> = settings
+        .omega_range()
+        .into_iter()
+        .filter(|&w| nyquist.is_none_or(|nyquist| w <= nyquist))
+        .collect();
+    let sigma_points = sigma_data(tfm, &omega);
+    let count = tfm.inputs().min(tfm.outputs());
+
+    ui.label("Singular Values (dB)");
+    egui_plot::Plot::new("sigma")
+        .legend(egui_plot::Legend::default())
+        .x_axis_label(format!("Frequency ({})", unit.label()))
+        .x_grid_spacer(decade_grid_spacer)
+        .x_axis_formatter(|mark, _| decade_label(mark.value))
+        .label_formatter(move |name, point| {
+            format!(
+                "{}\n{:.4} {}\n{:.2} dB",
+                name,
+                10f64.powf(point.x),
+                unit.label(),
+                point.y
+            )
+        })
+        .show(ui, |plot_ui| {
+            for k in 0..count {
+                let line_points: egui_plot::PlotPoints = sigma_points
+                    .iter()
+                    .map(|point| [unit.frequency(point.omega).log10(), point.sigma_db[k]])
+                    .collect();
+                plot_ui.line(egui_plot::Line::new(format!("σ{}", k + 1), line_points));
+            }
+            if let Some(nyquist) = nyquist {
+                plot_ui.vline(
+                    egui_plot::VLine::new("Nyquist", unit.frequency(nyquist).log10())
+                        .color(Color32::GRAY)
+                        .style(LineStyle::dashed_loose()),
+                );
+            }
+        });
+}